@@ -1,25 +1,39 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
 use log::info;
 use once_cell::sync::OnceCell;
 use usermgmt_lib::cli::OptFilePath;
 use usermgmt_lib::prelude::*;
 
-use usermgmt_lib::ssh::SshKeyPair;
+use usermgmt_lib::ssh::{SshKeyPair, SshPublicKeySuggestion, UnknownHostKey};
 use usermgmt_lib::{
     config::MgmtConfig,
     prelude::{anyhow, AppResult},
     ssh::SshCredentials,
 };
 
-use crate::user_input;
+use crate::{cli_user_input, user_input};
+
+/// Serializes prompts of connections which are established in parallel
+static PROMPT: Mutex<()> = Mutex::new(());
+
+/// Holds [`PROMPT`] so that no other connection prompts in the terminal meanwhile.
+fn lock_prompt() -> MutexGuard<'static, ()> {
+    PROMPT.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
 #[derive(Debug, Clone)]
 /// Username are retrieved by a terminal prompt or by a default one if provided in the
 /// configuration.
-/// Password are retrieved by a terminal prompt.
+/// Password and the passphrase of an encrypted key are retrieved by separate terminal prompts.
+/// Clones share the entered values, so that the user is asked only once for all connections.
 pub struct CliSshCredential {
     default_ssh_user: String,
-    username: OnceCell<String>,
-    password: OnceCell<String>,
+    username: Arc<OnceCell<String>>,
+    password: Arc<OnceCell<String>>,
+    key_passphrase: Arc<OnceCell<Option<String>>>,
+    agent_key_choice: Arc<OnceCell<usize>>,
     ssh_key_path: Option<SshKeyPair>,
 }
 
@@ -29,10 +43,15 @@ impl CliSshCredential {
             .as_ref()
             .cloned()
             .or_else(|| config.ssh_key_path.clone())
-            .map(SshKeyPair::from_one_path);
+            .map(|path| {
+                SshKeyPair::from_one_path(path)
+                    .with_certificate(config.ssh_certificate_path.clone())
+            });
         Self {
             username: Default::default(),
             password: Default::default(),
+            key_passphrase: Default::default(),
+            agent_key_choice: Default::default(),
             default_ssh_user: config.default_ssh_user.clone(),
             ssh_key_path,
         }
@@ -47,6 +66,7 @@ impl SshCredentials for CliSshCredential {
     /// - If the terminal prompt fails. See [`user_input::ask_for_line_from_user_over_term`].
     fn username(&self) -> AppResult<&str> {
         let username = self.username.get_or_try_init(|| {
+            let _prompt = lock_prompt();
             user_input::ask_for_line_from_user_over_term(
                 "Enter your SSH username",
                 Some(self.default_ssh_user.as_str()),
@@ -63,45 +83,94 @@ impl SshCredentials for CliSshCredential {
     /// - If the terminal prompt fails. See [`user_input::cli_ask_for_password`].
     fn password(&self) -> AppResult<&str> {
         let password = self.password.get_or_try_init(|| {
-            let from_prompt = user_input::cli_ask_for_password("Enter your SSH password (leave empty and press enter to attempt key authentication): ")?;
+            let _prompt = lock_prompt();
+            let from_prompt = user_input::cli_ask_for_password("Enter your SSH password: ")?;
             Ok::<String, AppError>(from_prompt.unwrap_or_default())
         })?;
 
         Ok(password)
     }
 
+    /// Passphrase is provided by a terminal prompt and remembered for all further connections.
+    ///
+    /// # Errors
+    ///
+    /// - If the terminal prompt fails. See [`user_input::cli_ask_for_password`].
+    fn key_passphrase(&self, private_key: &Path) -> AppResult<Option<&str>> {
+        let passphrase = self.key_passphrase.get_or_try_init(|| {
+            let _prompt = lock_prompt();
+            user_input::cli_ask_for_password(&format!(
+                "Enter the passphrase of SSH key {:?}: ",
+                private_key
+            ))
+        })?;
+
+        Ok(passphrase.as_deref())
+    }
+
+    /// The choice is remembered for all further connections.
+    ///
     /// # Errors
     ///
     /// - If reading the user choice from the terminal fails. See [`user_input::line_input_from_user`].
     /// - If the user enters a selection index greater than the greatest selection index.
-    fn auth_agent_resolve(
-        &self,
-        many_keys: Vec<usermgmt_lib::ssh::SshPublicKeySuggestion>,
-    ) -> AppResult<usize> {
-        let length = many_keys.len();
-        let last_index = length.saturating_sub(1);
-        println!("Found more than one key in SSH agent!");
-        println!("Choose a key between {} and {}", 0, last_index);
-        println!("===========================================");
-
-        for (index, next) in many_keys.iter().enumerate() {
-            let comment = next.comment();
-            println!("{} => comment: {}", index, comment);
-        }
-
-        let user_choice: usize = user_input::line_input_from_user()?
-            .ok_or_else(|| anyhow!("No valid key choice provided"))?
-            .parse()?;
+    fn auth_agent_resolve(&self, many_keys: Vec<SshPublicKeySuggestion>) -> AppResult<usize> {
+        self.agent_key_choice
+            .get_or_try_init(|| {
+                let _prompt = lock_prompt();
+                ask_for_agent_key(many_keys)
+            })
+            .copied()
+    }
 
-        if last_index < user_choice {
-            Err(anyhow!("Choice should between {} and {}", 0, last_index))
-        } else {
-            info!("SSH key at index {} chosen", user_choice);
-            Ok(user_choice)
-        }
+    /// Shows the fingerprint of the unknown host key and asks in the terminal whether to trust it.
+    ///
+    /// # Errors
+    ///
+    /// - If reading the answer from the terminal fails. See [`user_input::line_input_from_user`].
+    fn confirm_unknown_host_key(&self, unknown: &UnknownHostKey) -> AppResult<bool> {
+        let _prompt = lock_prompt();
+        println!(
+            "The authenticity of host {} can not be established.",
+            unknown.host()
+        );
+        println!("Its key fingerprint is {}.", unknown.fingerprint());
+        cli_user_input::ask_cli_confirmation(&format!(
+            "Trust this key and add it to {:?}?",
+            unknown.known_hosts()
+        ))
     }
 
     fn ssh_paths_pair_key(&self) -> Option<&SshKeyPair> {
         self.ssh_key_path.as_ref()
     }
 }
+
+/// Asks in the terminal which of `many_keys` from the SSH agent to use.
+fn ask_for_agent_key(many_keys: Vec<SshPublicKeySuggestion>) -> AppResult<usize> {
+    let length = many_keys.len();
+    let last_index = length.saturating_sub(1);
+    println!("Found more than one key in SSH agent!");
+    println!("Choose a key between {} and {}", 0, last_index);
+    println!("===========================================");
+
+    for (index, next) in many_keys.iter().enumerate() {
+        println!(
+            "{} => comment: {}, fingerprint: {}",
+            index,
+            next.comment(),
+            next.fingerprint()
+        );
+    }
+
+    let user_choice: usize = user_input::line_input_from_user()?
+        .ok_or_else(|| anyhow!("No valid key choice provided"))?
+        .parse()?;
+
+    if last_index < user_choice {
+        Err(anyhow!("Choice should between {} and {}", 0, last_index))
+    } else {
+        info!("SSH key at index {} chosen", user_choice);
+        Ok(user_choice)
+    }
+}
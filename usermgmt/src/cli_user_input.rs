@@ -1,3 +1,4 @@
+use usermgmt_lib::operations::ActiveJobsDecision;
 use usermgmt_lib::prelude::{anyhow, AppResult, Context};
 
 use crate::user_input;
@@ -19,3 +20,29 @@ pub fn ask_cli_password() -> AppResult<String> {
         .ok_or_else(|| anyhow!("No password provided"))?;
     Ok(password)
 }
+
+/// Asks the user the yes/no question `question` in the terminal.
+/// Only "y" or "yes" in any casing counts as confirmation.
+pub fn ask_cli_confirmation(question: &str) -> AppResult<bool> {
+    println!("{} [y/N]:", question);
+    let answer = user_input::line_input_from_user()?.unwrap_or_default();
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Asks in the terminal what to do with the active jobs of the user `username` before deleting it.
+/// Anything else than cancelling or waiting aborts the deletion.
+pub fn ask_cli_active_jobs_decision(
+    username: &str,
+    number_of_jobs: usize,
+) -> AppResult<ActiveJobsDecision> {
+    println!(
+        "User {} has {} running or pending job(s). [c]ancel them, [w]ait for them or [A]bort:",
+        username, number_of_jobs
+    );
+    let answer = user_input::line_input_from_user()?.unwrap_or_default();
+    Ok(match answer.to_lowercase().as_str() {
+        "c" | "cancel" => ActiveJobsDecision::Cancel,
+        "w" | "wait" => ActiveJobsDecision::Wait,
+        _ => ActiveJobsDecision::Abort,
+    })
+}
@@ -5,10 +5,17 @@ use clap::Parser;
 use cli_ssh_credential::CliSshCredential;
 use ldap_cli_credential::LdapCliCredential;
 use log::error;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use usermgmt_lib::cli::{self, Commands, GeneralArgs, OnWhichSystem};
-use usermgmt_lib::config::{self};
-use usermgmt_lib::{operations, prelude::*, ChangesToUser, Entity};
+use usermgmt_lib::cli::{
+    self, AccountCommand, Commands, CoordinatorCommand, GeneralArgs, OnWhichSystem, QosCommand,
+    SlurmCommand,
+};
+use usermgmt_lib::config::{self, MgmtConfig};
+use usermgmt_lib::operations::ActiveJobsHandler;
+use usermgmt_lib::slurm::{QueuedJob, SlurmDump};
+use usermgmt_lib::ssh::SshConnection;
+use usermgmt_lib::{operations, prelude::*, slurm, ChangesToUser, Entity};
 
 mod cli_ssh_credential;
 mod cli_user_input;
@@ -53,7 +60,7 @@ pub fn run_mgmt(args: cli::GeneralArgs) -> AppResult {
             to_add,
             on_which_sys,
         } => {
-            let config = config::load_config(args.config_file)?.config;
+            let config = load_config(args.config_file, args.profile)?;
             let ldap_credential = LdapCliCredential::new(&config);
             let on_which_sys = &OnWhichSystem::from_config_for_all(&config, &on_which_sys);
             let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
@@ -66,11 +73,26 @@ pub fn run_mgmt(args: cli::GeneralArgs) -> AppResult {
             )?
         }
         Commands::Modify { data, on_which_sys } => {
-            let config = config::load_config(args.config_file)?.config;
+            let config = load_config(args.config_file, args.profile)?;
             let ldap_credential = LdapCliCredential::new(&config);
             let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
             let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
             let data = Entity::new_modifiable_conf(data, &config)?;
+            if !data.remove_accounts.is_empty() {
+                let accounts: Vec<String> = data
+                    .remove_accounts
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                confirm_on_production(
+                    &config,
+                    &format!(
+                        "Remove user {} from the accounts {}",
+                        data.username,
+                        accounts.join(", ")
+                    ),
+                )?;
+            }
             let data = ChangesToUser::try_new(data)?;
             operations::modify_user(
                 data,
@@ -80,25 +102,110 @@ pub fn run_mgmt(args: cli::GeneralArgs) -> AppResult {
                 cli_ssh_credential,
             )?
         }
-        Commands::Delete { user, on_which_sys } => {
-            let config = config::load_config(args.config_file)?.config;
+        Commands::Delete {
+            user,
+            on_which_sys,
+            force,
+        } => {
+            let config = load_config(args.config_file, args.profile)?;
             let ldap_credential = LdapCliCredential::new(&config);
             // let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
             let on_which_sys = &OnWhichSystem::from_config_for_all(&config, &on_which_sys);
+            confirm_on_production(&config, &format!("Delete user {}", user))?;
             let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
+            let ask_about_jobs = |jobs: &[QueuedJob]| {
+                println!("{}", slurm::jobs_to_pretty_table(jobs));
+                cli_user_input::ask_cli_active_jobs_decision(user.as_ref(), jobs.len())
+            };
             operations::delete_user(
                 user.as_ref(),
                 on_which_sys,
                 &config,
                 ldap_credential,
                 cli_ssh_credential,
+                (!force).then_some(&ask_about_jobs as ActiveJobsHandler),
             )?;
         }
+        Commands::Account { command, clusters } => {
+            let config = load_config(args.config_file, args.profile)?;
+            match &command {
+                AccountCommand::Delete { name, .. } => {
+                    confirm_on_production(&config, &format!("Delete account {}", name))?
+                }
+                AccountCommand::Coordinator {
+                    command: CoordinatorCommand::Remove { coordinator, .. },
+                } => confirm_on_production(
+                    &config,
+                    &format!(
+                        "Remove user {} as coordinator of account {}",
+                        coordinator.user, coordinator.account
+                    ),
+                )?,
+                _ => (),
+            }
+            let cli_ssh_credential = CliSshCredential::new(&config, command.ssh_path());
+            operations::manage_slurm_accounts(
+                command,
+                &clusters.clusters,
+                &config,
+                cli_ssh_credential,
+            )?
+        }
+        Commands::Slurm { command } => {
+            let config = load_config(args.config_file, args.profile)?;
+            let cli_ssh_credential = CliSshCredential::new(&config, command.ssh_path());
+            match command {
+                SlurmCommand::Backup { cluster, file, .. } => {
+                    let config = config.for_cluster_named(cluster.as_ref())?;
+                    let session = SshConnection::from_head_node(&config, cli_ssh_credential);
+                    slurm::backup_accounting(cluster.as_ref(), &file, &config, &session)?
+                }
+                SlurmCommand::Restore {
+                    file,
+                    clean,
+                    yes,
+                    dry_run,
+                    ..
+                } => {
+                    if !dry_run {
+                        let action = if clean {
+                            "Restore Slurm from a dump and delete all accounts and associations missing in it"
+                        } else {
+                            "Restore Slurm from a dump"
+                        };
+                        confirm_on_production(&config, action)?;
+                    }
+                    let to_restore = slurm::read_dump(&file)?;
+                    let config = config.for_cluster_named(&to_restore.cluster)?;
+                    let session = SshConnection::from_head_node(&config, cli_ssh_credential);
+                    restore_slurm(&config, &session, &file, &to_restore, clean, yes, dry_run)?
+                }
+            }
+        }
+        Commands::Qos { command, clusters } => {
+            let config = load_config(args.config_file, args.profile)?;
+            let cli_ssh_credential = CliSshCredential::new(&config, command.ssh_path());
+            match command {
+                QosCommand::Sync { yes, dry_run, .. } => {
+                    if !dry_run {
+                        confirm_on_production(&config, "Synchronize the QOS of Slurm")?;
+                    }
+                    operations::on_each_slurm_cluster(&config, &clusters.clusters, |config, _| {
+                        sync_qos(config, cli_ssh_credential.clone(), yes, dry_run)
+                    })?
+                }
+            }
+        }
+        Commands::Usage { args: usage } => {
+            let config = load_config(args.config_file, args.profile)?;
+            let cli_ssh_credential = CliSshCredential::new(&config, usage.ssh_path());
+            operations::print_usage(&usage, &config, cli_ssh_credential)?
+        }
         Commands::List {
             on_which_sys,
             simple_output_for_ldap,
         } => {
-            let config = config::load_config(args.config_file)?.config;
+            let config = load_config(args.config_file, args.profile)?;
             let ldap_credential = LdapCliCredential::new(&config);
             let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
             let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
@@ -114,3 +221,103 @@ pub fn run_mgmt(args: cli::GeneralArgs) -> AppResult {
 
     Ok(())
 }
+
+/// Shows the changes needed so that the QOS in Slurm match the configuration.
+/// The changes are applied afterwards if the user confirms them or `yes` is true.
+///
+/// # Errors
+///
+/// - If the QOS in Slurm could not be listed. See [`slurm::plan_qos_sync`]
+/// - If the changes could not be applied. See [`slurm::apply_qos_changes`]
+fn sync_qos(
+    config: &MgmtConfig,
+    ssh_credential: CliSshCredential,
+    yes: bool,
+    dry_run: bool,
+) -> AppResult {
+    let session = SshConnection::from_head_node(config, ssh_credential);
+    let changes = slurm::plan_qos_sync(config, &session)?;
+    if changes.is_empty() {
+        println!("QOS in Slurm match the configuration. Nothing to change.");
+        return Ok(());
+    }
+
+    for change in changes.iter() {
+        print!("{}", change);
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !yes && !cli_user_input::ask_cli_confirmation("Apply these changes to Slurm?")? {
+        println!("No changes were applied.");
+        return Ok(());
+    }
+    slurm::apply_qos_changes(&changes, config, &session)
+}
+
+/// Loads the configuration of the active profile or conf.toml and shows which one is used.
+///
+/// # Errors
+///
+/// - If the configuration could not be loaded. See [`config::load_config`]
+fn load_config(config_file: Option<PathBuf>, profile: Option<String>) -> AppResult<MgmtConfig> {
+    let loaded = config::load_config(config_file, config::active_profile(profile))?;
+    eprintln!("Configuration: {}", loaded.profile_label());
+    Ok(loaded.config)
+}
+
+/// Asks for confirmation before the destructive action `action` if the configuration is marked as production.
+///
+/// # Errors
+///
+/// - If the action was not confirmed.
+fn confirm_on_production(config: &MgmtConfig, action: &str) -> AppResult {
+    if config.production
+        && !cli_user_input::ask_cli_confirmation(&format!(
+            "The configuration is marked as production. {}?",
+            action
+        ))?
+    {
+        bail!("Aborted: {}", action);
+    }
+    Ok(())
+}
+
+/// Shows the differences of accounts and associations between Slurm and the dump `to_restore`
+/// read from `file` and loads the dump after confirmation.
+///
+/// # Errors
+///
+/// - If the differences could not be determined. See [`slurm::plan_restore`]
+/// - If the dump could not be loaded. See [`slurm::restore_accounting`]
+fn restore_slurm(
+    config: &MgmtConfig,
+    session: &SshConnection<CliSshCredential>,
+    file: &Path,
+    to_restore: &SlurmDump,
+    clean: bool,
+    yes: bool,
+    dry_run: bool,
+) -> AppResult {
+    let diff = slurm::plan_restore(to_restore, config, session)?;
+    if diff.is_empty() {
+        println!(
+            "Accounts and associations of cluster {} match the dump.",
+            to_restore.cluster
+        );
+    } else {
+        println!("Changes to cluster {}:", to_restore.cluster);
+        print!("{}", diff);
+        if !clean && (!diff.removed_accounts.is_empty() || !diff.removed_associations.is_empty()) {
+            println!("Removals (-) are only applied with --clean.");
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !yes && !cli_user_input::ask_cli_confirmation("Load this dump into Slurm?")? {
+        println!("Nothing was restored.");
+        return Ok(());
+    }
+    slurm::restore_accounting(file, clean, config, session)
+}
@@ -1,5 +1,5 @@
 use usermgmt_lib::{
-    cli::{CommonUserFields, UserToAdd},
+    cli::{self, CommonUserFields, UserToAdd},
     prelude::AppResult,
     util::TrimmedNonEmptyText,
 };
@@ -16,6 +16,9 @@ pub struct AddState {
     pub default_qos: String,
     pub publickey: String,
     pub qos: Vec<String>,
+    pub limits: Vec<String>,
+    pub accounts: Vec<String>,
+    pub default_account: String,
     pub adding_res_io: IoResourceManager<String>,
     pub last_added_username: String,
 }
@@ -42,6 +45,18 @@ impl AddState {
             lastname.try_into()?,
             username.try_into()?,
         );
+        let limits = self
+            .limits
+            .iter()
+            .filter(|limit| !limit.trim().is_empty())
+            .map(|limit| cli::slurm_limit(limit))
+            .collect::<AppResult<_>>()?;
+        let accounts = self
+            .accounts
+            .iter()
+            .filter(|account| !account.trim().is_empty())
+            .map(|account| cli::account_membership(account))
+            .collect::<AppResult<_>>()?;
 
         let user = UserToAdd::new(
             firstname,
@@ -53,6 +68,9 @@ impl AddState {
                 default_qos: some_if_not_blank_str(&self.default_qos),
                 publickey: some_if_not_blank_str(&self.publickey),
                 qos,
+                limits,
+                accounts,
+                default_account: some_if_not_blank_str(&self.default_account),
             },
         );
 
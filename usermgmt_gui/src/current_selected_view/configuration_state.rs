@@ -2,11 +2,28 @@ use std::path::PathBuf;
 
 use usermgmt_lib::config::LoadedMgmtConfig;
 
-use crate::{drawing::configuration::CacheForConfFields, io_resource_manager::IoResourceManager};
+use crate::{
+    drawing::configuration::CacheForConfFields,
+    io_resource_manager::{IoResourceManager, IoTaskStatus},
+};
 
 #[derive(Debug, Default)]
 pub struct ConfigurationState {
     pub gui_field_cache: CacheForConfFields,
     pub io_conf: IoResourceManager<LoadedMgmtConfig>,
     pub io_save_conf: IoResourceManager<PathBuf>,
+    /// Profile which is loaded instead of conf.toml if some
+    pub profile: Option<String>,
+    /// Names of the profiles next to the configuration file
+    pub profiles: Vec<String>,
+}
+
+impl ConfigurationState {
+    /// Returns true if the loaded configuration is marked as production.
+    pub fn is_production(&self) -> bool {
+        matches!(
+            self.io_conf.status(),
+            IoTaskStatus::Successful(LoadedMgmtConfig { config, .. }) if config.production
+        )
+    }
 }
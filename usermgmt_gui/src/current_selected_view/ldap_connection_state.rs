@@ -1,5 +1,11 @@
+use crate::general_utils::SharedLdapSession;
+
 #[derive(Debug, Default)]
 pub struct LdapConnectionState {
     pub username: Option<String>,
     pub password: Option<String>,
+    /// If true, the bound LDAP session is kept for the next operations instead of
+    /// binding again for every operation.
+    pub keep_session_alive: bool,
+    pub kept_session: SharedLdapSession,
 }
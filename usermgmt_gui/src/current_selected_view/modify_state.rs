@@ -1,5 +1,8 @@
 use usermgmt_lib::{
-    cli::CommonUserFields, config::MgmtConfig, util::TrimmedNonEmptyText, ChangesToUser, Entity,
+    cli::{self, CommonUserFields},
+    config::MgmtConfig,
+    util::TrimmedNonEmptyText,
+    ChangesToUser, Entity,
 };
 
 use crate::prelude::*;
@@ -14,8 +17,13 @@ pub struct ModifyState {
     pub default_qos: String,
     pub publickey: String,
     pub qos: Vec<String>,
+    pub limits: Vec<String>,
+    pub accounts: Vec<String>,
+    pub default_account: String,
     pub res_io: IoResourceManager<String>,
     pub last_added_username: String,
+    /// Modification was confirmed for a configuration marked as production
+    pub production_confirmed: bool,
 }
 
 impl ModifyState {
@@ -31,6 +39,19 @@ impl ModifyState {
             default_qos: general_utils::some_if_not_blank_str(&self.default_qos),
             publickey: general_utils::some_if_not_blank_str(&self.publickey),
             qos: self.qos.clone(),
+            limits: self
+                .limits
+                .iter()
+                .filter(|limit| !limit.trim().is_empty())
+                .map(|limit| cli::slurm_limit(limit))
+                .collect::<AppResult<_>>()?,
+            accounts: self
+                .accounts
+                .iter()
+                .filter(|account| !account.trim().is_empty())
+                .map(|account| cli::account_membership(account))
+                .collect::<AppResult<_>>()?,
+            default_account: general_utils::some_if_not_blank_str(&self.default_account),
         };
         let entity = Entity::new(firstname, lastname, common_fields, config)?;
         let changes = ChangesToUser::try_new(entity)?;
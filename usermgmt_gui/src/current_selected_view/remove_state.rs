@@ -5,4 +5,6 @@ pub struct RemoveState {
     pub username: String,
     pub remove_res_io: IoResourceManager<String>,
     pub last_username: String,
+    /// Deletion was confirmed for a configuration marked as production
+    pub production_confirmed: bool,
 }
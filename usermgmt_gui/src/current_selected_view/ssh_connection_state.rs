@@ -7,6 +7,8 @@ pub struct SshConnectionState {
     pub username: Option<String>,
     pub password: Option<String>,
     pub ssh_key_pair: Option<String>,
+    /// Passphrase of an encrypted private key, separate from the password
+    pub key_passphrase: Option<String>,
 }
 
 impl ConnectionState for SshConnectionState {
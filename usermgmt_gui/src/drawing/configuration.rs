@@ -48,6 +48,7 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
     );
 
     draw_utils::draw_file_path(ui, window);
+    draw_profile_selection(window, ui, can_reload);
     ui.separator();
     draw_buttons(window, ui, can_reload);
     ui.separator();
@@ -82,17 +83,54 @@ fn draw_buttons(window: &mut UsermgmtWindow, ui: &mut egui::Ui, can_reload: bool
             let loaded_conf = LoadedMgmtConfig {
                 path: Default::default(),
                 config: default,
+                profile: None,
             };
             window.conf_state.io_conf.set_success(loaded_conf);
         }
     });
 }
 
+/// Draws a dropdown of the profiles next to the configuration file.
+/// Choosing another profile loads it.
+fn draw_profile_selection(window: &mut UsermgmtWindow, ui: &mut egui::Ui, can_reload: bool) {
+    let texts = window.settings.texts();
+    let conf_state = &mut window.conf_state;
+    let selected_text = conf_state
+        .profile
+        .as_deref()
+        .unwrap_or(texts.profile_none())
+        .to_owned();
+    let mut selected = conf_state.profile.clone();
+    ui.add_enabled_ui(can_reload, |ui| {
+        egui::ComboBox::from_label(texts.profile())
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, texts.profile_none());
+                for profile in conf_state.profiles.iter() {
+                    ui.selectable_value(&mut selected, Some(profile.clone()), profile);
+                }
+            });
+    });
+    if selected != conf_state.profile {
+        info!(
+            "Changed profile from {:?} to {:?}",
+            conf_state.profile, selected
+        );
+        conf_state.profile = selected;
+        let path = window.conf_path.clone();
+        general_utils::start_load_config(conf_state, Some(path));
+    }
+}
+
 fn save_config(config_state: &mut ConfigurationState, conf_path: PathBuf) {
     if let IoTaskStatus::Successful(loaded) = config_state.io_conf.status() {
         let config = loaded.config.clone();
+        let profile_file = loaded.profile.is_some().then(|| loaded.file_path());
         config_state.io_save_conf.spawn_task(
-            move || config.save(&conf_path),
+            move || match profile_file {
+                Some(profile_file) => config.save_to_file(&profile_file),
+                None => config.save(&conf_path),
+            },
             String::from("Saving configuration"),
         );
     } else {
@@ -123,6 +161,19 @@ fn draw_fields(window: &mut ConfigurationState, settings: &Settings, ui: &mut eg
                                 &mut TextFieldEntry::new_opt(&label, val).with_tool_tip(tool_tip),
                             );
                         }
+                        ConfiField::SecretOpt {
+                            val,
+                            label,
+                            tool_tip,
+                        } => {
+                            draw_utils::entry_field(
+                                ui,
+                                settings,
+                                &mut TextFieldEntry::new_opt(&label, val)
+                                    .with_as_password()
+                                    .with_tool_tip(tool_tip),
+                            );
+                        }
                         ConfiField::Single {
                             val,
                             label,
@@ -243,11 +294,20 @@ fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<Con
             )
                 .into()
         };
+        (secret $field:ident) => {
+            ConfiField::SecretOpt {
+                val: &mut config.$field,
+                label: snake_to_label(stringify!($field), map.clone()),
+                tool_tip: None,
+            }
+        };
     }
 
     let mut fields: Vec<ConfiField> = vec![
         create_conf_field!(objectclass_common),
         create_conf_field!(ldap_add_created_at),
+        create_conf_field!(ldap_add_slurm_limits),
+        create_conf_field!(ldap_slurm_account_attribute),
         create_conf_field!(student_default_qos),
         create_conf_field!(staff_default_qos),
         create_conf_field!(default_ssh_user),
@@ -268,12 +328,13 @@ fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<Con
         create_conf_field!(ldap_readonly_bind),
         create_conf_field!(ldap_server),
         create_conf_field!(ldap_readonly_user),
-        create_conf_field!(ldap_readonly_pw),
+        create_conf_field!(secret ldap_readonly_pw),
         create_conf_field!(include_ldap),
         create_conf_field!(include_slurm),
         create_conf_field!(include_dir_mgmt),
         create_conf_field!(use_homedir_helper),
         create_conf_field!(run_slurm_remote),
+        create_conf_field!(production),
         create_conf_field!(ssh_agent),
         create_conf_field!(ssh_port),
         create_conf_field!(compute_node_root_dir),
@@ -291,7 +352,25 @@ fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<Con
         create_conf_field!(staff_gid),
         create_conf_field!(faculty_gid),
         create_conf_field!(sacctmgr_path),
+        create_conf_field!(mkdir_path),
+        create_conf_field!(chown_path),
+        create_conf_field!(rm_path),
+        create_conf_field!(setquota_path),
+        create_conf_field!(mkhomedir_helper_path),
         create_conf_field!(ssh_key_path),
+        create_conf_field!(ssh_certificate_path),
+        create_conf_field!(ssh_agent_key_fingerprint),
+        create_conf_field!(ssh_known_hosts_path),
+        create_conf_field!(ssh_strict_host_key_checking),
+        create_conf_field!(ssh_jump_host),
+        create_conf_field!(use_ssh_config),
+        create_conf_field!(ssh_config_path),
+        create_conf_field!(use_slurmrestd),
+        create_conf_field!(slurmrestd_url),
+        create_conf_field!(slurmrestd_api_version),
+        create_conf_field!(slurmrestd_user),
+        create_conf_field!(secret slurmrestd_jwt),
+        create_conf_field!(slurmrestd_cluster),
     ];
     fields.sort();
     fields
@@ -312,6 +391,12 @@ enum ConfiField<'a> {
         label: LabelTyp,
         tool_tip: Option<&'a str>,
     },
+    /// Like `SingleOpt` but the value is hidden like a password
+    SecretOpt {
+        val: &'a mut Option<String>,
+        label: LabelTyp,
+        tool_tip: Option<&'a str>,
+    },
     Single {
         val: &'a mut String,
         label: LabelTyp,
@@ -343,6 +428,7 @@ impl ConfiField<'_> {
     pub fn label(&self) -> &str {
         match self {
             ConfiField::SingleOpt { label, .. }
+            | ConfiField::SecretOpt { label, .. }
             | ConfiField::Single { label, .. }
             | ConfiField::List { label, .. }
             | ConfiField::Checkbox { label, .. }
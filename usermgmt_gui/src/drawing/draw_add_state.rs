@@ -62,6 +62,27 @@ pub fn draw(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
                 &mut adding_fields.qos,
                 &GroupDrawing::new(texts.qos()).add_tooltip(tooltips.qos()),
             );
+            draw_utils::list_view(
+                ui,
+                &window.settings,
+                &mut adding_fields.limits,
+                &GroupDrawing::new(texts.limits()).add_tooltip(tooltips.limits()),
+            );
+            draw_utils::entry_field(
+                ui,
+                settings,
+                &mut TextFieldEntry::new(
+                    texts.default_account(),
+                    &mut adding_fields.default_account,
+                )
+                .with_tooltip(tooltips.default_account()),
+            );
+            draw_utils::list_view(
+                ui,
+                &window.settings,
+                &mut adding_fields.accounts,
+                &GroupDrawing::new(texts.accounts()).add_tooltip(tooltips.accounts()),
+            );
         });
     }
 
@@ -103,12 +124,18 @@ pub fn draw(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
             let username = to_add.common_user_fields().username.to_string();
             let _ = adding_state.adding_res_io.spawn_task(
                 move || {
-                    operations::add_user(
-                        to_add,
-                        &prep.on_which_sys,
-                        &prep.config,
+                    general_utils::with_ldap_session(
                         prep.ldap_cred,
-                        prep.ssh_cred,
+                        prep.kept_ldap_session,
+                        |ldap_session| {
+                            operations::add_user_keep_ldap_session(
+                                to_add,
+                                &prep.on_which_sys,
+                                &prep.config,
+                                ldap_session,
+                                prep.ssh_cred,
+                            )
+                        },
                     )?;
                     Ok(username)
                 },
@@ -1,4 +1,4 @@
-use usermgmt_lib::operations;
+use usermgmt_lib::operations::{self, ActiveJobsDecision};
 
 use crate::prelude::*;
 
@@ -17,12 +17,19 @@ pub fn draw(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
                     .with_tooltip(tooltips.username()),
             );
         });
-        !remove_state.username.trim().is_empty()
+        let confirmed = draw_utils::confirm_on_production(
+            ui,
+            settings,
+            &window.conf_state,
+            &mut remove_state.production_confirmed,
+        );
+        confirmed && !remove_state.username.trim().is_empty()
     };
     draw_utils::draw_credentials(ui, window, false);
     ui.add_enabled_ui(allow_deletion, |ui| {
         let text = window.settings.texts();
         if ui.button(text.btn_action_remove()).clicked() {
+            window.remove_state.production_confirmed = false;
             delete_user(window)
         }
     });
@@ -52,12 +59,19 @@ fn delete_user(window: &mut UsermgmtWindow) {
         let username = window.remove_state.username.clone();
         let _ = window.remove_state.remove_res_io.spawn_task(
             move || {
-                operations::delete_user(
-                    &username,
-                    &prep.on_which_sys,
-                    &prep.config,
+                general_utils::with_ldap_session(
                     prep.ldap_cred,
-                    prep.ssh_cred,
+                    prep.kept_ldap_session,
+                    |ldap_session| {
+                        operations::delete_user_keep_ldap_session(
+                            &username,
+                            &prep.on_which_sys,
+                            &prep.config,
+                            ldap_session,
+                            prep.ssh_cred,
+                            Some(&|_| Ok(ActiveJobsDecision::Abort)),
+                        )
+                    },
                 )?;
                 Ok(username)
             },
@@ -4,8 +4,9 @@ use crate::current_selected_view::ConnectionState;
 use drawing::draw_utils::GroupDrawing;
 use egui_extras::{Size, StripBuilder};
 use usermgmt_lib::{
-    ldap::{list_ldap_users, LDAPConfig, LdapSearchResult, LdapSimpleCredential},
-    slurm::{self, ListedUser},
+    backend,
+    ldap::{list_ldap_users, LdapSearchResult, LdapSimpleCredential},
+    slurm::{SlurmAssociation, ASSOCIATION_FIELDS},
     ssh::{SshConnection, SshGivenCredential},
 };
 
@@ -54,7 +55,7 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
             (
                 || texts.listing_slurm_init().to_string(),
                 || texts.listing_slurm_loading().to_string(),
-                |_: &ListedUser| texts.listing_slurm_success().to_string(),
+                |_: &Vec<SlurmAssociation>| texts.listing_slurm_success().to_string(),
                 || texts.listing_slurm_failure().to_string(),
             ),
         );
@@ -93,19 +94,18 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
         }
     }
 
-    fn draw_slurm_table(ui: &mut egui::Ui, settings: &Settings, slurm_users: &ListedUser) {
+    fn draw_slurm_table(ui: &mut egui::Ui, settings: &Settings, slurm_users: &[SlurmAssociation]) {
         use egui_extras::{Column, TableBuilder};
         draw_table(ui, settings, slurm_users);
 
-        fn draw_table(ui: &mut egui::Ui, settings: &Settings, raw: &ListedUser) {
+        fn draw_table(ui: &mut egui::Ui, settings: &Settings, associations: &[SlurmAssociation]) {
             let mut table = TableBuilder::new(ui)
                 .striped(true)
                 .resizable(true)
                 .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
                 .min_scrolled_height(0.);
 
-            let headers = raw.headers();
-            let rows = raw.fields();
+            let headers = ASSOCIATION_FIELDS;
             table = table
                 .columns(Column::auto(), headers.len().saturating_sub(1))
                 .column(Column::remainder());
@@ -118,9 +118,9 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
                     }
                 })
                 .body(|mut body| {
-                    for single_row in rows {
+                    for association in associations {
                         body.row(10., |mut row| {
-                            for column in single_row {
+                            for column in association.cells() {
                                 row.col(|ui| _ = ui.label(column));
                             }
                         });
@@ -169,14 +169,14 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
                     window.ssh_state.ssh_key_pair(),
                     &mgmt_conf.config,
                 ),
-            );
+            )
+            .with_key_passphrase(window.ssh_state.key_passphrase.as_deref());
             let mgmt_conf = mgmt_conf.config.clone();
-            let failed_parsing_slurm = text.failed_parsing_slurm().clone();
             _ = window.listin_state.list_slurm_user_res.spawn_task(
                 move || {
                     let connection = SshConnection::from_head_node(&mgmt_conf, ssh_credentials);
-                    let slurm_users_raw = slurm::list_users(&mgmt_conf, &connection, true)?;
-                    ListedUser::new(&slurm_users_raw).ok_or(anyhow!(failed_parsing_slurm))
+                    let mut accounting = backend::accounting_from_config(&mgmt_conf, &connection)?;
+                    accounting.list_associations(&mgmt_conf)
                 },
                 String::from("Getting slurm user"),
             );
@@ -217,13 +217,17 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
                     listing_state.rw_pw.clone().unwrap_or_default(),
                 );
                 let mgmt_conf = mgmt_conf.config.clone();
+                let kept_ldap_session = window
+                    .ldap_state
+                    .keep_session_alive
+                    .then(|| window.ldap_state.kept_session.clone());
                 window.listin_state.list_ldap_res.spawn_task(
                     move || {
-                        let config = LDAPConfig::new(
-                            &mgmt_conf,
+                        general_utils::with_ldap_session(
                             LdapSimpleCredential::new(username, password),
-                        )?;
-                        list_ldap_users(&config)
+                            kept_ldap_session,
+                            |ldap_session| list_ldap_users(ldap_session.get_or_open(&mgmt_conf)?),
+                        )
                     },
                     "Listing ldap user".to_owned(),
                 );
@@ -10,7 +10,7 @@ use eframe::egui::{self, RichText};
 use num::{Bounded, FromPrimitive, Signed, ToPrimitive};
 
 use crate::{
-    current_selected_view::{LdapConnectionState, SshConnectionState},
+    current_selected_view::{ConfigurationState, LdapConnectionState, SshConnectionState},
     which_systems,
 };
 
@@ -62,6 +62,44 @@ pub fn list_view(
     });
 }
 
+/// Draws the name of the loaded configuration or profile as heading.
+/// The heading has the error color if the configuration is marked as production.
+pub fn draw_active_profile(
+    ui: &mut egui::Ui,
+    settings: &Settings,
+    conf_state: &ConfigurationState,
+) {
+    if let IoTaskStatus::Successful(loaded) = conf_state.io_conf.status() {
+        let text = format!(
+            "{}: {}",
+            settings.texts().active_profile(),
+            loaded.profile_label()
+        );
+        let text = if loaded.config.production {
+            RichText::new(text).color(settings.colors().err_msg())
+        } else {
+            RichText::new(text)
+        };
+        ui.heading(text);
+    }
+}
+
+/// Draws a checkbox to confirm a destructive action if the configuration is marked as production.
+/// Returns true if the action is confirmed or the configuration is not marked as production.
+pub fn confirm_on_production(
+    ui: &mut egui::Ui,
+    settings: &Settings,
+    conf_state: &ConfigurationState,
+    confirmed: &mut bool,
+) -> bool {
+    if conf_state.is_production() {
+        ui.checkbox(confirmed, settings.texts().confirm_production());
+        *confirmed
+    } else {
+        true
+    }
+}
+
 pub fn draw_file_path(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
     let conf_state = &window.conf_state;
     let mut path = window.conf_path_owned();
@@ -102,6 +140,15 @@ pub fn draw_ssh_credentials(
         username,
         password,
     );
+    entry_field(
+        ui,
+        settings,
+        &mut TextFieldEntry::new_opt(
+            settings.texts().ssh_key_passphrase(),
+            &mut ssh_state.key_passphrase,
+        )
+        .with_as_password(),
+    );
 }
 
 pub fn draw_ldap_credentials(
@@ -119,6 +166,18 @@ pub fn draw_ldap_credentials(
         username,
         password,
     );
+    ui.horizontal(|ui| {
+        let toggled = ui
+            .checkbox(
+                &mut ldap_state.keep_session_alive,
+                settings.texts().keep_ldap_session(),
+            )
+            .changed();
+        if toggled && !ldap_state.keep_session_alive {
+            general_utils::drop_kept_ldap_session(&ldap_state.kept_session);
+        }
+        tooltip_widget(ui, settings, settings.tooltiptexts().keep_ldap_session());
+    });
 }
 
 pub fn user_password_box(
@@ -343,5 +402,23 @@ fn status_msg<T, C>(
         };
         let text = RichText::new(raw_text).color(color).strong();
         ui.label(text);
+        if let IoTaskStatus::Failed(error) = status {
+            draw_trust_host_key(ui, settings, error);
+        }
     });
 }
+
+/// Offers to trust the key of a host if an action failed because the key is not known yet.
+/// The user retries the action afterwards.
+fn draw_trust_host_key(ui: &mut egui::Ui, settings: &Settings, error: &AppError) {
+    let unknown = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<usermgmt_lib::ssh::UnknownHostKey>());
+    if let Some(unknown) = unknown {
+        if ui.button(settings.texts().trust_host_key()).clicked() {
+            if let Err(error) = unknown.trust() {
+                error!("{:?}", error);
+            }
+        }
+    }
+}
@@ -9,9 +9,18 @@ pub fn draw(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
     draw_typing_fields(ui, &window.settings, &mut window.modify_state);
     draw_utils::draw_credentials(ui, window, false);
     ui.separator();
-    if ui.button("Modify User").clicked() {
-        handle_modify_req(window);
-    }
+    let confirmed = draw_utils::confirm_on_production(
+        ui,
+        &window.settings,
+        &window.conf_state,
+        &mut window.modify_state.production_confirmed,
+    );
+    ui.add_enabled_ui(confirmed, |ui| {
+        if ui.button("Modify User").clicked() {
+            window.modify_state.production_confirmed = false;
+            handle_modify_req(window);
+        }
+    });
     ui.separator();
     let last_username = &window.modify_state.last_added_username;
     let text = window.settings.texts();
@@ -38,6 +47,7 @@ fn handle_modify_req(window: &mut UsermgmtWindow) {
         ssh_cred,
         config,
         on_which_sys,
+        kept_ldap_session,
     }) = general_utils::prep_conf_creds(window, |app| &mut app.adding_state.adding_res_io, true)
     {
         match window.modify_state.create_changes_to_user(&config) {
@@ -45,12 +55,18 @@ fn handle_modify_req(window: &mut UsermgmtWindow) {
                 window.modify_state.res_io.spawn_task(
                     move || {
                         let username = changes.username.to_string();
-                        operations::modify_user(
-                            changes,
-                            &on_which_sys,
-                            &config,
+                        general_utils::with_ldap_session(
                             ldap_cred,
-                            ssh_cred,
+                            kept_ldap_session,
+                            |ldap_session| {
+                                operations::modify_user_keep_ldap_session(
+                                    changes,
+                                    &on_which_sys,
+                                    &config,
+                                    ldap_session,
+                                    ssh_cred,
+                                )
+                            },
                         )?;
                         Ok(username)
                     },
@@ -111,5 +127,23 @@ fn draw_typing_fields(ui: &mut egui::Ui, settings: &Settings, modify_state: &mut
             &mut modify_state.qos,
             &GroupDrawing::new(texts.qos()).add_tooltip(tooltips.qos()),
         );
+        draw_utils::list_view(
+            ui,
+            settings,
+            &mut modify_state.limits,
+            &GroupDrawing::new(texts.limits()).add_tooltip(tooltips.limits()),
+        );
+        draw_utils::entry_field(
+            ui,
+            settings,
+            &mut TextFieldEntry::new(texts.default_account(), &mut modify_state.default_account)
+                .with_tooltip(tooltips.default_account()),
+        );
+        draw_utils::list_view(
+            ui,
+            settings,
+            &mut modify_state.accounts,
+            &GroupDrawing::new(texts.accounts()).add_tooltip(tooltips.accounts()),
+        );
     });
 }
@@ -1,13 +1,14 @@
 use std::{
     fmt::{Debug, Display},
     path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, TryLockError},
 };
 
 use crate::{current_selected_view::ConfigurationState, prelude::*};
 use usermgmt_lib::{
     cli::OnWhichSystem,
     config::{self, LoadedMgmtConfig, MgmtConfig},
-    ldap::LdapSimpleCredential,
+    ldap::{LdapSimpleCredential, ReusableLdapSession},
     prelude::{anyhow, AppResult},
     ssh::SshGivenCredential,
     util::TrimmedNonEmptyText,
@@ -30,18 +31,89 @@ pub fn is_some_and_not_empty(input: Option<&str>) -> bool {
         .map(|input| some_if_not_blank_str(input).is_some())
         .unwrap_or(false)
 }
+/// Loads the configuration file or the selected profile next to it in the background.
+/// The list of available profiles is refreshed beforehand.
 pub fn start_load_config(conf_state: &mut ConfigurationState, path: Option<PathBuf>) {
+    conf_state.profiles = config::list_profiles(path.clone()).unwrap_or_else(|error| {
+        warn!("Could not list profiles. Details: {:?}", error);
+        Vec::new()
+    });
+    let profile = conf_state.profile.clone();
     conf_state.io_conf.spawn_task(
-        || config::load_config(path),
+        || config::load_config(path, profile),
         "Loading configuration".to_string(),
     );
 }
 
+/// LDAP session which is shared between the GUI and its IO background tasks.
+/// It stays alive across several operations if the user chose to keep it.
+pub type SharedLdapSession = Arc<Mutex<Option<ReusableLdapSession<LdapSimpleCredential>>>>;
+
 pub struct PreparationBeforeIoTask {
     pub ldap_cred: LdapSimpleCredential,
     pub ssh_cred: SshGivenCredential,
     pub config: MgmtConfig,
     pub on_which_sys: OnWhichSystem,
+    /// Is some if the LDAP session should be kept alive after the IO task.
+    pub kept_ldap_session: Option<SharedLdapSession>,
+}
+
+/// Performs `operation` with the kept LDAP session if there is one for the same credentials.
+/// Otherwise a new session is used which is only kept if the user chose to keep it alive.
+/// A kept session opened for another LDAP server, base DN or mode is replaced by
+/// [`ReusableLdapSession::get_or_open`] and [`ReusableLdapSession::get_or_open_readonly`].
+///
+/// # Errors
+///
+/// - If `operation` fails
+pub fn with_ldap_session<R>(
+    ldap_cred: LdapSimpleCredential,
+    kept_ldap_session: Option<SharedLdapSession>,
+    operation: impl FnOnce(&mut ReusableLdapSession<LdapSimpleCredential>) -> AppResult<R>,
+) -> AppResult<R> {
+    match kept_ldap_session {
+        Some(shared) => {
+            let mut kept = shared.lock().unwrap_or_else(PoisonError::into_inner);
+            let is_outdated = kept
+                .as_ref()
+                .map(|session| !session.has_same_credentials(&ldap_cred))
+                .unwrap_or(true);
+            if is_outdated {
+                debug!("GUI: opening a new LDAP session to be kept alive");
+                *kept = Some(ReusableLdapSession::new(ldap_cred));
+            }
+            let session = kept
+                .as_mut()
+                .expect("Session is set in the lines above if there was none");
+            operation(session)
+        }
+        None => operation(&mut ReusableLdapSession::new(ldap_cred)),
+    }
+}
+
+/// Closes the kept LDAP session.
+/// If an IO background task is still using the session, it is closed as soon as the task is done.
+pub fn drop_kept_ldap_session(kept_ldap_session: &SharedLdapSession) {
+    match kept_ldap_session.try_lock() {
+        Ok(mut kept) => {
+            info!("GUI: closing kept LDAP session");
+            *kept = None;
+        }
+        Err(TryLockError::Poisoned(poisoned)) => {
+            info!("GUI: closing kept LDAP session");
+            *poisoned.into_inner() = None;
+        }
+        Err(TryLockError::WouldBlock) => {
+            warn!("GUI: kept LDAP session is still in use. It is closed after the running task.");
+            let kept_ldap_session = Arc::clone(kept_ldap_session);
+            std::thread::spawn(move || {
+                *kept_ldap_session
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = None;
+                info!("GUI: closed kept LDAP session after the running task");
+            });
+        }
+    }
 }
 
 pub fn prep_conf_creds<T: Send + 'static>(
@@ -89,11 +161,16 @@ Details of error are embedded within respective io resource state.",
             } else {
                 Default::default()
             };
+            let kept_ldap_session = window
+                .ldap_state
+                .keep_session_alive
+                .then(|| window.ldap_state.kept_session.clone());
             return Ok(PreparationBeforeIoTask {
                 config,
                 on_which_sys,
                 ldap_cred,
                 ssh_cred,
+                kept_ldap_session,
             });
         }
         unreachable!("At this point, there should be a successfully loaded configuration");
@@ -64,6 +64,7 @@ pub struct Texts {
     conf_load_group: ReadonlyText,
     conf_save_group: ReadonlyText,
     ssh_cred: ReadonlyText,
+    ssh_key_passphrase: ReadonlyText,
     ldap_cred: ReadonlyText,
     dir_conf_path: ReadonlyText,
     general_status: ReadonlyText,
@@ -79,6 +80,9 @@ pub struct Texts {
     mail: ReadonlyText,
     qos: ReadonlyText,
     default_qos: ReadonlyText,
+    limits: ReadonlyText,
+    accounts: ReadonlyText,
+    default_account: ReadonlyText,
     public_key: ReadonlyText,
     group: ReadonlyText,
     btn_action_conf_load: ReadonlyText,
@@ -108,9 +112,14 @@ pub struct Texts {
     mode_ldap: ReadonlyText,
     mode_slurm: ReadonlyText,
     mode_directory: ReadonlyText,
-    failed_parsing_slurm: ReadonlyText,
     ldap_cred_missing: ReadonlyText,
     ssh_cred_missing: ReadonlyText,
+    keep_ldap_session: ReadonlyText,
+    profile: ReadonlyText,
+    profile_none: ReadonlyText,
+    active_profile: ReadonlyText,
+    confirm_production: ReadonlyText,
+    trust_host_key: ReadonlyText,
 }
 #[derive(Debug, Deserialize, Default, Getters)]
 #[getset(get = "pub")]
@@ -128,6 +137,9 @@ pub struct TextTooltip {
     group: String,
     default_qos: String,
     qos: String,
+    limits: String,
+    accounts: String,
+    default_account: String,
     conf_student_qos: String,
     conf_student_default_qos: String,
     conf_run_slurm_remote: String,
@@ -170,6 +182,7 @@ pub struct TextTooltip {
     conf_student_gid: String,
     conf_faculty_gid: String,
     conf_sacctmgr_path: String,
+    keep_ldap_session: String,
 }
 
 #[derive(Debug, Deserialize, Default, CopyGetters)]
@@ -46,7 +46,10 @@ pub struct UsermgmtWindow {
 
 impl Default for UsermgmtWindow {
     fn default() -> Self {
-        let mut conf_state: ConfigurationState = Default::default();
+        let mut conf_state = ConfigurationState {
+            profile: usermgmt_lib::config::active_profile(None),
+            ..Default::default()
+        };
 
         info!("Loading init data for gui.");
         let init = toml::from_str(include_str!("../../assets/Init.toml"))
@@ -110,7 +113,8 @@ impl UsermgmtWindow {
                 username?,
                 password.unwrap_or_default(),
                 usermgmt_lib::ssh::create_ssh_key_pair_conf(ssh_state.ssh_key_pair(), &conf.config),
-            );
+            )
+            .with_key_passphrase(ssh_state.key_passphrase.as_deref());
             Some(cred)
         } else {
             None
@@ -130,6 +134,7 @@ impl eframe::App for UsermgmtWindow {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
             query_io_tasks::query(self);
+            draw_utils::draw_active_profile(ui, &self.settings, &self.conf_state);
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
                     draw_utils::draw_box_group(
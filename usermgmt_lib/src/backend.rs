@@ -0,0 +1,232 @@
+//! Abstraction over the systems on which users are managed.
+//!
+//! - [`DirectoryBackend`]: Stores users with their attributes like LDAP.
+//! - [`AccountingBackend`]: Accounts resources of users like the Slurm database.
+//! - [`StorageBackend`]: Provides directories of users like home or NFS directories.
+//!
+//! Operations in [`crate::operations`] only talk to these traits.
+//! Besides the implementations for LDAP, sacctmgr, slurmrestd and directory management over SSH,
+//! there are in-memory implementations which allow to exercise operations without any remote system.
+
+mod in_memory;
+
+pub use in_memory::{
+    AccountedUser, DirectoryAttributes, InMemoryAccounting, InMemoryDirectory, InMemoryStorage,
+};
+
+use crate::{
+    config::MgmtConfig,
+    dir,
+    ldap::{self, LdapCredential, LdapSearchResult, LdapSession},
+    prelude::*,
+    slurm::{self, AccountCoordinators, SlurmAssociation, SlurmRestd},
+    ssh::{SshConnection, SshConnectionPool, SshCredentials},
+    ChangesToUser, NewEntity,
+};
+
+/// Stores users with their attributes, for example LDAP.
+pub trait DirectoryBackend {
+    fn add_user(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult;
+    fn delete_user(&mut self, username: &str, config: &MgmtConfig) -> AppResult;
+    fn modify_user(&mut self, modifiable: &ChangesToUser, config: &MgmtConfig) -> AppResult;
+    fn list_users(&mut self, config: &MgmtConfig) -> AppResult<LdapSearchResult>;
+}
+
+/// Accounts resources of users, for example the Slurm database.
+pub trait AccountingBackend {
+    fn add_user(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult;
+    fn delete_user(&mut self, username: &str, config: &MgmtConfig) -> AppResult;
+    fn modify_user(&mut self, modifiable: &ChangesToUser, config: &MgmtConfig) -> AppResult;
+    /// Returns the associations of all users and accounts.
+    fn list_associations(&mut self, config: &MgmtConfig) -> AppResult<Vec<SlurmAssociation>>;
+    /// Makes the user a coordinator who can manage the users of the account.
+    fn add_coordinator(&mut self, account: &str, username: &str, config: &MgmtConfig) -> AppResult;
+    fn remove_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        config: &MgmtConfig,
+    ) -> AppResult;
+    /// Returns all accounts which have coordinators.
+    fn list_coordinators(&mut self, config: &MgmtConfig) -> AppResult<Vec<AccountCoordinators>>;
+}
+
+/// Provides directories for users, for example home directories.
+pub trait StorageBackend {
+    fn add_user_directories(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult;
+    fn delete_user_directories(&mut self, username: &str, config: &MgmtConfig) -> AppResult;
+}
+
+/// Systems on which an operation is performed.
+/// A system is left out if its field is `None`.
+#[derive(Default)]
+pub struct Backends<'a> {
+    pub directory: Option<&'a mut dyn DirectoryBackend>,
+    pub accounting: Option<&'a mut dyn AccountingBackend>,
+    pub storage: Option<&'a mut dyn StorageBackend>,
+}
+
+impl<T> DirectoryBackend for LdapSession<T>
+where
+    T: LdapCredential,
+{
+    fn add_user(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult {
+        ldap::add_ldap_user(entity, config, self)
+    }
+
+    fn delete_user(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        ldap::delete_ldap_user(username, self)
+    }
+
+    fn modify_user(&mut self, modifiable: &ChangesToUser, config: &MgmtConfig) -> AppResult {
+        ldap::modify_ldap_user(modifiable, config, self)
+    }
+
+    fn list_users(&mut self, _config: &MgmtConfig) -> AppResult<LdapSearchResult> {
+        ldap::list_ldap_users(self)
+    }
+}
+
+/// Slurm database managed via sacctmgr.
+/// Whether sacctmgr is executed on the head node or locally depends on the configuration.
+pub struct SlurmAccounting<'s, 'c, C> {
+    ssh_connection: &'s SshConnection<'c, C>,
+}
+
+impl<'s, 'c, C> SlurmAccounting<'s, 'c, C> {
+    pub fn new(ssh_connection: &'s SshConnection<'c, C>) -> Self {
+        Self { ssh_connection }
+    }
+}
+
+impl<'s, 'c, C> AccountingBackend for SlurmAccounting<'s, 'c, C>
+where
+    C: SshCredentials,
+{
+    fn add_user(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult {
+        slurm::add_slurm_user(entity, config, self.ssh_connection)
+    }
+
+    fn delete_user(&mut self, username: &str, config: &MgmtConfig) -> AppResult {
+        slurm::delete_slurm_user(username, config, self.ssh_connection)
+    }
+
+    fn modify_user(&mut self, modifiable: &ChangesToUser, config: &MgmtConfig) -> AppResult {
+        slurm::modify_slurm_user(modifiable, config, self.ssh_connection)
+    }
+
+    fn list_associations(&mut self, config: &MgmtConfig) -> AppResult<Vec<SlurmAssociation>> {
+        slurm::list_associations(config, self.ssh_connection)
+    }
+
+    fn add_coordinator(&mut self, account: &str, username: &str, config: &MgmtConfig) -> AppResult {
+        slurm::add_coordinator(account, username, config, self.ssh_connection)
+    }
+
+    fn remove_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        config: &MgmtConfig,
+    ) -> AppResult {
+        slurm::remove_coordinator(account, username, config, self.ssh_connection)
+    }
+
+    fn list_coordinators(&mut self, config: &MgmtConfig) -> AppResult<Vec<AccountCoordinators>> {
+        slurm::list_coordinators(config, self.ssh_connection)
+    }
+}
+
+impl AccountingBackend for SlurmRestd {
+    fn add_user(&mut self, entity: &NewEntity, _config: &MgmtConfig) -> AppResult {
+        SlurmRestd::add_user(self, entity)
+    }
+
+    fn delete_user(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        SlurmRestd::delete_user(self, username)
+    }
+
+    fn modify_user(&mut self, modifiable: &ChangesToUser, _config: &MgmtConfig) -> AppResult {
+        SlurmRestd::modify_user(self, modifiable)
+    }
+
+    fn list_associations(&mut self, _config: &MgmtConfig) -> AppResult<Vec<SlurmAssociation>> {
+        SlurmRestd::list_associations(self)
+    }
+
+    fn add_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        _config: &MgmtConfig,
+    ) -> AppResult {
+        SlurmRestd::add_coordinator(self, account, username)
+    }
+
+    /// slurmrestd offers no endpoint to remove a coordinator.
+    ///
+    /// # Errors
+    ///
+    /// - Always
+    fn remove_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        _config: &MgmtConfig,
+    ) -> AppResult {
+        bail!(
+            "Coordinator {} of account {} can not be removed via slurmrestd. Set use_slurmrestd = false to remove it via sacctmgr.",
+            username,
+            account
+        )
+    }
+
+    fn list_coordinators(&mut self, _config: &MgmtConfig) -> AppResult<Vec<AccountCoordinators>> {
+        SlurmRestd::list_coordinators(self)
+    }
+}
+
+/// Returns the accounting backend selected by the field `use_slurmrestd` in the configuration.
+/// This is either slurmrestd or sacctmgr which may run over `ssh_connection`.
+///
+/// # Errors
+///
+/// - If slurmrestd is selected but not configured properly. See [`SlurmRestd::from_config`]
+pub fn accounting_from_config<'s, 'c, C>(
+    config: &MgmtConfig,
+    ssh_connection: &'s SshConnection<'c, C>,
+) -> AppResult<Box<dyn AccountingBackend + 's>>
+where
+    C: SshCredentials,
+{
+    if config.use_slurmrestd {
+        Ok(Box::new(SlurmRestd::from_config(config)?))
+    } else {
+        Ok(Box::new(SlurmAccounting::new(ssh_connection)))
+    }
+}
+
+/// Directories on compute nodes, NFS hosts and the home host managed over SSH.
+/// Connections are taken from `pool`, so that a pool can be shared by several operations in a batch.
+pub struct SshStorage<'p, 'c, C> {
+    pool: &'p SshConnectionPool<'c, C>,
+}
+
+impl<'p, 'c, C> SshStorage<'p, 'c, C> {
+    pub fn new(pool: &'p SshConnectionPool<'c, C>) -> Self {
+        Self { pool }
+    }
+}
+
+impl<'p, 'c, C> StorageBackend for SshStorage<'p, 'c, C>
+where
+    C: SshCredentials,
+{
+    fn add_user_directories(&mut self, entity: &NewEntity, _config: &MgmtConfig) -> AppResult {
+        dir::add_user_directories(entity, self.pool)
+    }
+
+    fn delete_user_directories(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        dir::delete_user_directories(username, self.pool)
+    }
+}
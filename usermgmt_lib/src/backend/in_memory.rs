@@ -0,0 +1,368 @@
+//! Implementations of the backend traits which keep all users in memory.
+//! They mimic the behaviour of the real systems closely enough to test operations on users.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use ldap3::Mod;
+use log::warn;
+
+use crate::{
+    config::MgmtConfig,
+    ldap::{self, LdapSearchResult},
+    prelude::*,
+    slurm::{AccountCoordinators, AccountMembership, SlurmAssociation, SlurmLimit},
+    util::get_new_uid,
+    ChangesToUser, NewEntity,
+};
+
+use super::{AccountingBackend, DirectoryBackend, StorageBackend};
+
+/// Attribute names with their values of one user in a directory
+pub type DirectoryAttributes = BTreeMap<String, BTreeSet<String>>;
+
+/// Directory which stores the same attributes for users as LDAP does.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryDirectory {
+    users: BTreeMap<String, DirectoryAttributes>,
+}
+
+impl InMemoryDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(&self, username: &str) -> Option<&DirectoryAttributes> {
+        self.users.get(username)
+    }
+
+    /// Returns all values of `attribute` for the user named `username`.
+    /// Returns an empty list if the user or attribute does not exist.
+    pub fn attribute(&self, username: &str, attribute: &str) -> Vec<&str> {
+        self.users
+            .get(username)
+            .and_then(|attributes| attributes.get(attribute))
+            .map(|values| values.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    fn user_mut(&mut self, username: &str) -> AppResult<&mut DirectoryAttributes> {
+        self.users
+            .get_mut(username)
+            .with_context(|| format!("No LDAP entry found for user {}", username))
+    }
+
+    fn all_uids(&self) -> AppResult<Vec<u32>> {
+        self.users
+            .values()
+            .flat_map(|attributes| attributes.get("uidNumber").into_iter().flatten())
+            .map(|uid| {
+                uid.parse::<u32>()
+                    .with_context(|| format!("Uid `{}` is not a valid number", uid))
+            })
+            .collect()
+    }
+}
+
+impl DirectoryBackend for InMemoryDirectory {
+    fn add_user(&mut self, entity: &NewEntity, config: &MgmtConfig) -> AppResult {
+        let username = entity.username.to_string();
+        if self.users.contains_key(&username) {
+            warn!(
+                "User {} already exists in LDAP. Skipping LDAP user creation.",
+                username
+            );
+            return Ok(());
+        }
+
+        let uid = get_new_uid(&self.all_uids()?, entity.group.id())?;
+        let attributes = ldap::new_user_fields(entity, uid, config)
+            .into_iter()
+            .map(|(key, values)| (key, values.into_iter().collect()))
+            .collect();
+        self.users.insert(username, attributes);
+        Ok(())
+    }
+
+    fn delete_user(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        if self.users.remove(username).is_none() {
+            warn!("No dn found to delete under the username {}", username);
+        }
+        Ok(())
+    }
+
+    fn modify_user(&mut self, modifiable: &ChangesToUser, config: &MgmtConfig) -> AppResult {
+        let user = self.user_mut(modifiable.username.as_ref())?;
+        let old_qos: Vec<String> = match &modifiable.qos {
+            Some(_) => user
+                .get("slurmQos")
+                .map(|qos| qos.iter().cloned().collect())
+                .unwrap_or_default(),
+            None => Vec::default(),
+        };
+        let old_limits: Option<Vec<String>> =
+            ldap::mirrors_slurm_limits(modifiable, config).then(|| {
+                user.get(ldap::SLURM_LIMIT)
+                    .map(|limits| limits.iter().cloned().collect())
+                    .unwrap_or_default()
+            });
+
+        let old_accounts: Option<Vec<String>> =
+            ldap::mirrored_account_attribute(modifiable, config).map(|attribute| {
+                user.get(attribute)
+                    .map(|accounts| accounts.iter().cloned().collect())
+                    .unwrap_or_default()
+            });
+
+        for modification in ldap::make_modification_vec(
+            modifiable,
+            config,
+            &old_qos,
+            old_limits.as_deref(),
+            old_accounts.as_deref(),
+        ) {
+            match modification {
+                Mod::Replace(attribute, values) => {
+                    user.insert(attribute, values.into_iter().collect());
+                }
+                Mod::Add(attribute, values) => user.entry(attribute).or_default().extend(values),
+                Mod::Delete(attribute, values) => {
+                    if let Some(present) = user.get_mut(&attribute) {
+                        for value in values {
+                            present.remove(&value);
+                        }
+                    }
+                }
+                Mod::Increment(attribute, _) => {
+                    bail!("Increment of attribute {} is not supported", attribute)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn list_users(&mut self, _config: &MgmtConfig) -> AppResult<LdapSearchResult> {
+        let rows = self.users.values().map(|attributes| {
+            attributes
+                .iter()
+                .filter(|(key, _)| ldap::SORTED_LDAP_LISTING_ATTRIBUTES.contains(&key.as_str()))
+                .map(|(key, values)| (key.clone(), values.iter().cloned().collect()))
+                .collect::<HashMap<String, Vec<String>>>()
+        });
+        Ok(LdapSearchResult::new(
+            ldap::SORTED_LDAP_LISTING_ATTRIBUTES.iter(),
+            rows,
+        ))
+    }
+}
+
+/// User with its associations in the accounting of resources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountedUser {
+    pub default_account: String,
+    /// One association per account and partition
+    pub accounts: Vec<AccountMembership>,
+    pub default_qos: String,
+    pub qos: Vec<String>,
+    /// Limits on the association keyed by their name like `MaxJobs`
+    pub limits: BTreeMap<String, String>,
+}
+
+/// Accounting which stores the same information about users as the Slurm database does.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryAccounting {
+    users: BTreeMap<String, AccountedUser>,
+    /// Coordinators keyed by the name of the account they coordinate
+    coordinators: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl InMemoryAccounting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(&self, username: &str) -> Option<&AccountedUser> {
+        self.users.get(username)
+    }
+
+    fn user_mut(&mut self, username: &str) -> AppResult<&mut AccountedUser> {
+        self.users
+            .get_mut(username)
+            .with_context(|| format!("No user {} in Slurm", username))
+    }
+}
+
+/// Sets the limits on the association of `user` like sacctmgr does. The value -1 removes a limit.
+fn set_limits(user: &mut AccountedUser, limits: impl Iterator<Item = SlurmLimit>) {
+    for limit in limits {
+        if limit.is_unset() {
+            user.limits.remove(limit.key());
+        } else {
+            user.limits
+                .insert(limit.key().to_owned(), limit.value().to_owned());
+        }
+    }
+}
+
+impl AccountingBackend for InMemoryAccounting {
+    fn add_user(&mut self, entity: &NewEntity, _config: &MgmtConfig) -> AppResult {
+        let username = entity.username.to_string();
+        if self.users.contains_key(&username) {
+            bail!("User {} already exists in Slurm", username);
+        }
+        let mut user = AccountedUser {
+            default_account: entity.default_account.to_string(),
+            accounts: entity.accounts.clone(),
+            default_qos: entity.default_qos.to_string(),
+            qos: (&entity.qos).into_iter().map(ToString::to_string).collect(),
+            limits: BTreeMap::new(),
+        };
+        set_limits(&mut user, entity.limits.iter().cloned());
+        self.users.insert(username, user);
+        Ok(())
+    }
+
+    fn delete_user(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        self.users
+            .remove(username)
+            .map(|_| ())
+            .with_context(|| format!("Failed to delete user {} from Slurm", username))
+    }
+
+    fn modify_user(&mut self, modifiable: &ChangesToUser, _config: &MgmtConfig) -> AppResult {
+        {
+            let user = self.user_mut(modifiable.username.as_ref())?;
+            for membership in modifiable.accounts.iter() {
+                if !user.accounts.contains(membership) {
+                    user.accounts.push(membership.clone());
+                }
+            }
+            if let Some(default_account) = &modifiable.default_account {
+                if !user
+                    .accounts
+                    .iter()
+                    .any(|membership| &membership.account == default_account)
+                {
+                    bail!(
+                        "User is not associated with the account {}",
+                        default_account
+                    );
+                }
+                user.default_account = default_account.to_string();
+            }
+            // Without a partition all associations with the account are removed like sacctmgr does.
+            user.accounts.retain(|membership| {
+                !modifiable.remove_accounts.iter().any(|removed| {
+                    removed.account == membership.account
+                        && (removed.partition.is_none()
+                            || removed.partition == membership.partition)
+                })
+            });
+        }
+        if let Some((qos, default_qos)) = modifiable.may_qos_and_default_qos() {
+            let user = self.user_mut(modifiable.username.as_ref())?;
+            user.qos = qos;
+            user.default_qos = default_qos;
+        }
+        if !modifiable.limits.is_empty() {
+            let user = self.user_mut(modifiable.username.as_ref())?;
+            set_limits(user, modifiable.limits.iter().cloned());
+        }
+        Ok(())
+    }
+
+    fn list_associations(&mut self, _config: &MgmtConfig) -> AppResult<Vec<SlurmAssociation>> {
+        let associations = self
+            .users
+            .iter()
+            .flat_map(|(username, user)| {
+                user.accounts.iter().map(|membership| SlurmAssociation {
+                    user: username.clone(),
+                    account: membership.account.to_string(),
+                    partition: membership.partition.as_ref().map(ToString::to_string),
+                    default_qos: user.default_qos.clone(),
+                    qos: user.qos.clone(),
+                    limits: user.limits.clone(),
+                })
+            })
+            .collect();
+        Ok(associations)
+    }
+
+    fn add_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        _config: &MgmtConfig,
+    ) -> AppResult {
+        self.user_mut(username)?;
+        self.coordinators
+            .entry(account.to_owned())
+            .or_default()
+            .insert(username.to_owned());
+        Ok(())
+    }
+
+    fn remove_coordinator(
+        &mut self,
+        account: &str,
+        username: &str,
+        _config: &MgmtConfig,
+    ) -> AppResult {
+        let removed = self
+            .coordinators
+            .get_mut(account)
+            .is_some_and(|coordinators| coordinators.remove(username));
+        if !removed {
+            bail!(
+                "User {} is no coordinator of account {} in Slurm",
+                username,
+                account
+            );
+        }
+        Ok(())
+    }
+
+    fn list_coordinators(&mut self, _config: &MgmtConfig) -> AppResult<Vec<AccountCoordinators>> {
+        let coordinators = self
+            .coordinators
+            .iter()
+            .filter(|(_, coordinators)| !coordinators.is_empty())
+            .map(|(account, coordinators)| AccountCoordinators {
+                account: account.clone(),
+                coordinators: coordinators.iter().cloned().collect(),
+            })
+            .collect();
+        Ok(coordinators)
+    }
+}
+
+/// Storage which only remembers for which users directories were created.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryStorage {
+    users_with_directories: BTreeSet<String>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_directories(&self, username: &str) -> bool {
+        self.users_with_directories.contains(username)
+    }
+}
+
+impl StorageBackend for InMemoryStorage {
+    fn add_user_directories(&mut self, entity: &NewEntity, _config: &MgmtConfig) -> AppResult {
+        self.users_with_directories
+            .insert(entity.username.to_string());
+        Ok(())
+    }
+
+    fn delete_user_directories(&mut self, username: &str, _config: &MgmtConfig) -> AppResult {
+        if !self.users_with_directories.remove(username) {
+            warn!("No directories to delete for user {}", username);
+        }
+        Ok(())
+    }
+}
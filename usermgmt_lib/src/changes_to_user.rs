@@ -1,4 +1,4 @@
-use crate::{prelude::*, Entity};
+use crate::{config::ClusterConfig, prelude::*, Entity};
 use derive_more::{AsRef, Deref};
 #[derive(Debug, AsRef, Deref)]
 pub struct ChangesToUser(Entity);
@@ -17,6 +17,27 @@ impl ChangesToUser {
         }
     }
 
+    /// Returns the changes with the accounts as they are named on `cluster`.
+    ///
+    /// # Errors
+    ///
+    /// - If an account is mapped to an invalid name. See [`ClusterConfig::map_account`]
+    pub fn for_cluster(&self, cluster: &ClusterConfig) -> AppResult<Self> {
+        let mut entity = self.0.clone();
+        for membership in entity
+            .accounts
+            .iter_mut()
+            .chain(&mut entity.remove_accounts)
+        {
+            *membership = cluster.map_membership(membership)?;
+        }
+        entity.default_account = entity
+            .default_account
+            .map(|account| cluster.map_account(&account))
+            .transpose()?;
+        Ok(Self(entity))
+    }
+
     /// # Returns Some
     ///
     /// Only if `qos` and `default qos` are to be changed together.
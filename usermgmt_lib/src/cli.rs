@@ -1,14 +1,26 @@
 use std::path::PathBuf;
 
 pub use on_which_system::{OnSlurmLdapOnlyCli, OnWhichSystem, OnWhichSystemCli, OptFilePath};
+pub use slurm_account::{
+    AccountCommand, ClustersCli, CoordinatorCommand, SlurmAccountChanges, SlurmAccountFields,
+    SlurmAccountToAdd, SlurmCoordinator, SshPathCli,
+};
+pub use slurm_backup::SlurmCommand;
+pub use slurm_qos::QosCommand;
+pub use slurm_usage::UsageArgs;
 
 mod on_which_system;
+mod slurm_account;
+mod slurm_backup;
+mod slurm_qos;
+mod slurm_usage;
 
 use clap::{Args, Parser, Subcommand};
 use const_format::concatcp;
 use derive_more::Into;
 
 use crate::prelude::*;
+use crate::slurm::{AccountMembership, SlurmLimit};
 use crate::util::TrimmedNonEmptyText;
 
 pub const fn short_about() -> &'static str {
@@ -41,6 +53,11 @@ pub struct GeneralArgs {
     /// Provide the path to a .toml configuration file.
     /// If absent, an attempt is made to locate the configuration file in various places on your system.
     pub config_file: Option<PathBuf>,
+    /// Name of the profile to use instead of conf.toml.
+    /// A profile is a complete configuration in profiles/<name>.toml next to conf.toml.
+    /// Defaults to the environment variable USERMGMT_PROFILE.
+    #[arg(long, verbatim_doc_comment)]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -70,6 +87,9 @@ pub enum Commands {
         user: TrimmedNonEmptyText,
         #[command(flatten)]
         on_which_sys: OnWhichSystemCli,
+        /// Delete the user without checking for running or pending jobs via squeue.
+        #[clap(long)]
+        force: bool,
     },
     /// List users in Slurm and/or LDAP
     #[clap(visible_alias = "l")]
@@ -80,6 +100,31 @@ pub enum Commands {
         #[clap(long, verbatim_doc_comment)]
         simple_output_for_ldap: Option<bool>,
     },
+    /// Add, delete, modify or list accounts (banks) in Slurm
+    Account {
+        #[clap(subcommand)]
+        command: AccountCommand,
+        #[command(flatten)]
+        clusters: ClustersCli,
+    },
+    /// Manage QOS in Slurm as defined in conf.toml
+    Qos {
+        #[clap(subcommand)]
+        command: QosCommand,
+        #[command(flatten)]
+        clusters: ClustersCli,
+    },
+    /// Report CPU and GPU hours used by users and accounts via sreport and sacct
+    Usage {
+        #[command(flatten)]
+        args: UsageArgs,
+    },
+    /// Back up or restore accounts, users and associations of Slurm via sacctmgr dump and load.
+    /// With [[clusters]] in conf.toml, the head node of the cluster named in the backup or dump is used.
+    Slurm {
+        #[clap(subcommand)]
+        command: SlurmCommand,
+    },
     #[clap(visible_alias = "gc")]
     /// Prints a default configuration (conf.toml) to stdout.
     GenerateConfig,
@@ -96,6 +141,11 @@ pub struct Modifiable {
     lastname: Option<TrimmedNonEmptyText>,
     #[command(flatten)]
     common_user_fields: CommonUserFields,
+    /// Slurm account whose association with the user is removed, given as account or account:partition.
+    /// Without a partition all associations of the user with the account are removed.
+    /// Can be given several times.
+    #[clap(long = "remove-account", value_parser = account_membership, verbatim_doc_comment)]
+    remove_accounts: Vec<AccountMembership>,
 }
 
 impl Modifiable {
@@ -104,6 +154,7 @@ impl Modifiable {
             firstname: Default::default(),
             lastname: Default::default(),
             common_user_fields: CommonUserFields::new(username),
+            remove_accounts: Default::default(),
         }
     }
 }
@@ -160,6 +211,20 @@ pub struct CommonUserFields {
     /// List of QoS assigned to the user (must be valid QoS i.e., they must exist in valid_qos of conf.toml). Arguments must be provided as a whitespace separated list (e.g. interactive basic).
     #[clap(short, long, num_args(0..=20))]
     pub qos: Vec<String>,
+    /// Slurm limit of the user given as key=value e.g. MaxJobs=10 or GrpTRES=cpu=8,gres/gpu=1.
+    /// Can be given several times. The value -1 removes a limit.
+    /// Known limits: MaxJobs, MaxSubmitJobs, MaxTRES, MaxWall, GrpJobs, GrpSubmitJobs, GrpTRES, GrpWall and Fairshare.
+    #[clap(long = "limit", value_parser = slurm_limit, verbatim_doc_comment)]
+    pub limits: Vec<SlurmLimit>,
+    /// Slurm account of the user given as account or account:partition e.g. proj1 or proj1:gpu.
+    /// Can be given several times. If absent for a new user, the group is the only account.
+    /// When modifying a user, associations with the given accounts are added.
+    #[clap(long = "account", value_parser = account_membership, verbatim_doc_comment)]
+    pub accounts: Vec<AccountMembership>,
+    /// Default Slurm account of the user.
+    /// For a new user it must be one of the accounts given and is the first of them if absent.
+    #[clap(long, value_parser = trimmed_non_empty, verbatim_doc_comment)]
+    pub default_account: Option<TrimmedNonEmptyText>,
 }
 
 impl CommonUserFields {
@@ -171,6 +236,9 @@ impl CommonUserFields {
             default_qos: Default::default(),
             publickey: Default::default(),
             qos: Default::default(),
+            limits: Default::default(),
+            accounts: Default::default(),
+            default_account: Default::default(),
         }
     }
 }
@@ -181,3 +249,15 @@ pub fn trimmed_non_empty(s: &str) -> AppResult<TrimmedNonEmptyText> {
     let to_validate = TrimmedNonEmptyText::try_from(s)?;
     Ok(to_validate)
 }
+
+/// Used by argument parser to ensure that
+/// the argument is a known Slurm limit with a valid value given as key=value
+pub fn slurm_limit(s: &str) -> AppResult<SlurmLimit> {
+    s.parse()
+}
+
+/// Used by argument parser to ensure that
+/// the argument is an account optionally followed by a partition like account:partition
+pub fn account_membership(s: &str) -> AppResult<AccountMembership> {
+    s.parse()
+}
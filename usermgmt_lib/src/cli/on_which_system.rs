@@ -36,12 +36,17 @@ pub struct OnSlurmLdapOnlyCli {
     #[arg(long, verbatim_doc_comment)]
     #[getset(get = "pub")]
     ssh_path: Option<PathBuf>,
+    /// Name of a cluster within [[clusters]] of conf.toml on which Slurm is managed.
+    /// Can be given several times. Defaults to all clusters.
+    #[arg(long = "cluster", verbatim_doc_comment)]
+    #[getset(get = "pub")]
+    clusters: Vec<String>,
 }
 
 /// Toggle the systems (Slurm, LDAP, Directories) that will be affected by actions such as user creation.
 /// Ensures flexibility for user to toggle systems via CLI and options from configuration file.
 /// CLI options have priority over default values from configuration file.
-#[derive(CopyGetters, Getters, Debug)]
+#[derive(CopyGetters, Getters, Debug, Clone)]
 pub struct OnWhichSystem {
     #[getset(get_copy = "pub")]
     slurm: bool,
@@ -51,6 +56,9 @@ pub struct OnWhichSystem {
     dirs: bool,
     #[getset(get = "pub")]
     ssh_path: OptFilePath,
+    /// Names of the selected clusters of the configuration. Empty for all clusters.
+    #[getset(get = "pub")]
+    clusters: Vec<String>,
 }
 
 impl OnWhichSystem {
@@ -60,9 +68,21 @@ impl OnWhichSystem {
             ldap,
             dirs,
             ssh_path,
+            clusters: Vec::new(),
         }
     }
 
+    /// Restricts Slurm to the clusters of the configuration with the names in `clusters`.
+    pub fn with_clusters(mut self, clusters: Vec<String>) -> Self {
+        self.clusters = clusters;
+        self
+    }
+
+    /// Only Slurm is affected, for example by operations on accounts.
+    pub fn slurm_only(ssh_path: OptFilePath) -> Self {
+        Self::new(true, false, false, ssh_path)
+    }
+
     pub fn from_config_for_all(config: &MgmtConfig, from_cli: &OnWhichSystemCli) -> Self {
         let mut slurm_ldap = Self::from_config_for_slurm_ldap(config, &from_cli.ldap_slurm);
         slurm_ldap.dirs = Self::use_cli_over_config(from_cli.dirs(), config.include_dir_mgmt);
@@ -79,6 +99,7 @@ impl OnWhichSystem {
                 .as_ref()
                 .cloned()
                 .or_else(|| config.ssh_key_path.clone()),
+            clusters: from_cli.clusters().clone(),
         }
     }
 
@@ -108,6 +129,7 @@ mod testing {
                 ldap: Some(true),
                 slurm: Some(true),
                 ssh_path: None,
+                clusters: Vec::new(),
             },
         );
 
@@ -125,6 +147,7 @@ mod testing {
                     ldap: None,
                     slurm: None,
                     ssh_path: None,
+                    clusters: Vec::new(),
                 },
                 dirs: None,
             },
@@ -138,6 +161,7 @@ mod testing {
                     ldap: None,
                     slurm: Some(false),
                     ssh_path: None,
+                    clusters: Vec::new(),
                 },
                 dirs: None,
             },
@@ -151,6 +175,7 @@ mod testing {
                     ldap: Some(false),
                     slurm: Some(false),
                     ssh_path: None,
+                    clusters: Vec::new(),
                 },
                 dirs: None,
             },
@@ -167,6 +192,7 @@ mod testing {
                     ldap: None,
                     slurm: None,
                     ssh_path: None,
+                    clusters: Vec::new(),
                 },
                 dirs: None,
             },
@@ -184,6 +210,7 @@ mod testing {
                     ldap: Some(false),
                     slurm: Some(false),
                     ssh_path: None,
+                    clusters: Vec::new(),
                 },
                 dirs: Some(true),
             },
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+use crate::util::TrimmedNonEmptyText;
+
+use super::{trimmed_non_empty, OptFilePath};
+
+/// CLI sub commands for operations on accounts (banks) in the Slurm database
+#[derive(Subcommand, Debug)]
+pub enum AccountCommand {
+    /// Add an account to Slurm
+    #[clap(visible_alias = "a")]
+    Add {
+        #[command(flatten)]
+        to_add: SlurmAccountToAdd,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Delete an account from Slurm
+    #[clap(visible_alias = "d")]
+    Delete {
+        /// Name of the account e.g. staff.
+        #[clap(value_parser = trimmed_non_empty)]
+        name: TrimmedNonEmptyText,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Modify an account in Slurm
+    #[clap(visible_alias = "m")]
+    Modify {
+        #[command(flatten)]
+        changes: SlurmAccountChanges,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// List accounts in Slurm
+    #[clap(visible_alias = "l")]
+    List {
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Add an account for every group in valid_slurm_groups of conf.toml which does not exist in Slurm yet
+    Bootstrap {
+        /// Parent account of the added accounts.
+        /// Slurm uses the account root as parent if absent.
+        #[clap(long, value_parser = trimmed_non_empty, verbatim_doc_comment)]
+        parent: Option<TrimmedNonEmptyText>,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Manage coordinators of accounts who can manage the users of their accounts
+    #[clap(visible_alias = "c")]
+    Coordinator {
+        #[command(subcommand)]
+        command: CoordinatorCommand,
+    },
+}
+
+impl AccountCommand {
+    pub fn ssh_path(&self) -> &OptFilePath {
+        let ssh = match self {
+            AccountCommand::Add { ssh, .. }
+            | AccountCommand::Delete { ssh, .. }
+            | AccountCommand::Modify { ssh, .. }
+            | AccountCommand::List { ssh }
+            | AccountCommand::Bootstrap { ssh, .. } => ssh,
+            AccountCommand::Coordinator { command } => return command.ssh_path(),
+        };
+        &ssh.ssh_path
+    }
+}
+
+/// CLI sub commands for coordinators of accounts in the Slurm database
+#[derive(Subcommand, Debug)]
+pub enum CoordinatorCommand {
+    /// Make a user a coordinator of an account
+    #[clap(visible_alias = "a")]
+    Add {
+        #[command(flatten)]
+        coordinator: SlurmCoordinator,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Remove a user as coordinator of an account
+    #[clap(visible_alias = "r")]
+    Remove {
+        #[command(flatten)]
+        coordinator: SlurmCoordinator,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// List the coordinators of all accounts
+    #[clap(visible_alias = "l")]
+    List {
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+}
+
+impl CoordinatorCommand {
+    pub fn ssh_path(&self) -> &OptFilePath {
+        let ssh = match self {
+            CoordinatorCommand::Add { ssh, .. }
+            | CoordinatorCommand::Remove { ssh, .. }
+            | CoordinatorCommand::List { ssh } => ssh,
+        };
+        &ssh.ssh_path
+    }
+}
+
+/// User who coordinates an account
+#[derive(Args, Debug, Clone)]
+pub struct SlurmCoordinator {
+    /// Name of the account e.g. proj1.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub account: TrimmedNonEmptyText,
+    /// Username of the coordinator e.g. jane.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub user: TrimmedNonEmptyText,
+}
+
+/// Path to the key pair for the SSH connection to the head node
+#[derive(Args, Debug, Default)]
+pub struct SshPathCli {
+    /// Path to key pair used to establish the SSH connection.
+    /// Has priority over `ssh_key_path` provided in conf.toml.
+    #[arg(long, verbatim_doc_comment)]
+    pub ssh_path: Option<PathBuf>,
+}
+
+/// Clusters on which an operation only on Slurm is performed
+#[derive(Args, Debug, Default)]
+pub struct ClustersCli {
+    /// Name of a cluster within [[clusters]] of conf.toml on which Slurm is managed.
+    /// Can be given several times. Defaults to all clusters.
+    #[arg(long = "cluster", global = true, verbatim_doc_comment)]
+    pub clusters: Vec<String>,
+}
+
+/// Defines options for adding an account
+#[derive(Args, Debug, Clone)]
+pub struct SlurmAccountToAdd {
+    /// Name of the account e.g. staff.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub name: TrimmedNonEmptyText,
+    #[command(flatten)]
+    pub fields: SlurmAccountFields,
+}
+
+impl SlurmAccountToAdd {
+    pub fn new(name: TrimmedNonEmptyText) -> Self {
+        Self {
+            name,
+            fields: Default::default(),
+        }
+    }
+}
+
+/// Defines options for modifying an account
+#[derive(Args, Debug, Clone)]
+pub struct SlurmAccountChanges {
+    /// Name of the account to modify e.g. staff.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub name: TrimmedNonEmptyText,
+    #[command(flatten)]
+    pub fields: SlurmAccountFields,
+}
+
+/// Attributes of an account. Absent attributes are not set.
+#[derive(Args, Debug, Clone, Default)]
+pub struct SlurmAccountFields {
+    /// Parent account in the account tree e.g. root.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub parent: Option<TrimmedNonEmptyText>,
+    /// Description of the account.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub description: Option<TrimmedNonEmptyText>,
+    /// Organisation to which the account belongs.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub organization: Option<TrimmedNonEmptyText>,
+    /// Fairshare of the account, the number of shares relative to its siblings.
+    #[clap(long)]
+    pub fairshare: Option<u32>,
+}
+
+impl SlurmAccountFields {
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_none()
+            && self.description.is_none()
+            && self.organization.is_none()
+            && self.fairshare.is_none()
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use crate::util::TrimmedNonEmptyText;
+
+use super::{trimmed_non_empty, OptFilePath, SshPathCli};
+
+/// CLI sub commands for backing up and restoring the accounting tree of Slurm
+#[derive(Subcommand, Debug)]
+pub enum SlurmCommand {
+    /// Save accounts, users and associations of a cluster via sacctmgr dump into a local file.
+    /// If Slurm commands run remotely, the dump is downloaded from the head node.
+    #[clap(verbatim_doc_comment)]
+    Backup {
+        /// Name of the cluster to back up as known to Slurm.
+        #[clap(value_parser = trimmed_non_empty)]
+        cluster: TrimmedNonEmptyText,
+        /// Local path of the file for the dump.
+        file: PathBuf,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+    /// Load a dump created by "usermgmt slurm backup" via sacctmgr load.
+    /// The differences of accounts and associations are shown and have to be confirmed first.
+    #[clap(verbatim_doc_comment)]
+    Restore {
+        /// Local path of the dump to restore.
+        file: PathBuf,
+        /// Remove everything of the cluster from Slurm which is not in the dump.
+        /// Without it, the dump is only added to the current accounting tree.
+        #[clap(long, verbatim_doc_comment)]
+        clean: bool,
+        /// Restore without asking for confirmation.
+        #[clap(long)]
+        yes: bool,
+        /// Only show the differences without restoring anything.
+        #[clap(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+}
+
+impl SlurmCommand {
+    pub fn ssh_path(&self) -> &OptFilePath {
+        match self {
+            SlurmCommand::Backup { ssh, .. } | SlurmCommand::Restore { ssh, .. } => &ssh.ssh_path,
+        }
+    }
+}
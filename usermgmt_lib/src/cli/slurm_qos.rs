@@ -0,0 +1,29 @@
+use clap::Subcommand;
+
+use super::{OptFilePath, SshPathCli};
+
+/// CLI sub commands for QOS in the Slurm database
+#[derive(Subcommand, Debug)]
+pub enum QosCommand {
+    /// Create or modify QOS in Slurm to match the sections [qos.<name>] of conf.toml.
+    /// The changes are shown and have to be confirmed before they are applied.
+    #[clap(verbatim_doc_comment)]
+    Sync {
+        /// Apply the changes without asking for confirmation.
+        #[clap(long)]
+        yes: bool,
+        /// Only show the changes without applying them.
+        #[clap(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        ssh: SshPathCli,
+    },
+}
+
+impl QosCommand {
+    pub fn ssh_path(&self) -> &OptFilePath {
+        match self {
+            QosCommand::Sync { ssh, .. } => &ssh.ssh_path,
+        }
+    }
+}
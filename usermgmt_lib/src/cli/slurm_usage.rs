@@ -0,0 +1,74 @@
+use chrono::{Days, Local, NaiveDate};
+use clap::Args;
+
+use crate::{
+    prelude::*,
+    slurm::{UsageFilter, UsagePeriod},
+    util::TrimmedNonEmptyText,
+};
+
+use super::{trimmed_non_empty, ClustersCli, OptFilePath, SshPathCli};
+
+/// Length of the reported period in days if no start is given
+const DEFAULT_PERIOD_DAYS: u64 = 30;
+
+/// Defines options for reporting the usage of users and accounts
+#[derive(Args, Debug)]
+pub struct UsageArgs {
+    /// Username whose usage is reported.
+    /// The usage of all users is reported if neither a user nor an account is given.
+    #[clap(value_parser = trimmed_non_empty, conflicts_with = "account", verbatim_doc_comment)]
+    pub user: Option<TrimmedNonEmptyText>,
+    /// Account whose usage is reported together with the usage of each of its users.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub account: Option<TrimmedNonEmptyText>,
+    /// First day of the reported period as YYYY-MM-DD.
+    /// Defaults to 30 days before the end.
+    #[clap(long, value_parser = usage_date, verbatim_doc_comment)]
+    pub start: Option<NaiveDate>,
+    /// Day after the reported period as YYYY-MM-DD.
+    /// Defaults to today.
+    #[clap(long, value_parser = usage_date, verbatim_doc_comment)]
+    pub end: Option<NaiveDate>,
+    /// Print the usage as JSON instead of tables.
+    #[clap(long)]
+    pub json: bool,
+    #[command(flatten)]
+    pub ssh: SshPathCli,
+    #[command(flatten)]
+    pub clusters: ClustersCli,
+}
+
+impl UsageArgs {
+    pub fn ssh_path(&self) -> &OptFilePath {
+        &self.ssh.ssh_path
+    }
+
+    pub fn filter(&self) -> UsageFilter {
+        match (&self.user, &self.account) {
+            (Some(user), _) => UsageFilter::User(user.to_string()),
+            (None, Some(account)) => UsageFilter::Account(account.to_string()),
+            (None, None) => UsageFilter::All,
+        }
+    }
+
+    /// # Errors
+    ///
+    /// - If the start is not before the end. See [`UsagePeriod::new`]
+    pub fn period(&self) -> AppResult<UsagePeriod> {
+        let end = self.end.unwrap_or_else(|| Local::now().date_naive());
+        let start = match self.start {
+            Some(start) => start,
+            None => end
+                .checked_sub_days(Days::new(DEFAULT_PERIOD_DAYS))
+                .ok_or_else(|| anyhow!("No start before the end {} of the usage period", end))?,
+        };
+        UsagePeriod::new(start, end)
+    }
+}
+
+/// Used by argument parser to ensure that the argument is a date like 2024-01-31
+fn usage_date(s: &str) -> AppResult<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("'{}' is no date in the format YYYY-MM-DD", s))
+}
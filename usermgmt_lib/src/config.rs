@@ -1,8 +1,19 @@
+pub use cluster_config::ClusterConfig;
 pub use path_sources::get_path_to_conf;
+pub use privilege_escalation::PrivilegeEscalation;
+pub use qos_definition::{QosAttributeKind, QosDefinition};
+pub use ssh_host_config::SshHostConfig;
 
+mod cluster_config;
 mod path_sources;
+mod privilege_escalation;
+mod qos_definition;
+mod ssh_host_config;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use log::info;
@@ -39,6 +50,12 @@ pub struct MgmtConfig {
     pub ldap_bind_prefix: Option<String>,
     pub ldap_bind_org_unit: Option<String>,
     pub ldap_add_created_at: bool,
+    /// Mirror the Slurm limits of users into the LDAP attribute slurmLimit as key=value
+    #[serde(default)]
+    pub ldap_add_slurm_limits: bool,
+    /// LDAP attribute into which the names of the Slurm accounts of users are mirrored
+    #[serde(default)]
+    pub ldap_slurm_account_attribute: Option<String>,
     pub home_host: String,
     pub nfs_host: Vec<String>,
     pub head_node: String,
@@ -58,12 +75,128 @@ pub struct MgmtConfig {
     pub include_ldap: bool,
     pub include_dir_mgmt: bool,
     pub use_homedir_helper: bool,
+    /// How the commands of the directory management gain root privileges on remote hosts
+    #[serde(default)]
+    pub privilege_escalation: PrivilegeEscalation,
+    /// Path of mkdir on remote hosts. Defaults to mkdir found via PATH.
+    pub mkdir_path: Option<String>,
+    /// Path of chown on remote hosts. Defaults to chown found via PATH.
+    pub chown_path: Option<String>,
+    /// Path of rm on remote hosts. Defaults to rm found via PATH.
+    pub rm_path: Option<String>,
+    /// Path of setquota on remote hosts. Defaults to setquota found via PATH.
+    pub setquota_path: Option<String>,
+    /// Path of mkhomedir_helper on the home host. Defaults to mkhomedir_helper found via PATH.
+    pub mkhomedir_helper_path: Option<String>,
     pub run_slurm_remote: bool,
     pub ssh_port: u32,
     pub ssh_agent: bool,
     pub ssh_key_path: Option<PathBuf>,
+    /// OpenSSH user certificate signed by a CA which is used together with the key of `ssh_key_path`.
+    /// Defaults to the file with the suffix `-cert.pub` next to the key if it exists.
+    pub ssh_certificate_path: Option<PathBuf>,
+    /// Fingerprint like `SHA256:...` of the key in the SSH agent to authenticate with.
+    /// Without it the user is asked to choose if the agent has several keys.
+    pub ssh_agent_key_fingerprint: Option<String>,
+    /// Known hosts file against which host keys are checked. Defaults to `~/.ssh/known_hosts`.
+    pub ssh_known_hosts_path: Option<PathBuf>,
+    /// If true, connections to hosts which are not in the known hosts file fail.
+    /// Otherwise the user is asked whether to trust the key of such a host.
+    #[serde(default)]
+    pub ssh_strict_host_key_checking: bool,
+    /// Hosts through which all SSH connections are made, like the option ProxyJump of OpenSSH
+    pub ssh_jump_host: Option<String>,
+    /// If true, host aliases, users, ports, identity files and jump hosts are taken from the OpenSSH
+    /// client configuration. Values of this configuration take precedence.
+    #[serde(default)]
+    pub use_ssh_config: bool,
+    /// OpenSSH client configuration used with `use_ssh_config`. Defaults to `~/.ssh/config`.
+    pub ssh_config_path: Option<PathBuf>,
+    /// Maximum number of hosts, like compute nodes, on which commands run at the same time.
+    /// Defaults to [`constants::DEFAULT_SSH_MAX_PARALLEL`].
+    pub ssh_max_parallel: Option<u32>,
+    /// Seconds after which a remote command is aborted.
+    /// Defaults to [`constants::DEFAULT_SSH_COMMAND_TIMEOUT_SECS`].
+    pub ssh_command_timeout_secs: Option<u32>,
+    /// Seconds to wait for the jobs of a user to finish or be cancelled before deleting the user fails.
+    /// Defaults to [`constants::DEFAULT_ACTIVE_JOBS_TIMEOUT_SECS`].
+    pub active_jobs_timeout_secs: Option<u32>,
+    #[serde(default)]
+    pub use_slurmrestd: bool,
+    pub slurmrestd_url: Option<String>,
+    pub slurmrestd_api_version: Option<String>,
+    pub slurmrestd_user: Option<String>,
+    pub slurmrestd_jwt: Option<String>,
+    pub slurmrestd_cluster: Option<String>,
+    /// Marks the configuration as production. Destructive actions have to be confirmed then.
+    #[serde(default)]
+    pub production: bool,
+    /// Port and user for SSH connections to certain hosts given as sections `[ssh_hosts."<host>"]`
+    #[serde(default)]
+    pub ssh_hosts: BTreeMap<String, SshHostConfig>,
+    /// Slurm clusters given as sections `[[clusters]]`.
+    /// If empty, Slurm is managed on the single cluster of `head_node` and `sacctmgr_path`.
+    #[serde(default)]
+    pub clusters: Vec<ClusterConfig>,
+    /// QOS which `usermgmt qos sync` creates or modifies in Slurm, given as sections `[qos.<name>]`
+    #[serde(default)]
+    pub qos: BTreeMap<String, QosDefinition>,
 }
 impl MgmtConfig {
+    /// Returns the configuration with the head node, sacctmgr path and remote flag of `cluster`.
+    /// The name of the cluster is used as `slurmrestd_cluster`.
+    pub fn for_cluster(&self, cluster: &ClusterConfig) -> Self {
+        let mut config = self.clone();
+        if let Some(head_node) = &cluster.head_node {
+            config.head_node.clone_from(head_node);
+        }
+        if let Some(sacctmgr_path) = &cluster.sacctmgr_path {
+            config.sacctmgr_path.clone_from(sacctmgr_path);
+        }
+        if let Some(run_slurm_remote) = cluster.run_slurm_remote {
+            config.run_slurm_remote = run_slurm_remote;
+        }
+        config.slurmrestd_cluster = Some(cluster.name.clone());
+        config
+    }
+
+    /// Returns the configuration for the Slurm cluster named `name`. See [`MgmtConfig::for_cluster`].
+    /// Without clusters, the configuration is returned as it is.
+    ///
+    /// # Errors
+    ///
+    /// - If there are clusters but none of them is named `name`.
+    pub fn for_cluster_named(&self, name: &str) -> AppResult<Self> {
+        if self.clusters.is_empty() {
+            return Ok(self.clone());
+        }
+        self.clusters
+            .iter()
+            .find(|cluster| cluster.name == name)
+            .map(|cluster| self.for_cluster(cluster))
+            .ok_or_else(|| anyhow!("No cluster named {} in the configuration", name))
+    }
+
+    /// Returns the clusters with the names in `selection` or all clusters if `selection` is empty.
+    ///
+    /// # Errors
+    ///
+    /// - If a name in `selection` is not the name of any cluster in `clusters`.
+    pub fn selected_clusters(&self, selection: &[String]) -> AppResult<Vec<&ClusterConfig>> {
+        if selection.is_empty() {
+            return Ok(self.clusters.iter().collect());
+        }
+        selection
+            .iter()
+            .map(|name| {
+                self.clusters
+                    .iter()
+                    .find(|cluster| &cluster.name == name)
+                    .ok_or_else(|| anyhow!("No cluster named {} in the configuration", name))
+            })
+            .collect()
+    }
+
     /// # Errors
     ///
     /// - If the parameter `path` can not be converted into an absolute path.
@@ -74,10 +207,21 @@ impl MgmtConfig {
             .canonicalize()
             .with_context(|| format!("Could get absolute path at {:?}", path))?
             .join(constants::NAME_CONFIG_FILE);
+        self.save_to_file(&file_path)?;
+        Ok(path.to_path_buf())
+    }
+
+    /// Saves the configuration into the file at `file_path`, for example the file of a profile.
+    ///
+    /// # Errors
+    ///
+    /// - If the content could not converted into the toml format.
+    /// - If writing the new content to file at `file_path` fails.
+    pub fn save_to_file(&self, file_path: &Path) -> AppResult<PathBuf> {
         let text = toml::to_string_pretty(&self)?;
-        std::fs::write(&file_path, text)
+        std::fs::write(file_path, text)
             .with_context(|| format!("Could not save to path: {:?}", file_path))?;
-        Ok(path.to_path_buf())
+        Ok(file_path.to_path_buf())
     }
 }
 
@@ -85,6 +229,8 @@ impl Default for MgmtConfig {
     fn default() -> Self {
         MgmtConfig {
             ldap_add_created_at: false,
+            ldap_add_slurm_limits: false,
+            ldap_slurm_account_attribute: None,
             student_default_qos: "basic".to_string(),
             staff_default_qos: "advanced".to_string(),
             student_qos: vec!["interactive".to_string(), "basic".to_string()],
@@ -135,6 +281,12 @@ impl Default for MgmtConfig {
             include_ldap: true,
             include_dir_mgmt: false,
             use_homedir_helper: true,
+            privilege_escalation: PrivilegeEscalation::default(),
+            mkdir_path: None,
+            chown_path: None,
+            rm_path: None,
+            setquota_path: None,
+            mkhomedir_helper_path: None,
             filesystem: "".to_string(),
             home_filesystem: "".to_string(),
             nfs_filesystem: vec!["".to_string()],
@@ -144,18 +296,85 @@ impl Default for MgmtConfig {
             ssh_port: 22,
             ssh_agent: false,
             ssh_key_path: None,
+            ssh_certificate_path: None,
+            ssh_agent_key_fingerprint: None,
+            ssh_known_hosts_path: None,
+            ssh_strict_host_key_checking: false,
+            ssh_jump_host: None,
+            use_ssh_config: false,
+            ssh_config_path: None,
+            ssh_max_parallel: None,
+            ssh_command_timeout_secs: None,
+            active_jobs_timeout_secs: None,
+            use_slurmrestd: false,
+            slurmrestd_url: None,
+            slurmrestd_api_version: None,
+            slurmrestd_user: None,
+            slurmrestd_jwt: None,
+            slurmrestd_cluster: None,
+            production: false,
+            ssh_hosts: BTreeMap::new(),
+            clusters: Vec::new(),
+            qos: BTreeMap::new(),
         }
     }
 }
 
-/// Attempts to load config.toml.
+/// Returns `profile` or the profile of the environment variable [`constants::PROFILE_ENV_VARIABLE`]
+/// if `profile` is none.
+pub fn active_profile(profile: Option<String>) -> Option<String> {
+    profile.or_else(|| {
+        std::env::var(constants::PROFILE_ENV_VARIABLE)
+            .ok()
+            .filter(|from_env| !from_env.trim().is_empty())
+    })
+}
+
+/// Returns the names of all profiles in the profile directory next to the configuration file.
+/// The names are sorted. No profile is returned if there is no profile directory.
+///
+/// # Errors
+///
+/// - If the configuration file could not be located. See [`get_path_to_conf`]
+/// - If the profile directory could not be read.
+pub fn list_profiles(manual_path: Option<PathBuf>) -> AppResult<Vec<String>> {
+    let directory = profile_directory(&config::get_path_to_conf(manual_path)?)?;
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut profiles = std::fs::read_dir(&directory)
+        .with_context(|| format!("Could not read profile directory at {:?}", directory))?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let is_toml = path
+                .extension()
+                .is_some_and(|extension| extension == "toml");
+            is_toml
+                .then(|| path.file_stem()?.to_str().map(ToOwned::to_owned))
+                .flatten()
+        })
+        .collect::<Vec<_>>();
+    profiles.sort();
+    Ok(profiles)
+}
+
+/// Attempts to load config.toml or the profile `profile` next to it.
+/// A profile is a complete configuration in the file `profiles/<profile>.toml`
+/// within the directory of config.toml.
 ///
 /// # Error
 ///
 /// - Can not check if directory exists where conf.toml is located
 /// - Can not read or create a conf.toml file
-pub fn load_config(manual_path: Option<PathBuf>) -> AppResult<LoadedMgmtConfig> {
+/// - If the file of `profile` does not exist or is not a valid configuration.
+pub fn load_config(
+    manual_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> AppResult<LoadedMgmtConfig> {
     let path = config::get_path_to_conf(manual_path)?;
+    if let Some(profile) = profile {
+        return load_profile(&path, profile);
+    }
 
     info!("Loading configuration file from path: {:?}", path);
     // Load (or create if nonexistent) configuration file conf.toml
@@ -169,18 +388,123 @@ pub fn load_config(manual_path: Option<PathBuf>) -> AppResult<LoadedMgmtConfig>
         .parent()
         .ok_or_else(|| anyhow!("{:?} must have a parent folder", &path))?
         .to_path_buf();
-    Ok(LoadedMgmtConfig { path, config })
+    Ok(LoadedMgmtConfig {
+        path,
+        config,
+        profile: None,
+    })
+}
+
+fn load_profile(path_to_conf: &Path, profile: String) -> AppResult<LoadedMgmtConfig> {
+    let directory = profile_directory(path_to_conf)?;
+    let file = directory.join(format!("{}.toml", profile));
+    info!("Loading profile {} from path: {:?}", profile, file);
+    if !file.is_file() {
+        bail!(
+            "Profile {} does not exist. Expected a configuration file at {:?}",
+            profile,
+            file
+        );
+    }
+    let text = std::fs::read_to_string(&file)
+        .with_context(|| format!("Could not read profile {} at {:?}", profile, file))?;
+    let config = toml::from_str(&text).with_context(|| {
+        format!(
+            "Profile {} at {:?} is not a valid configuration",
+            profile, file
+        )
+    })?;
+    let path = directory
+        .parent()
+        .ok_or_else(|| anyhow!("{:?} must have a parent folder", &directory))?
+        .to_path_buf();
+    Ok(LoadedMgmtConfig {
+        path,
+        config,
+        profile: Some(profile),
+    })
+}
+
+fn profile_directory(path_to_conf: &Path) -> AppResult<PathBuf> {
+    let directory = path_to_conf
+        .parent()
+        .ok_or_else(|| anyhow!("{:?} must have a parent folder", path_to_conf))?;
+    Ok(directory.join(constants::PROFILE_DIRECTORY))
 }
 
 #[derive(Debug, Default)]
 pub struct LoadedMgmtConfig {
+    /// Directory of the configuration file
     pub path: PathBuf,
     pub config: MgmtConfig,
+    /// Name of the loaded profile. None if config.toml itself is loaded.
+    pub profile: Option<String>,
 }
 
-impl LoadedMgmtConfig {}
+impl LoadedMgmtConfig {
+    /// Returns the path of the file from which the configuration was loaded.
+    pub fn file_path(&self) -> PathBuf {
+        match &self.profile {
+            Some(profile) => self
+                .path
+                .join(constants::PROFILE_DIRECTORY)
+                .join(format!("{}.toml", profile)),
+            None => self.path.join(constants::NAME_CONFIG_FILE),
+        }
+    }
+
+    /// Returns the name of the profile for display, marked if it is a production profile.
+    pub fn profile_label(&self) -> String {
+        let name = self
+            .profile
+            .as_deref()
+            .unwrap_or(constants::NAME_CONFIG_FILE);
+        if self.config.production {
+            format!("{} (production)", name)
+        } else {
+            name.to_owned()
+        }
+    }
+}
 
 pub fn config_for_save() -> String {
     toml::to_string_pretty(&MgmtConfig::default())
         .expect("Unable to convert default configuration into .toml format")
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn locates_and_labels_profiles_next_to_config_file() {
+        let staging = LoadedMgmtConfig {
+            path: PathBuf::from("/etc/usermgmt"),
+            config: MgmtConfig::default(),
+            profile: Some("staging".to_owned()),
+        };
+        let production = LoadedMgmtConfig {
+            config: MgmtConfig {
+                production: true,
+                ..Default::default()
+            },
+            path: staging.path.clone(),
+            profile: None,
+        };
+
+        assert_eq!(
+            (
+                PathBuf::from("/etc/usermgmt/profiles/staging.toml"),
+                "staging".to_owned()
+            ),
+            (staging.file_path(), staging.profile_label())
+        );
+        assert_eq!(
+            (
+                PathBuf::from("/etc/usermgmt/conf.toml"),
+                "conf.toml (production)".to_owned()
+            ),
+            (production.file_path(), production.profile_label())
+        );
+    }
+}
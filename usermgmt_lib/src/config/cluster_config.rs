@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, slurm::AccountMembership, util::TrimmedNonEmptyText};
+
+/// Describes one Slurm cluster within a section `[[clusters]]` of conf.toml.
+/// Absent values are taken from the top level of the configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    /// Name which selects the cluster via `--cluster`
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_node: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sacctmgr_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_slurm_remote: Option<bool>,
+    /// Maps names of accounts, for example groups like staff, to the names of the accounts on this cluster.
+    /// Accounts without an entry keep their name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub account_mapping: BTreeMap<String, String>,
+}
+
+impl ClusterConfig {
+    /// Returns the name of the account on this cluster for `account`.
+    ///
+    /// # Errors
+    ///
+    /// - If `account` is mapped to a blank name in `account_mapping`.
+    pub fn map_account(&self, account: &TrimmedNonEmptyText) -> AppResult<TrimmedNonEmptyText> {
+        match self.account_mapping.get(account.as_ref()) {
+            Some(mapped) => TrimmedNonEmptyText::try_from(mapped.as_str()).map_err(|_| {
+                anyhow!(
+                    "Account {} is mapped to the invalid name '{}' in account_mapping of cluster {}",
+                    account,
+                    mapped,
+                    self.name
+                )
+            }),
+            None => Ok(account.clone()),
+        }
+    }
+
+    /// Returns the membership with the account on this cluster. The partition is kept.
+    ///
+    /// # Errors
+    ///
+    /// - If the account is mapped to an invalid name. See [`ClusterConfig::map_account`]
+    pub fn map_membership(&self, membership: &AccountMembership) -> AppResult<AccountMembership> {
+        Ok(AccountMembership {
+            account: self.map_account(&membership.account)?,
+            partition: membership.partition.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::config::MgmtConfig;
+
+    fn two_clusters() -> MgmtConfig {
+        let gpu = ClusterConfig {
+            name: "gpu".to_owned(),
+            head_node: Some("gpu.head".to_owned()),
+            run_slurm_remote: Some(true),
+            account_mapping: BTreeMap::from([("staff".to_owned(), "gpu_staff".to_owned())]),
+            ..Default::default()
+        };
+        MgmtConfig {
+            head_node: "hpc.head".to_owned(),
+            clusters: vec![
+                ClusterConfig {
+                    name: "hpc".to_owned(),
+                    ..Default::default()
+                },
+                gpu,
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn selects_clusters_and_takes_absent_values_from_top_level() {
+        let config = two_clusters();
+
+        let all = config.selected_clusters(&[]).unwrap();
+        assert_eq!(
+            vec!["hpc", "gpu"],
+            all.iter().map(|c| &c.name).collect::<Vec<_>>()
+        );
+        let gpu = config.selected_clusters(&["gpu".to_owned()]).unwrap()[0];
+        assert!(config.selected_clusters(&["unknown".to_owned()]).is_err());
+
+        let (hpc, gpu) = (config.for_cluster(all[0]), config.for_cluster(gpu));
+        assert_eq!(
+            ("hpc.head", false, Some("hpc")),
+            (
+                hpc.head_node.as_str(),
+                hpc.run_slurm_remote,
+                hpc.slurmrestd_cluster.as_deref()
+            )
+        );
+        assert_eq!(
+            ("gpu.head", true, config.sacctmgr_path.as_str()),
+            (
+                gpu.head_node.as_str(),
+                gpu.run_slurm_remote,
+                gpu.sacctmgr_path.as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn finds_configuration_of_cluster_by_name() {
+        let config = two_clusters();
+
+        let gpu = config.for_cluster_named("gpu").unwrap();
+        assert_eq!("gpu.head", gpu.head_node);
+        assert!(config.for_cluster_named("unknown").is_err());
+
+        let without_clusters = MgmtConfig::default();
+        assert_eq!(
+            without_clusters.head_node,
+            without_clusters.for_cluster_named("any").unwrap().head_node
+        );
+    }
+
+    #[test]
+    fn maps_accounts_and_keeps_partitions() {
+        let config = two_clusters();
+        let gpu = &config.clusters[1];
+
+        let staff: AccountMembership = "staff:a100".parse().unwrap();
+        let student: AccountMembership = "student".parse().unwrap();
+
+        assert_eq!(
+            "gpu_staff:a100".parse::<AccountMembership>().unwrap(),
+            gpu.map_membership(&staff).unwrap()
+        );
+        assert_eq!(student, gpu.map_membership(&student).unwrap());
+    }
+
+    #[test]
+    fn fails_for_account_mapped_to_blank_name() {
+        let cluster = ClusterConfig {
+            name: "gpu".to_owned(),
+            account_mapping: BTreeMap::from([("staff".to_owned(), "  ".to_owned())]),
+            ..Default::default()
+        };
+
+        let staff: AccountMembership = "staff".parse().unwrap();
+        assert!(cluster.map_membership(&staff).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// How commands of the directory management gain root privileges on remote hosts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivilegeEscalation {
+    /// Commands run as they are. Meant for connections as root.
+    None,
+    /// Commands run via sudo which must not ask for a password, see NOPASSWD in sudoers.
+    #[default]
+    Sudo,
+    /// Commands run via `sudo -S`. The password of the SSH user is passed via stdin.
+    SudoPassword,
+    /// Commands run via doas which must not ask for a password, see nopass in doas.conf.
+    Doas,
+}
+
+impl PrivilegeEscalation {
+    /// Returns the command line which runs `tool` with `args` with root privileges.
+    pub fn command(self, tool: &str, args: &str) -> String {
+        let prefix = match self {
+            Self::None => "",
+            Self::Sudo => "sudo ",
+            Self::SudoPassword => "sudo -S -p '' ",
+            Self::Doas => "doas ",
+        };
+        format!("{prefix}{tool} {args}")
+    }
+
+    /// True if the password of the SSH user has to be passed via stdin.
+    pub fn needs_password(self) -> bool {
+        self == Self::SudoPassword
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn prefixes_commands_per_escalation() {
+        let actual: Vec<String> = [
+            PrivilegeEscalation::None,
+            PrivilegeEscalation::Sudo,
+            PrivilegeEscalation::SudoPassword,
+            PrivilegeEscalation::Doas,
+        ]
+        .into_iter()
+        .map(|escalation| escalation.command("/usr/bin/mkdir", "-p /data/alice"))
+        .collect();
+        assert_eq!(
+            vec![
+                "/usr/bin/mkdir -p /data/alice",
+                "sudo /usr/bin/mkdir -p /data/alice",
+                "sudo -S -p '' /usr/bin/mkdir -p /data/alice",
+                "doas /usr/bin/mkdir -p /data/alice",
+            ],
+            actual
+        );
+    }
+}
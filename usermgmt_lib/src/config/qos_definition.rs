@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of value of a QOS attribute which determines how values are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QosAttributeKind {
+    /// Compared as is
+    Text,
+    /// Comma separated list whose order does not matter like `Preempt` or `GrpTRES`
+    List,
+    /// Time limit in one of the formats accepted by Slurm like `1-00:00:00` or `90`
+    WallTime,
+}
+
+/// Describes a QOS in the Slurm database within a section `[qos.<name>]` of conf.toml.
+/// Only attributes which are present are managed. Absent attributes are left as they are in Slurm.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QosDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_wall: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tres_per_user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_jobs_per_user: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_submit_jobs_per_user: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grp_tres: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grp_jobs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preempt: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preempt_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<Vec<String>>,
+}
+
+impl QosDefinition {
+    /// Names of the attributes as sacctmgr knows them together with the kind of their values.
+    /// The order is the same as in [`QosDefinition::attributes`].
+    pub const ATTRIBUTES: [(&'static str, QosAttributeKind); 11] = [
+        ("Description", QosAttributeKind::Text),
+        ("Priority", QosAttributeKind::Text),
+        ("MaxWall", QosAttributeKind::WallTime),
+        ("MaxTRESPerUser", QosAttributeKind::List),
+        ("MaxJobsPerUser", QosAttributeKind::Text),
+        ("MaxSubmitJobsPerUser", QosAttributeKind::Text),
+        ("GrpTRES", QosAttributeKind::List),
+        ("GrpJobs", QosAttributeKind::Text),
+        ("Preempt", QosAttributeKind::List),
+        ("PreemptMode", QosAttributeKind::Text),
+        ("Flags", QosAttributeKind::List),
+    ];
+
+    /// Returns all attributes which are present with their name as sacctmgr knows them
+    /// and their value as sacctmgr expects it.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        let to_text = |value: &Option<String>| value.clone();
+        let to_number = |value: &Option<u32>| value.map(|number| number.to_string());
+        let to_list = |value: &Option<Vec<String>>| value.as_ref().map(|list| list.join(","));
+        let values = [
+            to_text(&self.description),
+            to_number(&self.priority),
+            to_text(&self.max_wall),
+            to_text(&self.max_tres_per_user),
+            to_number(&self.max_jobs_per_user),
+            to_number(&self.max_submit_jobs_per_user),
+            to_text(&self.grp_tres),
+            to_number(&self.grp_jobs),
+            to_list(&self.preempt),
+            to_text(&self.preempt_mode),
+            to_list(&self.flags),
+        ];
+        Self::ATTRIBUTES
+            .iter()
+            .zip(values)
+            .filter_map(|((name, _), value)| value.map(|value| (*name, value)))
+            .collect()
+    }
+}
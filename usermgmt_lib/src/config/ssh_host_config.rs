@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Overrides for SSH connections to one host within a section `[ssh_hosts."<host>"]` of conf.toml.
+/// Absent values are taken from `ssh_port` and the SSH credentials.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshHostConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u32>,
+    /// User name for this host instead of the one of the SSH credentials
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Run commands for this host on the local machine without SSH.
+    /// Defaults to false, also for localhost.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local: Option<bool>,
+}
@@ -1,9 +1,21 @@
 use const_format::concatcp;
 
 pub const SSH_TIME_OUT_MILL_SECS: u32 = 6000;
+/// Number of hosts on which commands run at the same time if `ssh_max_parallel` is not configured
+pub const DEFAULT_SSH_MAX_PARALLEL: u32 = 8;
+/// Seconds after which a remote command is aborted if `ssh_command_timeout_secs` is not configured
+pub const DEFAULT_SSH_COMMAND_TIMEOUT_SECS: u32 = 300;
+/// Seconds between two checks via squeue while waiting for the jobs of a user to finish.
+pub const ACTIVE_JOBS_POLL_SECS: u64 = 10;
+/// Seconds after which waiting for the jobs of a user to finish fails if `active_jobs_timeout_secs` is not configured
+pub const DEFAULT_ACTIVE_JOBS_TIMEOUT_SECS: u32 = 3600;
 /// Name of the file in which all values for configuration of this app are located
 /// besides the CLI arguments.
 pub const NAME_CONFIG_FILE: &str = "conf.toml";
+/// Name of the directory next to the configuration file which contains the profiles as `<name>.toml`
+pub const PROFILE_DIRECTORY: &str = "profiles";
+/// Environment variable which selects a profile if no profile is given otherwise
+pub const PROFILE_ENV_VARIABLE: &str = "USERMGMT_PROFILE";
 pub const README_LINK: &str = "https://github.com/th-nuernberg/usermgmt/blob/main/README.md";
 pub const ISSUE_LINK: &str = "https://github.com/th-nuernberg/usermgmt/issues";
 pub const REPOSITORY_LINK: &str = "https://github.com/th-nuernberg/usermgmt";
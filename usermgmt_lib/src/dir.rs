@@ -2,45 +2,49 @@
 use log::{debug, info, warn};
 
 use crate::config::MgmtConfig;
-use crate::prelude::AppResult;
-use crate::ssh::{self, SshConnection, SshCredentials};
+use crate::prelude::*;
+use crate::ssh::{self, RemoteOutput, SshConnection, SshConnectionPool, SshCredentials};
 use crate::util::ResultAccumulator;
 use crate::{Group, NewEntity};
 
-pub fn add_user_directories<T>(
-    entity: &NewEntity,
-    config: &MgmtConfig,
-    credentials: &T,
-) -> AppResult
+pub fn add_user_directories<T>(entity: &NewEntity, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
-    handle_compute_nodes(entity, config, credentials)?;
+    handle_compute_nodes(entity, pool)?;
 
-    handle_nfs(entity, config, credentials)?;
+    handle_nfs(entity, pool)?;
 
-    handle_home(entity, config, credentials)?;
+    handle_home(entity, pool)?;
 
     Ok(())
 }
 
-pub fn delete_user_directories<T>(username: &str, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Deletes the home directory, the NFS directories and the directories on compute nodes of the user
+/// in this order.
+///
+/// # Errors
+///
+/// - If the deletion fails on any host. Every host of the failed step is still tried,
+///   but the following steps are skipped.
+pub fn delete_user_directories<T>(username: &str, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
-    delete_home_dir(username, config, credentials)?;
-    delete_nfs_dir(username, config, credentials)?;
-    delete_node_local_dir(username, config, credentials)?;
+    delete_home_dir(username, pool)?;
+    delete_nfs_dir(username, pool)?;
+    delete_node_local_dir(username, pool)?;
 
     Ok(())
 }
 
-/// Establish SSH connection to each compute node and delete user directory
-fn delete_node_local_dir<T>(username: &str, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Delete user directory on all compute nodes in parallel
+fn delete_node_local_dir<T>(username: &str, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     info!("Start deleting directories on compute nodes");
+    let config = pool.config();
 
     if config.compute_nodes.is_empty() {
         warn!("No compute nodes provided in config. Unable to delete user directories on nodes.");
@@ -56,51 +60,41 @@ where
         return Ok(());
     }
 
-    let mut rm_exit_codes = Vec::new();
-    for server in config.compute_nodes.iter() {
+    let directory = format!("{}/{}", config.compute_node_root_dir, username);
+    let tasks = config
+        .compute_nodes
+        .iter()
+        .map(|server| (server.as_str(), ()))
+        .collect();
+    let outcomes = pool.run_on_each(tasks, |sess, ()| {
         info!(
-            "{}",
-            format!(
-                "Connecting to compute node {} for directory deletion",
-                server
-            )
+            "Connecting to compute node {} for directory deletion",
+            sess.endpoint()
         );
-        let sess = SshConnection::new(server, config, credentials.clone());
-        // Delete directory
-        let directory = format!("{}/{}", config.compute_node_root_dir, username);
-        let (dir_exit_code, _) = delete_directory(&sess, &directory)?;
-        rm_exit_codes.push(dir_exit_code);
-    }
+        delete_directory(sess, config, &directory)?.check_success("Directory deletion")?;
+        Ok(())
+    });
 
     let mut errors_from_codes =
         ResultAccumulator::new("Failed to delete all directories on compute nodes".to_owned());
+    for (server, outcome) in outcomes {
+        errors_from_codes.add_if_err(&format!("Compute node {}", server), outcome);
+    }
 
-    let all_exit_codes_are_zero = rm_exit_codes.iter().all(|&x| x == 0);
-
-    errors_from_codes.add_err_if_false(
-        all_exit_codes_are_zero,
-        "Not all compute nodes returned exit code 0 during directory deletion!".to_owned(),
-    );
-
-    if !errors_from_codes.errs.is_empty() {
-        warn!(
-            "{}: {}",
-            errors_from_codes.base_err_msg,
-            errors_from_codes.errs.join("\n")
-        );
-    } else {
+    if errors_from_codes.errs.is_empty() {
         info!("Successfully deleted directories on compute nodes.");
     }
 
-    Ok(())
+    errors_from_codes.into()
 }
 
-/// Establish SSH connection to NFS hosts and delete user directory
-fn delete_nfs_dir<T>(username: &str, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Delete user directory on all NFS hosts in parallel
+fn delete_nfs_dir<T>(username: &str, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     debug!("Start deleting NFS user directories");
+    let config = pool.config();
 
     if config.nfs_host.is_empty() {
         warn!("No NFS host provided in config. Unable to create directory.");
@@ -111,65 +105,53 @@ where
         return Ok(());
     }
 
-    let mut detected_errors =
-        ResultAccumulator::new("Errors during NFS directory deletion occurred".to_owned());
-    for i in 0..config.nfs_host.len() {
-        let current_nfs_host = &config.nfs_host[i];
-        let current_nfs_root_dir = &config.nfs_root_dir[i];
+    // Infer user group
+    let mut group_dir = "staff";
+    if username
+        .chars()
+        .last()
+        .map(|c| c.is_ascii_digit())
+        .unwrap_or(false)
+    {
+        group_dir = "students";
+    }
 
+    let tasks = config
+        .nfs_host
+        .iter()
+        .zip(config.nfs_root_dir.iter())
+        .map(|(host, root_dir)| (host.as_str(), root_dir))
+        .collect();
+    let outcomes = pool.run_on_each(tasks, |sess, current_nfs_root_dir| {
         info!(
             "Connecting to NFS host {} for directory deletion",
-            current_nfs_host
+            sess.endpoint()
         );
-        let sess = SshConnection::new(current_nfs_host, config, credentials.clone());
-
-        // Infer user group
-        let mut group_dir = "staff";
-        if username
-            .chars()
-            .last()
-            .map(|c| c.is_ascii_digit())
-            .unwrap_or(false)
-        {
-            group_dir = "students";
-        }
-
         let directory = format!("{}/{}/{}", current_nfs_root_dir, group_dir, username);
-        let (dir_exit_code, _) = delete_directory(&sess, &directory)?;
-
-        if dir_exit_code != 0 {
-            detected_errors.add_err(format!(
-                "NFS host {} did not return with exit code 0 during directory deletion!",
-                current_nfs_host
-            ));
-        } else {
-            info!(
-                "{}",
-                format!(
-                    "Successfully deleted user directory on NFS host {}.",
-                    current_nfs_host
-                )
-            );
-        }
-    }
-
-    if !detected_errors.errs.is_empty() {
-        warn!(
-            "{}: {}",
-            detected_errors.base_err_msg,
-            detected_errors.errs.join("\n")
+        delete_directory(sess, config, &directory)?.check_success("Directory deletion")?;
+        info!(
+            "Successfully deleted user directory on NFS host {}.",
+            sess.endpoint()
         );
+        Ok(())
+    });
+
+    let mut detected_errors =
+        ResultAccumulator::new("Errors during NFS directory deletion occurred".to_owned());
+    for (current_nfs_host, outcome) in outcomes {
+        detected_errors.add_if_err(&format!("NFS host {}", current_nfs_host), outcome);
     }
 
-    Ok(())
+    detected_errors.into()
 }
 
-/// Establish SSH connection to home host and delete user home directory
-fn delete_home_dir<T>(username: &str, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Delete user home directory on the home host
+fn delete_home_dir<T>(username: &str, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     debug!("Start deleting home directory");
+    let config = pool.config();
 
     if config.home_host.is_empty() {
         warn!("No home host provided in config. Unable to delete user home directory.");
@@ -177,36 +159,31 @@ where
     }
 
     info!(
-        "{}",
-        format!(
-            "Connecting to home host {} for directory deletion",
-            &config.home_host
-        )
+        "Connecting to home host {} for directory deletion",
+        &config.home_host
     );
-    let sess = SshConnection::new(&config.home_host, config, credentials.clone());
 
     // Delete directory
     let directory = format!("/home/{}", username);
-    let (dir_exit_code, _) = delete_directory(&sess, &directory)?;
+    let output = pool.with_connection(&config.home_host, |sess| {
+        delete_directory(sess, config, &directory)
+    })?;
 
-    if dir_exit_code == 0 {
-        info!("Successfully deleted user home directory.");
-    } else {
-        warn!(
-            "{}",
-            format!("Failed to delete user home directory: {}", &directory)
-        );
-    }
+    output
+        .check_success("Directory deletion")
+        .with_context(|| format!("Failed to delete user home directory {}", &directory))?;
+    info!("Successfully deleted user home directory.");
 
     Ok(())
 }
 
-/// Establish SSH connection to each compute node, make user directory and set quota
-fn handle_compute_nodes<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Make user directory and set quota on all compute nodes in parallel
+fn handle_compute_nodes<T>(entity: &NewEntity, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     info!("Start handling directories on compute nodes");
+    let config = pool.config();
 
     if config.compute_nodes.is_empty() {
         warn!("No compute nodes provided in config. Unable to create user directories.");
@@ -231,67 +208,47 @@ where
         warn!("Hard-/softlimit and/or filesystem for quotas isn't properly configured. Refusing to set user quotas based on these values. Please check your conf.toml");
     }
 
-    let mut mkdir_exit_codes = Vec::new();
-    let mut owner_exit_codes = Vec::new();
-    let mut quota_exit_codes = Vec::new();
-    for server in config.compute_nodes.iter() {
-        info!("{}", format!("Connecting to compute node {}", server));
-        let sess = SshConnection::new(server, config, credentials.clone());
+    let directory = format!("{}/{}", config.compute_node_root_dir, entity.username);
+    let tasks = config
+        .compute_nodes
+        .iter()
+        .map(|server| (server.as_str(), ()))
+        .collect();
+    let outcomes = pool.run_on_each(tasks, |sess, ()| {
+        info!("Connecting to compute node {}", sess.endpoint());
         // Create directory
-        let directory = format!("{}/{}", config.compute_node_root_dir, entity.username);
-        let (dir_exit_code, _) = make_directory(&sess, &directory)?;
-        mkdir_exit_codes.push(dir_exit_code);
+        make_directory(sess, config, &directory)?.check_success("Directory creation")?;
 
-        if dir_exit_code == 0 {
-            // Give ownership to user
-            let owner_exit_code = change_ownership(
-                &sess,
-                &directory,
+        // Give ownership to user
+        change_ownership(
+            sess,
+            config,
+            &directory,
+            entity.username.as_ref(),
+            &entity.group.to_string(),
+        )?
+        .check_success("Ownership change")?;
+
+        // Set user quota
+        if can_set_quota {
+            set_quota(
+                sess,
+                config,
                 entity.username.as_ref(),
-                &entity.group.to_string(),
-            );
-            owner_exit_codes.push(owner_exit_code);
-
-            // Set user quota
-            let mut quota_exit_code = 1;
-            if can_set_quota {
-                (quota_exit_code, _) = set_quota(
-                    &sess,
-                    entity.username.as_ref(),
-                    &config.quota_softlimit,
-                    &config.quota_hardlimit,
-                    &config.filesystem,
-                )?;
-            }
-            quota_exit_codes.push(quota_exit_code);
+                &config.quota_softlimit,
+                &config.quota_hardlimit,
+                &config.filesystem,
+            )?
+            .check_success("Quota setup")?;
         }
-    }
+        Ok(())
+    });
 
     let mut errors_from_codes =
         ResultAccumulator::new("Failed to create directories on compute nodes.".to_owned());
-
-    let all_exit_codes_are_zero = mkdir_exit_codes.iter().all(|&x| x == 0);
-
-    errors_from_codes.add_err_if_false(
-        all_exit_codes_are_zero,
-        "Not all compute nodes returned exit code 0 during directory creation!".to_owned(),
-    );
-
-    let all_owner_exit_codes_are_zero = owner_exit_codes
-        .iter()
-        .all(|x| x.as_ref().is_ok_and(|(code, _)| *code == 0));
-
-    errors_from_codes.add_err_if_false(
-        all_owner_exit_codes_are_zero,
-        "Not all compute nodes returned exit code 0 during ownership change!".to_owned(),
-    );
-
-    let all_quota_exit_codes_are_zero = quota_exit_codes.iter().all(|&x| x == 0);
-
-    errors_from_codes.add_err_if_false(
-        all_quota_exit_codes_are_zero,
-        "Not all compute nodes returned exit code 0 during quota setup!".to_owned(),
-    );
+    for (server, outcome) in outcomes {
+        errors_from_codes.add_if_err(&format!("Compute node {}", server), outcome);
+    }
 
     AppResult::from(errors_from_codes)?;
 
@@ -300,12 +257,13 @@ where
     Ok(())
 }
 
-/// Establish SSH connection to NFS hosts, make user directory and set quota
-fn handle_nfs<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Make user directory and set quota on all NFS hosts in parallel
+fn handle_nfs<T>(entity: &NewEntity, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     debug!("Start handling NFS user directories");
+    let config = pool.config();
 
     if config.nfs_host.is_empty() {
         warn!("No NFS host provided in config. Unable to create directory.");
@@ -325,74 +283,58 @@ where
         warn!("Hard-/softlimit and/or filesystem for quota isn't properly configured. Refusing to set user quota based on these values. Please check your conf.toml");
     }
 
-    let mut detected_errors =
-        ResultAccumulator::new("Errors during NFS directory creation occurred!".to_owned());
-    for i in 0..config.nfs_host.len() {
-        let current_nfs_host = &config.nfs_host[i];
-        let current_nfs_root_dir = &config.nfs_root_dir[i];
-        let current_quota_nfs_softlimit = &config.quota_nfs_softlimit[i];
-        let current_nfs_quota_nfs_hardlimit = &config.quota_nfs_hardlimit[i];
-        let current_nfs_filesystem = &config.nfs_filesystem[i];
+    let mut group_dir = "staff";
+    if entity.group.id() == Group::Student {
+        group_dir = "students"
+    }
 
-        info!("Connecting to NFS host {}", current_nfs_host);
-        let sess = SshConnection::new(current_nfs_host, config, credentials.clone());
+    let tasks = config
+        .nfs_host
+        .iter()
+        .enumerate()
+        .map(|(i, host)| (host.as_str(), i))
+        .collect();
+    let outcomes = pool.run_on_each(tasks, |sess, i| {
+        let current_nfs_root_dir = &config.nfs_root_dir[i];
+        info!("Connecting to NFS host {}", sess.endpoint());
 
         // Create directory
-        let mut group_dir = "staff";
-        if entity.group.id() == Group::Student {
-            group_dir = "students"
-        }
         let directory = format!("{}/{}/{}", current_nfs_root_dir, group_dir, entity.username);
-        let (dir_exit_code, _) = make_directory(&sess, &directory)?;
+        make_directory(sess, config, &directory)?.check_success("Directory creation")?;
 
-        let no_error_make_dir = dir_exit_code == 0;
-        if no_error_make_dir {
-            // Give ownership to user
-            let (owner_exit_code, _) = change_ownership(
-                &sess,
-                &directory,
-                entity.username.as_ref(),
-                &entity.group.to_string(),
-            )?;
-            if owner_exit_code != 0 {
-                detected_errors.add_err(format!(
-                    "NFS host {} did not return with exit code 0 during ownership change!",
-                    current_nfs_host
-                ));
-            } else {
-                info!(
-                    "{}",
-                    format!(
-                        "Successfully created user directory on NFS host {}.",
-                        current_nfs_host
-                    )
-                );
-            }
-        } else {
-            detected_errors.add_err(format!(
-                "NFS host {} did not return with exit code 0 during directory creation!",
-                current_nfs_host
-            ));
-        }
+        // Give ownership to user
+        change_ownership(
+            sess,
+            config,
+            &directory,
+            entity.username.as_ref(),
+            &entity.group.to_string(),
+        )?
+        .check_success("Ownership change")?;
+        info!(
+            "Successfully created user directory on NFS host {}.",
+            sess.endpoint()
+        );
 
         // Set user quota
         if can_set_quota {
-            let (quota_exit_code, _) = set_quota(
-                &sess,
+            set_quota(
+                sess,
+                config,
                 entity.username.as_ref(),
-                current_quota_nfs_softlimit,
-                current_nfs_quota_nfs_hardlimit,
-                current_nfs_filesystem,
-            )?;
-
-            detected_errors.add_err_if_false(
-                quota_exit_code == 0,
-                format!(
-                    "NFS host {} did not return with exit code 0 during quota setup!",
-                    current_nfs_host
-                ),
-            )
+                &config.quota_nfs_softlimit[i],
+                &config.quota_nfs_hardlimit[i],
+                &config.nfs_filesystem[i],
+            )?
+            .check_success("Quota setup")?;
         }
+        Ok(())
+    });
+
+    let mut detected_errors =
+        ResultAccumulator::new("Errors during NFS directory creation occurred!".to_owned());
+    for (current_nfs_host, outcome) in outcomes {
+        detected_errors.add_if_err(&format!("NFS host {}", current_nfs_host), outcome);
     }
 
     AppResult::from(detected_errors)?;
@@ -400,12 +342,13 @@ where
     Ok(())
 }
 
-/// Establish SSH connection to home host, make user directory and set quota
-fn handle_home<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+/// Make user home directory and set quota on the home host
+fn handle_home<T>(entity: &NewEntity, pool: &SshConnectionPool<T>) -> AppResult
 where
     T: SshCredentials,
 {
     debug!("Start handling home directory");
+    let config = pool.config();
 
     if config.home_host.is_empty() {
         warn!("No home host provided in config. Unable to create home directory for user.");
@@ -421,122 +364,131 @@ where
         warn!("Hard-/softlimit and/or filesystem for quota isn't properly configured. Refusing to set user quota based on these values. Please check your conf.toml");
     }
 
-    info!(
-        "{}",
-        format!("Connecting to home host {}", &config.home_host)
-    );
-    let sess = SshConnection::new(&config.home_host, config, credentials.clone());
-
-    // Create directory
-    let directory = format!("/home/{}", entity.username);
-
-    let (dir_exit_code, _) = if config.use_homedir_helper {
-        make_home_directory(&sess, entity.username.as_ref())
-    } else {
-        make_directory(&sess, &directory)
-    }?;
-
+    info!("Connecting to home host {}", &config.home_host);
     let mut detected_errors = ResultAccumulator::new(format!(
         "Errors during home directory creation occurred on host {}",
         &config.home_host
     ));
 
-    if dir_exit_code == 0 {
-        // Give ownership to user
-        let (owner_exit_code, _) = change_ownership(
-            &sess,
-            &directory,
-            entity.username.as_ref(),
-            &entity.group.to_string(),
-        )?;
-        if owner_exit_code != 0 {
-            detected_errors.add_err(
-                "Home host did not return with exit code 0 during ownership change!".to_owned(),
-            );
+    pool.with_connection(&config.home_host, |sess| {
+        // Create directory
+        let directory = format!("/home/{}", entity.username);
+
+        let dir_output = if config.use_homedir_helper {
+            make_home_directory(sess, config, entity.username.as_ref())
         } else {
-            info!("Successfully created user home directory.");
+            make_directory(sess, config, &directory)
+        }?;
+
+        let created =
+            detected_errors.add_if_err("Home host", dir_output.check_success("Directory creation"));
+        if created.is_some() {
+            // Give ownership to user
+            let owner_output = change_ownership(
+                sess,
+                config,
+                &directory,
+                entity.username.as_ref(),
+                &entity.group.to_string(),
+            )?;
+            let changed = detected_errors
+                .add_if_err("Home host", owner_output.check_success("Ownership change"));
+            if changed.is_some() {
+                info!("Successfully created user home directory.");
+            }
         }
-    } else {
-        detected_errors.add_err(
-            "Home host did not return with exit code 0 during directory creation!".to_owned(),
-        );
-    }
 
-    // Set user quota
-    if can_set_quota {
-        let (quota_exit_code, _) = set_quota(
-            &sess,
-            entity.username.as_ref(),
-            &config.quota_home_softlimit,
-            &config.quota_home_hardlimit,
-            &config.home_filesystem,
-        )?;
-        detected_errors.add_err_if_false(
-            quota_exit_code == 0,
-            format!(
-                "Home host did not return with exit code 0 (actual exit code: {}) during quota setup!",
-                quota_exit_code
-            ),
-        );
-    }
+        // Set user quota
+        if can_set_quota {
+            let quota_output = set_quota(
+                sess,
+                config,
+                entity.username.as_ref(),
+                &config.quota_home_softlimit,
+                &config.quota_home_hardlimit,
+                &config.home_filesystem,
+            )?;
+            detected_errors.add_if_err("Home host", quota_output.check_success("Quota setup"));
+        }
+        AppResult::Ok(())
+    })?;
 
     AppResult::from(detected_errors)?;
 
     Ok(())
 }
 
-fn make_directory<C>(sess: &SshConnection<C>, directory: &str) -> AppResult<(i32, String)>
+fn make_directory<C>(
+    sess: &SshConnection<C>,
+    config: &MgmtConfig,
+    directory: &str,
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
     debug!("Making directory {}", directory);
 
-    let cmd = format!("sudo mkdir -p {directory}");
-    ssh::run_remote_command(sess, &cmd)
+    let mkdir = tool_path(&config.mkdir_path, "mkdir");
+    run_privileged(sess, config, mkdir, &format!("-p {directory}"))
 }
 
-fn delete_directory<C>(sess: &SshConnection<C>, directory: &str) -> AppResult<(i32, String)>
+fn delete_directory<C>(
+    sess: &SshConnection<C>,
+    config: &MgmtConfig,
+    directory: &str,
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
     debug!("Deleting directory {}", directory);
 
-    let cmd = format!("sudo rm -r {directory}");
-    ssh::run_remote_command(sess, &cmd)
+    let rm = tool_path(&config.rm_path, "rm");
+    run_privileged(sess, config, rm, &format!("-r {directory}"))
 }
 
-fn make_home_directory<C>(sess: &SshConnection<C>, username: &str) -> AppResult<(i32, String)>
+fn make_home_directory<C>(
+    sess: &SshConnection<C>,
+    config: &MgmtConfig,
+    username: &str,
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
     debug!("Making home directory using the mkhomedir_helper utility");
 
-    let cmd = format!("sudo mkhomedir_helper {username}");
-    ssh::run_remote_command(sess, &cmd)
+    let mkhomedir_helper = tool_path(&config.mkhomedir_helper_path, "mkhomedir_helper");
+    run_privileged(sess, config, mkhomedir_helper, username)
 }
 
 fn change_ownership<C>(
     sess: &SshConnection<C>,
+    config: &MgmtConfig,
     directory: &str,
     username: &str,
     group: &str,
-) -> AppResult<(i32, String)>
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
     debug!("Changing ownership for directory {}", directory);
 
-    let cmd = format!("sudo chown {username}:{group} {directory}");
-    ssh::run_remote_command(sess, &cmd)
+    let chown = tool_path(&config.chown_path, "chown");
+    run_privileged(
+        sess,
+        config,
+        chown,
+        &format!("{username}:{group} {directory}"),
+    )
 }
 
 fn set_quota<C>(
     sess: &SshConnection<C>,
+    config: &MgmtConfig,
     username: &str,
     softlimit: &str,
     hardlimit: &str,
     filesystem: &str,
-) -> AppResult<(i32, String)>
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
@@ -545,7 +497,37 @@ where
         username, filesystem
     );
 
-    let cmd = format!("sudo setquota -u {username} {softlimit} {hardlimit} 0 0 {filesystem}");
+    let setquota = tool_path(&config.setquota_path, "setquota");
+    let args = format!("-u {username} {softlimit} {hardlimit} 0 0 {filesystem}");
+    run_privileged(sess, config, setquota, &args)
+}
+
+/// Returns the configured path of a tool or its name, so that it is found via PATH on the remote host.
+fn tool_path<'a>(configured: &'a Option<String>, name: &'a str) -> &'a str {
+    configured.as_deref().unwrap_or(name)
+}
 
-    ssh::run_remote_command(sess, &cmd)
+/// Runs `tool` with `args` with root privileges as configured by `privilege_escalation`.
+///
+/// # Errors
+///
+/// - If the password for `sudo -S` could not be retrieved from the credentials.
+/// - If the execution of the remote command fails. See [`ssh::run_remote_command`].
+fn run_privileged<C>(
+    sess: &SshConnection<C>,
+    config: &MgmtConfig,
+    tool: &str,
+    args: &str,
+) -> AppResult<RemoteOutput>
+where
+    C: SshCredentials,
+{
+    let escalation = config.privilege_escalation;
+    let cmd = escalation.command(tool, args);
+    if escalation.needs_password() {
+        let password = format!("{}\n", sess.password()?);
+        ssh::run_remote_command_with_input(sess, &cmd, Some(&password))
+    } else {
+        ssh::run_remote_command(sess, &cmd)
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
     cli::{CommonUserFields, Modifiable, UserToAdd},
     prelude::AppError,
+    slurm::{AccountMembership, SlurmLimit},
     util::{ResolvedGid, ValidGroupOfQos, ValidQos},
 };
 use anyhow::{ensure, Context};
@@ -22,6 +23,14 @@ pub struct Entity {
     /// TODO: Add validation if a present publickey is in valid format, OpenSsh
     pub publickey: Option<TrimmedNonEmptyText>,
     pub qos: Option<ValidGroupOfQos>,
+    /// Limits on the association of the user in Slurm. Empty if no limit is to be set.
+    pub limits: Vec<SlurmLimit>,
+    /// Associations of the user with accounts in Slurm which are to be added
+    pub accounts: Vec<AccountMembership>,
+    pub default_account: Option<TrimmedNonEmptyText>,
+    /// Associations of the user with accounts in Slurm which are to be removed.
+    /// Only used for modifications.
+    pub remove_accounts: Vec<AccountMembership>,
 }
 
 impl Entity {
@@ -57,7 +66,8 @@ impl Entity {
         config: &MgmtConfig,
         on_load_pubkey: impl Fn(&Path) -> AppResult<String>,
     ) -> AppResult<Self> {
-        let (username, group, mail, default_qos, publickey, qos) = to_add.into();
+        let (username, group, mail, default_qos, publickey, qos, limits, accounts, default_account) =
+            to_add.into();
         let group = group
             .map(|group| {
                 let group_id = Group::from_str(group.as_ref().as_str())
@@ -107,6 +117,10 @@ impl Entity {
             publickey,
             qos,
             mail,
+            limits,
+            accounts,
+            default_account,
+            remove_accounts: Vec::new(),
         })
     }
 
@@ -114,8 +128,12 @@ impl Entity {
     ///
     /// See [`Entity::new`]
     pub fn new_modifiable_conf(modif: Modifiable, conf: &MgmtConfig) -> AppResult<Self> {
-        let (firstname, lastname, common_user_fields) = modif.into();
-        Self::new(firstname, lastname, common_user_fields, conf)
+        let (firstname, lastname, common_user_fields, remove_accounts) = modif.into();
+        let entity = Self::new(firstname, lastname, common_user_fields, conf)?;
+        Ok(Self {
+            remove_accounts,
+            ..entity
+        })
     }
 
     /// # Errors
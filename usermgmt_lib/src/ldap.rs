@@ -4,6 +4,7 @@ mod ldap_paths;
 mod ldap_search_result;
 mod ldap_session;
 mod ldap_simple_credential;
+mod reusable_ldap_session;
 pub mod text_list_output;
 
 use chrono::Utc;
@@ -11,8 +12,11 @@ pub use ldap_config::LDAPConfig;
 pub use ldap_credential::LdapCredential;
 pub use ldap_search_result::LdapSearchResult;
 pub use ldap_session::LdapSession;
+#[cfg(feature = "async")]
+pub(crate) use ldap_session::{is_lost_connection, is_unsent_request};
 pub use ldap_simple_credential::LdapSimpleCredential;
 use once_cell::sync::Lazy;
+pub use reusable_ldap_session::ReusableLdapSession;
 
 #[cfg(test)]
 pub mod testing;
@@ -21,7 +25,7 @@ use crate::util::{get_new_uid, hashset_from_vec_str};
 use crate::{prelude::*, NewEntity};
 use crate::{ChangesToUser, MgmtConfig};
 use ldap3::controls::{MakeCritical, RelaxRules};
-use ldap3::{LdapConn, LdapError, LdapResult, Mod, Scope, SearchEntry, SearchResult};
+use ldap3::{LdapConn, LdapError, LdapResult, Mod, ResultEntry, Scope, SearchEntry};
 use log::{debug, info, warn};
 use maplit::hashset;
 use std::collections::HashSet;
@@ -61,111 +65,110 @@ pub fn add_ldap_user<T>(
 where
     T: LdapCredential,
 {
-    let exitence_of_username = username_exists(entity.username.as_ref(), ldap_session.config())?;
-    if exitence_of_username {
-        warn!(
-            "User {} already exists in LDAP. Skipping LDAP user creation.",
-            &entity.username
-        );
+    let username: &str = entity.username.as_ref();
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session)?;
+    if !is_new_user(username, found) {
         return Ok(());
     }
 
-    let uid_number = find_next_available_uid(ldap_session, entity.group.id())
-        .context("No users found or LDAP query failed. Unable to assign uid. Aborting...")?;
+    let uid_entries = search(&LdapSearch::uid_numbers(), ldap_session)?;
+    let (dn, fields) = new_user_entry(entity, config, ldap_session.config().base(), uid_entries)?;
+    ldap_session.action(|connection, _| {
+        let result_from_adding = connection.add(&dn, fields.clone());
+        ldap_is_success(result_from_adding).context("Unable to create LDAP user!")?;
+        Ok(())
+    })?;
 
-    debug!(
-        "LDAP connection established to {}",
-        ldap_session.config().bind()
-    );
+    info!("Added LDAP user {}", entity.username);
+    Ok(())
+}
 
-    add_to_ldap_db(entity, uid_number, ldap_session, config)?;
+/// Multi-valued attribute into which the Slurm limits of a user are mirrored as key=value
+/// if `ldap_add_slurm_limits` is enabled in the configuration.
+pub(crate) const SLURM_LIMIT: &str = "slurmLimit";
 
-    info!("Added LDAP user {}", entity.username);
-    return Ok(());
-
-    fn add_to_ldap_db<T>(
-        entity: &NewEntity,
-        uid: u32,
-        ldap_session: &mut LdapSession<T>,
-        config: &MgmtConfig,
-    ) -> AppResult
-    where
-        T: LdapCredential,
-    {
-        fn add_fields<T>(
-            connection: &mut LdapConn,
-            entity: &NewEntity,
-            ldap_config: &LDAPConfig<T>,
-            fields: Vec<(&str, HashSet<&str>)>,
-        ) -> AppResult
-        where
-            T: LdapCredential,
-        {
-            let result_from_adding = connection.add(
-                &format!("uid={},{}", entity.username, ldap_config.base()),
-                fields,
-            );
-
-            ldap_is_success(result_from_adding).context("Unable to create LDAP user!")?;
-            Ok(())
-        }
+/// Multi-valued attribute with the QOS of a user.
+pub(crate) const SLURM_QOS: &str = "slurmQos";
+
+/// Distinguished name of an user with the name `username` under the `base`.
+pub(crate) fn user_dn(username: &str, base: &str) -> String {
+    format!("uid={},{}", username, base)
+}
+
+/// LDAP search filter which matches exactly the user with the name `username`.
+pub(crate) fn uid_filter(username: &str) -> String {
+    format!("(uid={username})")
+}
+
+/// Attributes with their values of a new user in LDAP.
+pub(crate) type NewUserFields = Vec<(String, HashSet<String>)>;
+
+/// Creates all attributes with values for a new user in LDAP.
+/// The parameter `uid` is the UID number assigned to the new user.
+pub(crate) fn new_user_fields(entity: &NewEntity, uid: u32, config: &MgmtConfig) -> NewUserFields {
+    let un = entity.username.to_string();
+    let mail = entity
+        .mail
+        .as_ref()
+        .map(|trimmmed| trimmmed.to_string())
+        .unwrap_or_default();
+    let qos: HashSet<String> = (&entity.qos)
+        .into_iter()
+        .map(|qos| qos.to_string())
+        .collect();
+    let pubkey = entity
+        .publickey
+        .as_ref()
+        .map(|trimmmed| trimmmed.to_string())
+        .unwrap_or_default();
+
+    let mut fields = vec![
+        ("cn", hashset! {un.clone()}),
+        (
+            "objectClass",
+            hashset_from_vec_str(&config.objectclass_common)
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect(),
+        ),
+        ("gidNumber", hashset! {entity.group.gid().to_string()}),
+        ("uidNumber", hashset! {uid.to_string()}),
+        ("uid", hashset! {un}),
+        ("sn", hashset! {entity.lastname.to_string()}),
+        ("givenName", hashset! {entity.firstname.to_string()}),
+        ("mail", hashset! {mail}),
+        ("slurmDefaultQos", hashset! {entity.default_qos.to_string()}),
+        (
+            "homeDirectory",
+            hashset! {format!("/home/{}", entity.username)},
+        ),
+        ("slurmQos", qos),
+        ("sshPublicKey", hashset! {pubkey}),
+        ("loginShell", hashset! {config.login_shell.clone()}),
+    ];
+
+    if config.ldap_add_created_at {
+        fields.push(("createdAt", hashset! {Utc::now().to_rfc3339()}));
+    }
+
+    if config.ldap_add_slurm_limits && !entity.limits.is_empty() {
+        let limits = entity.limits.iter().map(ToString::to_string).collect();
+        fields.push((SLURM_LIMIT, limits));
+    }
 
-        let un = entity.username.as_ref().as_str();
-        let gid = entity.group.gid().to_string();
-        let uid = uid.to_string();
-        let ln = entity.lastname.as_ref().as_str();
-        let gn = entity.firstname.as_ref().as_str();
-        let mail: &str = entity
-            .mail
-            .as_ref()
-            .map(|trimmmed| trimmmed.as_ref().as_str())
-            .unwrap_or("");
-
-        let def_qos = entity.default_qos.as_ref().as_str();
-        let home = &format!("/home/{}", entity.username);
-        let qos: HashSet<&str> = (&entity.qos)
-            .into_iter()
-            .map(|qos| qos.as_ref().as_str())
+    if let Some(attribute) = &config.ldap_slurm_account_attribute {
+        let accounts = entity
+            .accounts
+            .iter()
+            .map(|membership| membership.account.to_string())
             .collect();
-        let pubkey = entity
-            .publickey
-            .as_ref()
-            .map(|trimmmed| trimmmed.as_ref().as_str())
-            .unwrap_or("");
-
-        ldap_session.action(|connection, ldap_config| {
-            let mut fields = vec![
-                ("cn", hashset! {un}),
-                (
-                    "objectClass",
-                    hashset_from_vec_str(&config.objectclass_common).to_owned(),
-                ),
-                ("gidNumber", hashset! {gid.as_str()}),
-                ("uidNumber", hashset! {uid.as_str()}),
-                ("uid", hashset! {un}),
-                ("sn", hashset! {ln}),
-                ("givenName", hashset! {gn}),
-                ("mail", hashset! {mail}),
-                ("slurmDefaultQos", hashset! {def_qos}),
-                ("homeDirectory", hashset! {home.as_str()}),
-                ("slurmQos", qos),
-                ("sshPublicKey", hashset! {pubkey}),
-                ("loginShell", hashset! {config.login_shell.as_str()}),
-            ];
-
-            if config.ldap_add_created_at {
-                let created_at = Utc::now().to_rfc3339();
-                let attr = hashset! {created_at.as_str()};
-                fields.push(("createdAt", attr));
-
-                add_fields(connection, entity, ldap_config, fields)?;
-                Ok(())
-            } else {
-                add_fields(connection, entity, ldap_config, fields)?;
-                Ok(())
-            }
-        })
+        fields.push((attribute.as_str(), accounts));
     }
+
+    fields
+        .into_iter()
+        .map(|(key, values)| (key.to_owned(), values))
+        .collect()
 }
 
 /// # Errors
@@ -176,63 +179,43 @@ pub fn delete_ldap_user<T>(username: &str, ldap_session: &mut LdapSession<T>) ->
 where
     T: LdapCredential,
 {
-    let dn = find_dn_by_uid(username, ldap_session)
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session)?;
+    let dn_to_delete = dn_from_search(username, found)
         .with_context(|| format!("No DN found for username {}!", username))?;
-    debug!(
-        "LDAP connection established to {}",
-        ldap_session.config().bind()
-    );
 
-    match &dn {
-        Some(dn_to_delete) => {
-            ldap_session.action(|ldap, _| {
-                let result = ldap.delete(dn_to_delete);
-                ldap_is_success(result)?;
-                info!("Successfully deleted DN {}", dn_to_delete);
-                Ok(())
-            })?;
-        }
-        None => {
-            warn!("No dn found to delete under the username {}", username);
-        }
-    }
+    ldap_session.action(|ldap, _| {
+        let result = ldap.delete(&dn_to_delete);
+        ldap_is_success(result)?;
+        Ok(())
+    })?;
+    info!("Successfully deleted DN {}", dn_to_delete);
 
     Ok(())
 }
 
 /// # Errors
 ///
-/// - If finding the DN-LDAP  by the UID fails. See [`find_dn_by_uid`]
-/// - If finding the quality of service by the UID fails. See [`find_qos_by_uid`]
+/// - If searching the user with the old values of the changed attributes fails.
+/// - If the user is not found. See [`modification_from_search`]
+/// - If the modification of the user in the LDAP database failed.
 pub fn modify_ldap_user<T>(
     modifiable: &ChangesToUser,
+    config: &MgmtConfig,
     ldap_session: &mut LdapSession<T>,
 ) -> AppResult
 where
     T: LdapCredential,
 {
-    let dn = find_dn_by_uid(modifiable.username.as_ref(), ldap_session)
-        .with_context(|| {
-            format!(
-                "No DN found for username {}! Unable to modify user.",
-                modifiable.username.as_ref()
-            )
-        })?
-        .ok_or(anyhow!("No dn found for uid"))?;
-
-    // Prepare replace operation
-
-    let old_qos = match &modifiable.qos {
-        Some(_) => find_qos_by_uid(modifiable.username.as_ref(), ldap_session),
-        None => Ok(Vec::default()),
-    }?;
-    let mod_vec = make_modification_vec(modifiable, &old_qos);
+    let found = search(
+        &LdapSearch::for_modification(modifiable, config),
+        ldap_session,
+    )?;
+    let (dn, mod_vec) = modification_from_search(modifiable, config, found)?;
 
-    // Replace userPassword at given dn
     ldap_session.action(|ldap_connection, _| {
         let result = ldap_connection
             .with_controls(RelaxRules.critical())
-            .modify(&dn, mod_vec);
+            .modify(&dn, mod_vec.clone());
         ldap_is_success(result).context("User modification in LDAP failed!")
     })?;
 
@@ -246,52 +229,223 @@ where
 ///
 /// # Errors
 ///
-/// - If the connection to the LDAP instance fails. See [`make_ldap_connection`]
+/// - If the connection to the LDAP instance fails. See [`LdapSession::action`]
 /// - If the searching in LDAP failed
-pub fn list_ldap_users<T>(ldap_config: &LDAPConfig<T>) -> AppResult<LdapSearchResult>
+pub fn list_ldap_users<T>(ldap_session: &mut LdapSession<T>) -> AppResult<LdapSearchResult>
 where
     T: LdapCredential,
 {
-    // Establish LDAP connection and bind
-    let mut ldap =
-        make_ldap_connection(ldap_config).context("Error while connecting via LDAP !")?;
-
     debug!(
-        "LDAP connection established to {}. Will search under {}",
-        ldap_config.bind(),
-        ldap_config.base()
+        "Search for all users under {}",
+        ldap_session.config().base()
     );
+    let found = search(&LdapSearch::listing(), ldap_session)?;
+    Ok(users_from_search(found))
+}
+
+/// Search one level under the base of the LDAP configuration.
+///
+/// All searches of the LDAP operations are defined by the constructors of this type.
+/// This way the blocking operations of this module and
+/// the non-blocking ones of [`crate::non_blocking`] only differ in how a search is sent.
+#[derive(Debug, Clone)]
+pub(crate) struct LdapSearch<'a> {
+    pub filter: String,
+    pub attributes: Vec<&'a str>,
+}
+
+impl<'a> LdapSearch<'a> {
+    /// Search for the user named `username` which returns the given `attributes`.
+    pub fn by_uid(username: &str, attributes: Vec<&'a str>) -> Self {
+        Self {
+            filter: uid_filter(username),
+            attributes,
+        }
+    }
+
+    /// Search for the UID numbers of all users.
+    pub fn uid_numbers() -> Self {
+        Self::all(vec!["uidNumber"])
+    }
+
+    /// Search for all users with the attributes shown in a listing of users.
+    pub fn listing() -> Self {
+        Self::all(SORTED_LDAP_LISTING_ATTRIBUTES.clone())
+    }
+
+    /// Search for the user of `modifiable` which returns the old values needed by
+    /// [`make_modification_vec`].
+    /// Only the attributes whose values are replaced by the modification are requested.
+    pub fn for_modification(modifiable: &ChangesToUser, config: &'a MgmtConfig) -> Self {
+        let mut attributes = Vec::new();
+        if modifiable.qos.is_some() {
+            attributes.push(SLURM_QOS);
+        }
+        if mirrors_slurm_limits(modifiable, config) {
+            attributes.push(SLURM_LIMIT);
+        }
+        if let Some(attribute) = mirrored_account_attribute(modifiable, config) {
+            attributes.push(attribute);
+        }
+        if attributes.is_empty() {
+            // An empty list of attributes would return all attributes.
+            attributes.push("dn");
+        }
+        Self::by_uid(modifiable.username.as_ref(), attributes)
+    }
+
+    fn all(attributes: Vec<&'a str>) -> Self {
+        Self {
+            filter: "(objectclass=*)".to_owned(),
+            attributes,
+        }
+    }
+}
+
+/// Sends `to_search` to the LDAP instance of the session and returns the raw entries found.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails. See [`LdapSession::action`]
+/// - If the searching in LDAP failed
+fn search<T>(
+    to_search: &LdapSearch,
+    ldap_session: &mut LdapSession<T>,
+) -> AppResult<Vec<ResultEntry>>
+where
+    T: LdapCredential,
+{
+    let found = ldap_session.read_only_action(|connection, ldap_config| {
+        connection
+            .search(
+                ldap_config.base(),
+                Scope::OneLevel,
+                &to_search.filter,
+                &to_search.attributes,
+            )
+            .with_context(|| format!("LDAP search with filter {} failed", to_search.filter))
+    })?;
+    Ok(found.0)
+}
+
+/// Negation of [`user_exists_in_search`] which warns that the creation of the user is skipped
+/// if the user exists already.
+pub(crate) fn is_new_user(username: &str, entries: Vec<ResultEntry>) -> bool {
+    let exists = user_exists_in_search(username, entries);
+    if exists {
+        warn!(
+            "User {} already exists in LDAP. Skipping LDAP user creation.",
+            username
+        );
+    }
+    !exists
+}
+
+/// Returns the DN and all attributes of the user `entity` to be added under `base`.
+/// The UID number of the new user is determined from the raw entries of [`LdapSearch::uid_numbers`].
+///
+/// # Errors
+///
+/// - If no valid UID number can be assigned. See [`next_uid_from_search`]
+pub(crate) fn new_user_entry(
+    entity: &NewEntity,
+    config: &MgmtConfig,
+    base: &str,
+    uid_entries: Vec<ResultEntry>,
+) -> AppResult<(String, NewUserFields)> {
+    let uid_number = next_uid_from_search(uid_entries, entity.group.id())
+        .context("No users found or LDAP query failed. Unable to assign uid. Aborting...")?;
+    let dn = user_dn(entity.username.as_ref(), base);
+    Ok((dn, new_user_fields(entity, uid_number, config)))
+}
 
-    let attrs = SORTED_LDAP_LISTING_ATTRIBUTES.as_slice();
-    // Search for all entities under base dn
-    let search_result = ldap
-        .search(
-            ldap_config.base(),
-            Scope::OneLevel,
-            "(objectclass=*)",
-            attrs,
+/// Returns the DN of the user and the modifications of [`make_modification_vec`]
+/// from the raw entries of [`LdapSearch::for_modification`].
+///
+/// # Errors
+///
+/// - If the search found no user.
+pub(crate) fn modification_from_search(
+    modifiable: &ChangesToUser,
+    config: &MgmtConfig,
+    entries: Vec<ResultEntry>,
+) -> AppResult<(String, Vec<Mod<String>>)> {
+    let username: &str = modifiable.username.as_ref();
+    let entry = entries.into_iter().next().ok_or_else(|| {
+        anyhow!(
+            "No DN found for username {}! Unable to modify user.",
+            username
         )
-        .context("Error during LDAP search!")?;
+    })?;
+    let entry = SearchEntry::construct(entry);
+    debug!("Search result for modification: {:?}", entry);
+
+    let old_values = |attribute: &str| entry.attrs.get(attribute).cloned().unwrap_or_default();
+    let old_qos = old_values(SLURM_QOS);
+    let old_limits = mirrors_slurm_limits(modifiable, config).then(|| old_values(SLURM_LIMIT));
+    let old_accounts = mirrored_account_attribute(modifiable, config).map(old_values);
+    let mod_vec = make_modification_vec(
+        modifiable,
+        config,
+        &old_qos,
+        old_limits.as_deref(),
+        old_accounts.as_deref(),
+    );
+    Ok((entry.dn, mod_vec))
+}
+
+/// Converts the raw entries of [`LdapSearch::listing`] into a table of users.
+pub(crate) fn users_from_search(entries: Vec<ResultEntry>) -> LdapSearchResult {
+    let rows = entries
+        .into_iter()
+        .map(|entry| SearchEntry::construct(entry).attrs);
+    LdapSearchResult::new(SORTED_LDAP_LISTING_ATTRIBUTES.iter(), rows)
+}
 
-    let search_result = LdapSearchResult::from_ldap_raw_search(attrs.iter(), &search_result);
+/// Returns true if the Slurm limits of `modifiable` are to be mirrored into LDAP.
+pub(crate) fn mirrors_slurm_limits(modifiable: &ChangesToUser, config: &MgmtConfig) -> bool {
+    config.ldap_add_slurm_limits && !modifiable.limits.is_empty()
+}
 
-    Ok(search_result)
+/// Returns the LDAP attribute into which the Slurm accounts of users are mirrored
+/// if the accounts of `modifiable` change and the attribute is configured.
+pub(crate) fn mirrored_account_attribute<'a>(
+    modifiable: &ChangesToUser,
+    config: &'a MgmtConfig,
+) -> Option<&'a str> {
+    let accounts_change = !modifiable.accounts.is_empty() || !modifiable.remove_accounts.is_empty();
+    config
+        .ldap_slurm_account_attribute
+        .as_deref()
+        .filter(|_| accounts_change)
 }
 
 /// Creates modification parameters which are used by `ldap3` library to modify an user in LDAP.
-fn make_modification_vec<'a>(
-    modifiable: &'a ChangesToUser,
-    old_qos: &'a Vec<String>,
-) -> Vec<Mod<&'a str>> {
+///
+/// The Slurm limits are only mirrored into LDAP if `old_limits` is given.
+/// Old values of a limit are replaced by the new value. A new value of -1 only removes the old values.
+///
+/// The Slurm accounts are only mirrored into LDAP if `old_accounts` is given.
+/// An account is only removed if its association is removed without a partition.
+pub(crate) fn make_modification_vec(
+    modifiable: &ChangesToUser,
+    config: &MgmtConfig,
+    old_qos: &[String],
+    old_limits: Option<&[String]>,
+    old_accounts: Option<&[String]>,
+) -> Vec<Mod<String>> {
     macro_rules! may_push_simple_modification {
         ($name:expr, $modifiable:ident, $modification:ident, $field:ident) => {
             if let Some(val) = &$modifiable.$field {
                 info_log($name);
-                ($modification).push(Mod::Replace($name, HashSet::from([val.as_ref().as_str()])))
+                ($modification).push(Mod::Replace(
+                    $name.to_owned(),
+                    HashSet::from([val.to_string()]),
+                ))
             }
         };
     }
-    let mut modifications: Vec<Mod<&str>> = Vec::new();
+    let mut modifications: Vec<Mod<String>> = Vec::new();
 
     let modifiable = modifiable.as_ref();
     may_push_simple_modification!("givenName", modifiable, modifications, firstname);
@@ -303,15 +457,65 @@ fn make_modification_vec<'a>(
     let replace_old_with_new_qos = !old_qos.is_empty();
     if replace_old_with_new_qos {
         // first we delete all old qos
-        const SLURM_QOS: &str = "slurmQos";
         info_log(SLURM_QOS);
         for q in old_qos {
-            modifications.push(Mod::Delete(SLURM_QOS, HashSet::from([q.as_str()])))
+            modifications.push(Mod::Delete(
+                SLURM_QOS.to_owned(),
+                HashSet::from([q.clone()]),
+            ))
         }
         // then we add all new qos
         for q in modifiable.qos.iter() {
-            let q: HashSet<&str> = q.into_iter().map(|qos| qos.as_ref().as_str()).collect();
-            modifications.push(Mod::Add(SLURM_QOS, q))
+            let q: HashSet<String> = q.into_iter().map(|qos| qos.to_string()).collect();
+            modifications.push(Mod::Add(SLURM_QOS.to_owned(), q))
+        }
+    }
+
+    if let Some(old_limits) = old_limits {
+        info_log(SLURM_LIMIT);
+        for limit in modifiable.limits.iter() {
+            let prefix = format!("{}=", limit.key()).to_lowercase();
+            let outdated: HashSet<String> = old_limits
+                .iter()
+                .filter(|old| old.to_lowercase().starts_with(&prefix))
+                .cloned()
+                .collect();
+            if !outdated.is_empty() {
+                modifications.push(Mod::Delete(SLURM_LIMIT.to_owned(), outdated));
+            }
+            if !limit.is_unset() {
+                modifications.push(Mod::Add(
+                    SLURM_LIMIT.to_owned(),
+                    HashSet::from([limit.to_string()]),
+                ));
+            }
+        }
+    }
+
+    if let (Some(attribute), Some(old_accounts)) =
+        (config.ldap_slurm_account_attribute.as_deref(), old_accounts)
+    {
+        let old_accounts: HashSet<String> = old_accounts.iter().cloned().collect();
+        let removed: HashSet<String> = modifiable
+            .remove_accounts
+            .iter()
+            .filter(|membership| membership.partition.is_none())
+            .map(|membership| membership.account.to_string())
+            .collect();
+        let new_accounts: HashSet<String> = old_accounts
+            .iter()
+            .cloned()
+            .chain(
+                modifiable
+                    .accounts
+                    .iter()
+                    .map(|membership| membership.account.to_string()),
+            )
+            .filter(|account| !removed.contains(account))
+            .collect();
+        if new_accounts != old_accounts {
+            info_log(attribute);
+            modifications.push(Mod::Replace(attribute.to_owned(), new_accounts));
         }
     }
     return modifications;
@@ -327,7 +531,7 @@ fn make_modification_vec<'a>(
 /// # Errors
 ///
 /// - If establishing the connection to the LDAP instance fails.
-/// - If the new UID can not be valid. See [`get_new_uid`] for more details
+/// - If the new UID can not be valid. See [`next_uid_from_search`] for more details
 pub fn find_next_available_uid<T>(
     ldap_session: &mut LdapSession<T>,
     group: crate::Group,
@@ -335,31 +539,36 @@ pub fn find_next_available_uid<T>(
 where
     T: LdapCredential,
 {
-    {
-        let config = ldap_session.config();
-        debug!(
-            "find_next_available_uid: LDAP connection established to {}",
-            config.bind(),
-        );
+    debug!("Search for UIDs under {}", ldap_session.config().base());
+    let found = search(&LdapSearch::uid_numbers(), ldap_session)?;
+    next_uid_from_search(found, group)
+}
 
-        debug!("Search under {}", config.base());
-    }
+/// Returns the next available UID in the range of `group`
+/// from the raw entries of [`LdapSearch::uid_numbers`].
+///
+/// # Errors
+///
+/// - If an entry has no UID number or it is not a valid unsigned integer.
+/// - If the new UID can not be valid. See [`get_new_uid`] for more details
+pub(crate) fn next_uid_from_search(
+    entries: Vec<ResultEntry>,
+    group: crate::Group,
+) -> AppResult<u32> {
+    let uids = uids_from_search(entries)?;
+    get_new_uid(&uids, group)
+}
 
-    // Search for all uidNumbers under base dn
-    let search_result = ldap_session.action(|connection, config| {
-        connection
-            .search(
-                config.base(),
-                Scope::OneLevel,
-                "(objectclass=*)",
-                vec!["uidNumber"],
-            )
-            .context("Error during uid search!")
-    })?;
+/// Parses all UID numbers from the raw entries of a LDAP search for the attribute `uidNumber`.
+///
+/// # Errors
+///
+/// - If an entry has no UID number or it is not a valid unsigned integer.
+fn uids_from_search(entries: Vec<ResultEntry>) -> AppResult<Vec<u32>> {
     let mut uids: Vec<u32> = Vec::new();
-    for elem in search_result.0.iter() {
-        let search_result = SearchEntry::construct(elem.to_owned());
-        debug!("UID: {:?}", SearchEntry::construct(elem.to_owned()));
+    for elem in entries {
+        let search_result = SearchEntry::construct(elem);
+        debug!("UID: {:?}", search_result);
         let uid = {
             const ATTRIBUTE: &str = "uidNumber";
             let unparsed = &search_result.attrs[ATTRIBUTE].first().ok_or_else(|| {
@@ -373,8 +582,7 @@ where
 
         uids.push(uid);
     }
-
-    get_new_uid(&uids, group)
+    Ok(uids)
 }
 
 /// Search for a specific UID and return the corresponding dn.
@@ -390,24 +598,17 @@ pub fn find_dn_by_uid<T>(
 where
     T: LdapCredential,
 {
-    debug!(
-        "LDAP connection established to {}",
-        ldap_session.config().bind()
-    );
-
-    // Search for all uids under base dn and return dn of user
-    let search: SearchResult = ldap_session.action(|con, config| {
-        con.search(
-            config.base(),
-            Scope::OneLevel,
-            &format!("(uid={username})"),
-            vec!["dn"],
-        )
-        .context("LDAP search failed")
-    })?;
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session)?;
+    dn_from_search(username, found).map(Some)
+}
 
-    let entry = search
-        .0
+/// Returns the DN of the user named `username` from the raw entries of a search for this user.
+///
+/// # Errors
+///
+/// - If nothing was found for the user.
+pub(crate) fn dn_from_search(username: &str, entries: Vec<ResultEntry>) -> AppResult<String> {
+    let entry = entries
         .into_iter()
         .next()
         .with_context(|| format!("No LDAP entry found for user {}", username))?;
@@ -415,7 +616,7 @@ where
     let sr = SearchEntry::construct(entry);
     debug!("Search result for deletion: {:?}", sr);
 
-    Ok(Some(sr.dn))
+    Ok(sr.dn)
 }
 
 /// Search for a specific uid and return the corresponding qos.
@@ -430,40 +631,47 @@ pub fn find_qos_by_uid<T>(
 where
     T: LdapCredential,
 {
-    let mut fetched_all_qos: Vec<String> = Vec::new();
+    find_attribute_by_uid(username, SLURM_QOS, ldap_session)
+}
 
-    debug!(
-        "LDAP connection established to {}",
-        ldap_session.config().bind()
-    );
+/// Returns the Slurm limits of a user which are mirrored into LDAP as key=value.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails
+pub fn find_slurm_limits_by_uid<T>(
+    username: &str,
+    ldap_session: &mut LdapSession<T>,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    find_attribute_by_uid(username, SLURM_LIMIT, ldap_session)
+}
 
-    // Search for all uid under base dn and return dn of user
-    let search = ldap_session.action(|ldap_connection, ldap_config| {
-        ldap_connection
-            .search(
-                ldap_config.base(),
-                Scope::OneLevel,
-                &format!("(uid={})", username),
-                vec!["slurmQos"],
-            )
-            .with_context(|| {
-                format!(
-                    "search did not find any slurmQos for the user with uid {}",
-                    username
-                )
-            })
-    })?;
+fn find_attribute_by_uid<T>(
+    username: &str,
+    attribute: &str,
+    ldap_session: &mut LdapSession<T>,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    let found = search(&LdapSearch::by_uid(username, vec![attribute]), ldap_session)?;
+    Ok(values_from_search(found, attribute))
+}
 
-    for elem in search.0.iter() {
-        let search_result = SearchEntry::construct(elem.to_owned());
-        let q = &search_result.attrs["slurmQos"];
-        for one_qos in q {
-            debug!("Fetched QOS: {:?}", one_qos);
-            fetched_all_qos.push(one_qos.clone());
+/// Collects all values of the attribute `attribute` from the raw entries of a LDAP search.
+pub(crate) fn values_from_search(entries: Vec<ResultEntry>, attribute: &str) -> Vec<String> {
+    let mut fetched_values: Vec<String> = Vec::new();
+    for elem in entries {
+        let search_result = SearchEntry::construct(elem);
+        for value in search_result.attrs.get(attribute).into_iter().flatten() {
+            debug!("Fetched {}: {:?}", attribute, value);
+            fetched_values.push(value.clone());
         }
     }
-
-    Ok(fetched_all_qos)
+    fetched_values
 }
 
 /// Check if username already exists in ldap.
@@ -472,31 +680,26 @@ where
 /// # Errors
 ///
 /// - If the connection to the LDAP instance fails
-/// - If nothing is found in the LDAP query under the given user aka parameter `username`
-pub fn username_exists<T>(username: &String, ldap_config: &LDAPConfig<T>) -> AppResult<bool>
+pub fn username_exists<T>(username: &str, ldap_session: &mut LdapSession<T>) -> AppResult<bool>
 where
     T: LdapCredential,
 {
-    let mut username_exists = false;
-    let mut ldap = make_ldap_connection(ldap_config)?;
-    debug!("LDAP connection established to {}", ldap_config.bind());
-
-    // Search for all uid under base dn and return dn of user
-    let search_result = ldap.search(
-        ldap_config.base(),
-        Scope::OneLevel,
-        &format!("(uid={username})"),
-        vec!["dn"],
-    )?;
-    match search_result.0.into_iter().next() {
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session)?;
+    Ok(user_exists_in_search(username, found))
+}
+
+/// Returns true if the raw entries of a search for the user named `username` contain the user.
+pub(crate) fn user_exists_in_search(username: &str, entries: Vec<ResultEntry>) -> bool {
+    match entries.into_iter().next() {
         Some(entry) => {
-            // User found. Good.
             debug!("Found user: {:?}", SearchEntry::construct(entry));
-            username_exists = true
+            true
+        }
+        None => {
+            debug!("No LDAP entry found for user {}", username);
+            false
         }
-        None => debug!("No LDAP entry found for user {}", username),
     }
-    Ok(username_exists)
 }
 
 /// If ok is returned then ldap operation happened with zero error code, LDAP_SUCCESS
@@ -504,7 +707,7 @@ where
 /// Even if a call to ldap returns ok it has an error code inside it. Only if the code is zero
 /// then the operation really happened successfully.
 /// Link: https://docs.rs/ldap3/latest/ldap3/result/struct.LdapResult.html
-fn ldap_is_success(to_check: Result<LdapResult, LdapError>) -> Result<(), LdapError> {
+pub(crate) fn ldap_is_success(to_check: Result<LdapResult, LdapError>) -> Result<(), LdapError> {
     match to_check {
         Ok(might_have_non_zero_error_code) => match might_have_non_zero_error_code.success() {
             Ok(_with_zero_error_code) => Ok(()),
@@ -536,7 +739,7 @@ where
     Ok((ldap_user.trim().to_owned(), ldap_pass.trim().to_owned()))
 }
 
-static SORTED_LDAP_LISTING_ATTRIBUTES: Lazy<Vec<&str>> = Lazy::new(|| {
+pub(crate) static SORTED_LDAP_LISTING_ATTRIBUTES: Lazy<Vec<&str>> = Lazy::new(|| {
     // Make sure the keys are sorted alphabetic
     // This way the order fields in the final output deterministic
     let mut to_sort = vec![
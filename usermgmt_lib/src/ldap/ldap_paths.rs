@@ -27,13 +27,12 @@ impl LdapPaths {
         prefix: Option<String>,
         username: String,
     ) -> Self {
-        let org_unit = by_config_or_default(&org_unit, DEFAULT_ORG_UNIT);
         let prefix = by_config_or_default(&prefix, DEFAULT_BIND_PREFIX);
         let bind_org_unit = by_config_or_default(&bind, DEFAULT_BIND_ORG_UNIT);
         // create ldap paths by concat ldap components by comma
         let ldap_prefix_with_user_name = format!("{}={}", prefix, username);
+        let ldap_base = base_dn(dc.as_deref(), org_unit.as_deref());
         let ldap_dc = dc.unwrap_or_default();
-        let ldap_base = concat_by_comma_if_both_not_empty(org_unit, ldap_dc.clone());
         let ldap_bind = {
             let dc_and_bind_org = concat_by_comma_if_both_not_empty(bind_org_unit, ldap_dc.clone());
             concat_by_comma_if_both_not_empty(ldap_prefix_with_user_name, dc_and_bind_org)
@@ -58,13 +57,23 @@ impl LdapPaths {
                 None => default_val.to_owned(),
             }
         }
-        fn concat_by_comma_if_both_not_empty(left_part: String, right_part: String) -> String {
-            match (left_part.is_empty(), right_part.is_empty()) {
-                (true, true) => String::new(),
-                (true, false) => right_part,
-                (false, true) => left_part,
-                (false, false) => format!("{},{}", left_part, right_part),
-            }
-        }
+    }
+}
+
+/// Returns the DN under which users are created, deleted and modified.
+/// It consists of the org unit `org_unit` and the domain components `dc`.
+pub fn base_dn(dc: Option<&str>, org_unit: Option<&str>) -> String {
+    concat_by_comma_if_both_not_empty(
+        org_unit.unwrap_or(DEFAULT_ORG_UNIT).to_owned(),
+        dc.unwrap_or_default().to_owned(),
+    )
+}
+
+fn concat_by_comma_if_both_not_empty(left_part: String, right_part: String) -> String {
+    match (left_part.is_empty(), right_part.is_empty()) {
+        (true, true) => String::new(),
+        (true, false) => right_part,
+        (false, true) => left_part,
+        (false, false) => format!("{},{}", left_part, right_part),
     }
 }
@@ -1,6 +1,6 @@
 use crate::{AppError, AppResult};
-use anyhow::anyhow;
-use ldap3::LdapConn;
+use ldap3::{LdapConn, LdapError};
+use log::{debug, warn};
 
 use crate::config::MgmtConfig;
 use crate::ldap;
@@ -10,8 +10,13 @@ use super::{LDAPConfig, LdapCredential};
 /// API of the ldap crate requires us to mutate the connection
 /// to establish a session.
 /// We can not work with once_cell here because of it.
-type MutableLdapConnection = Option<AppResult<LdapConn>>;
+/// A failed connection attempt is not stored so the next action tries again.
+type MutableLdapConnection = Option<LdapConn>;
 
+/// Bound connection to an LDAP instance which is shared by all LDAP operations.
+/// The connection is established lazily on the first action and reused afterwards.
+/// If the connection turns out to be lost during an action, it is established again
+/// and the action is retried once.
 pub struct LdapSession<T> {
     config: LDAPConfig<T>,
     connection: MutableLdapConnection,
@@ -40,33 +45,149 @@ where
         &self.config
     }
 
+    /// Executes `action` on the connection of this session.
+    /// If the request of `action` could not be sent because the connection was lost,
+    /// the session reconnects and executes the `action` another time.
+    /// A request which was sent is not repeated because LDAP may have applied it already.
+    ///
     /// # Errors
     ///
     /// - If establishing of connection to the LDAP fails
+    /// - If `action` fails. After a lost connection, the error of the retried `action` is returned.
     pub fn action<RT>(
         &mut self,
-        action: impl FnOnce(&mut LdapConn, &LDAPConfig<T>) -> AppResult<RT>,
+        action: impl FnMut(&mut LdapConn, &LDAPConfig<T>) -> AppResult<RT>,
     ) -> Result<RT, AppError> {
-        self.establish_connection()?;
-        let config = &self.config;
-        let connection = {
-            let is_there = self
-                .connection
-                .as_mut()
-                .expect("Is Some because of establishing connection");
-            is_there
-                .as_mut()
-                .expect("Is ok because of establishing connection")
-        };
-        action(connection, config)
+        self.action_with_retry(action, is_unsent_request)
+    }
+
+    /// Same as [`LdapSession::action`] but the `action` is also executed another time
+    /// if the connection was lost after its request was sent.
+    /// Only for actions which do not change anything in LDAP like searches.
+    ///
+    /// # Errors
+    ///
+    /// See [`LdapSession::action`]
+    pub fn read_only_action<RT>(
+        &mut self,
+        action: impl FnMut(&mut LdapConn, &LDAPConfig<T>) -> AppResult<RT>,
+    ) -> Result<RT, AppError> {
+        self.action_with_retry(action, is_lost_connection)
     }
 
+    /// Establishes a connection if there is none or the current one is closed.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection or the bind fails. See [`ldap::make_ldap_connection`]
     pub fn establish_connection(&mut self) -> AppResult {
-        let _ = self
+        if let Some(connection) = self.connection.as_mut() {
+            if !connection.is_closed() {
+                return Ok(());
+            }
+            debug!("LDAP connection is closed. Establishing a new one.");
+        }
+
+        self.connection = Some(ldap::make_ldap_connection(&self.config)?);
+        Ok(())
+    }
+
+    /// Drops the current connection and establishes a new one.
+    ///
+    /// # Errors
+    ///
+    /// See [`LdapSession::establish_connection`]
+    pub fn reconnect(&mut self) -> AppResult {
+        self.connection = None;
+        self.establish_connection()
+    }
+
+    fn action_with_retry<RT>(
+        &mut self,
+        mut action: impl FnMut(&mut LdapConn, &LDAPConfig<T>) -> AppResult<RT>,
+        may_retry: fn(&AppError) -> bool,
+    ) -> AppResult<RT> {
+        match self.action_once(&mut action) {
+            Err(error) if may_retry(&error) => {
+                warn!(
+                    "LDAP connection to {} was lost. Reconnecting and trying again.\nDetails: {}",
+                    self.config.ldap_server(),
+                    error
+                );
+                self.reconnect()?;
+                self.action_once(&mut action)
+            }
+            result => result,
+        }
+    }
+
+    fn action_once<RT>(
+        &mut self,
+        action: &mut impl FnMut(&mut LdapConn, &LDAPConfig<T>) -> AppResult<RT>,
+    ) -> AppResult<RT> {
+        self.establish_connection()?;
+        let connection = self
             .connection
-            .get_or_insert_with(|| ldap::make_ldap_connection(&self.config))
             .as_mut()
-            .map_err(|error| anyhow!("{}", error))?;
-        Ok(())
+            .expect("Is Some because of establishing connection");
+        action(connection, &self.config)
+    }
+}
+
+/// Returns true if the request of an LDAP operation could not be sent
+/// because the connection to the LDAP instance was lost.
+/// LDAP has not applied such an operation, so it can be sent again over a new connection.
+pub(crate) fn is_unsent_request(error: &AppError) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<LdapError>(),
+            Some(LdapError::OpSend { .. })
+        )
+    })
+}
+
+/// Returns true if the error is caused by a broken connection to the LDAP instance
+/// instead of a rejected LDAP operation.
+/// The request may have been sent and applied by LDAP before the connection broke.
+pub(crate) fn is_lost_connection(error: &AppError) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<LdapError>(),
+            Some(
+                LdapError::Io { .. }
+                    | LdapError::OpSend { .. }
+                    | LdapError::ResultRecv { .. }
+                    | LdapError::IdScrubSend { .. }
+                    | LdapError::EndOfStream
+            )
+        )
+    })
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn detect_lost_connection_behind_context() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        let lost: AppResult = Err(LdapError::from(io_error)).context("LDAP search failed");
+        assert!(is_lost_connection(&lost.unwrap_err()));
+    }
+
+    #[test]
+    fn sent_request_is_not_unsent_after_lost_connection() {
+        let lost: AppResult = Err(LdapError::EndOfStream).context("User modification failed");
+        let lost = lost.unwrap_err();
+        assert!(is_lost_connection(&lost));
+        assert!(!is_unsent_request(&lost));
+    }
+
+    #[test]
+    fn not_lost_connection_for_other_errors() {
+        let not_lost: AppResult = Err(LdapError::FilterParsing).context("LDAP search failed");
+        assert!(!is_lost_connection(&not_lost.unwrap_err()));
+        assert!(!is_lost_connection(&anyhow::anyhow!("No dn found for uid")));
     }
 }
@@ -0,0 +1,203 @@
+use std::fmt::Debug;
+
+use log::info;
+
+use crate::{config::MgmtConfig, prelude::AppResult};
+
+use super::{ldap_paths, LdapCredential, LdapSession};
+
+/// Holds the credentials for an LDAP session and opens the session only once it is needed.
+/// After that, the same session is used for all subsequent operations on the same LDAP.
+/// This allows to keep a bound LDAP connection alive across several operations on users.
+pub struct ReusableLdapSession<T> {
+    credentials: T,
+    session: Option<KeptSession<T>>,
+}
+
+/// Opened session with the LDAP it was opened for
+struct KeptSession<T> {
+    target: SessionTarget,
+    session: LdapSession<T>,
+}
+
+/// LDAP and mode for which a session is opened.
+/// A kept session is only reused for the same target.
+#[derive(Debug, PartialEq, Eq)]
+struct SessionTarget {
+    ldap_server: String,
+    base: String,
+    readonly: bool,
+}
+
+impl SessionTarget {
+    fn new(config: &MgmtConfig, readonly: bool) -> Self {
+        Self {
+            ldap_server: config.ldap_server.clone(),
+            base: ldap_paths::base_dn(
+                config.ldap_domain_components.as_deref(),
+                config.ldap_org_unit.as_deref(),
+            ),
+            readonly,
+        }
+    }
+}
+
+impl<T> Debug for ReusableLdapSession<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReusableLdapSession")
+            .field(
+                "opened_for",
+                &self.session.as_ref().map(|kept| &kept.target),
+            )
+            .finish()
+    }
+}
+
+impl<T> ReusableLdapSession<T>
+where
+    T: LdapCredential,
+{
+    pub fn new(credentials: T) -> Self {
+        Self {
+            credentials,
+            session: None,
+        }
+    }
+
+    pub fn credentials(&self) -> &T {
+        &self.credentials
+    }
+
+    /// Returns true if an LDAP session was already opened.
+    pub fn is_open(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Returns true if the session was created from the same username and password
+    /// as given by parameter `other`.
+    pub fn has_same_credentials(&self, other: &T) -> bool {
+        let (own, other) = (&self.credentials, other);
+        matches!(
+            (own.username(), other.username(), own.password(), other.password()),
+            (Ok(own_user), Ok(other_user), Ok(own_pw), Ok(other_pw))
+                if own_user == other_user && own_pw == other_pw
+        )
+    }
+
+    /// Returns the kept session or opens a new one for writing actions.
+    /// A kept session is replaced if it was opened for another LDAP server or base DN
+    /// or with the readonly configuration.
+    ///
+    /// # Errors
+    ///
+    /// - If a new session could not be created. See [`LdapSession::new`]
+    pub fn get_or_open(&mut self, config: &MgmtConfig) -> AppResult<&mut LdapSession<T>> {
+        self.get_or_open_for(SessionTarget::new(config, false), |credentials| {
+            LdapSession::new(config, credentials)
+        })
+    }
+
+    /// Returns the kept session or opens a new one with the readonly configuration.
+    /// A kept session is replaced if it was opened for another LDAP server or base DN
+    /// or for writing actions.
+    ///
+    /// # Errors
+    ///
+    /// - If a new session could not be created. See [`LdapSession::from_ldap_readonly_config`]
+    pub fn get_or_open_readonly(&mut self, config: &MgmtConfig) -> AppResult<&mut LdapSession<T>> {
+        self.get_or_open_for(SessionTarget::new(config, true), |credentials| {
+            LdapSession::from_ldap_readonly_config(config, credentials)
+        })
+    }
+
+    fn get_or_open_for(
+        &mut self,
+        target: SessionTarget,
+        open: impl FnOnce(T) -> AppResult<LdapSession<T>>,
+    ) -> AppResult<&mut LdapSession<T>> {
+        let kept = match self.session.take() {
+            Some(kept) if kept.target == target => kept,
+            outdated => {
+                if let Some(outdated) = outdated {
+                    info!(
+                        "Opening a new LDAP session for {:?} instead of the kept one for {:?}",
+                        target, outdated.target
+                    );
+                }
+                let session = open(self.credentials.clone())?;
+                KeptSession { target, session }
+            }
+        };
+        Ok(&mut self.session.insert(kept).session)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::ldap::LdapSimpleCredential;
+
+    #[test]
+    fn keeps_opened_session_for_same_ldap() {
+        let config = MgmtConfig::default();
+        let mut reusable = ReusableLdapSession::new(LdapSimpleCredential::new(
+            "alice".to_owned(),
+            "xxxx".to_owned(),
+        ));
+        assert!(!reusable.is_open());
+
+        reusable.get_or_open(&config).unwrap();
+        assert!(reusable.is_open());
+        let kept = reusable.get_or_open(&config).unwrap();
+        assert_eq!("alice", kept.config().username());
+    }
+
+    #[test]
+    fn reopens_session_for_other_ldap_or_mode() {
+        let config = MgmtConfig {
+            ldap_readonly_user: Some("reader".to_owned()),
+            ldap_readonly_pw: Some("yyyy".to_owned()),
+            ..Default::default()
+        };
+        let mut reusable = ReusableLdapSession::new(LdapSimpleCredential::new(
+            "alice".to_owned(),
+            "xxxx".to_owned(),
+        ));
+        reusable.get_or_open(&config).unwrap();
+
+        let readonly = reusable.get_or_open_readonly(&config).unwrap();
+        assert_eq!("reader", readonly.config().username());
+        let writing = reusable.get_or_open(&config).unwrap();
+        assert_eq!("alice", writing.config().username());
+
+        let other_server = MgmtConfig {
+            ldap_server: "ldaps://other.test".to_owned(),
+            ..config.clone()
+        };
+        let reopened = reusable.get_or_open(&other_server).unwrap();
+        assert_eq!("ldaps://other.test", reopened.config().ldap_server());
+
+        let other_base = MgmtConfig {
+            ldap_org_unit: Some("ou=guests".to_owned()),
+            ..other_server
+        };
+        let reopened = reusable.get_or_open(&other_base).unwrap();
+        assert_eq!("ou=guests", reopened.config().base());
+    }
+
+    #[test]
+    fn compares_credentials() {
+        let reusable = ReusableLdapSession::new(LdapSimpleCredential::new(
+            "alice".to_owned(),
+            "xxxx".to_owned(),
+        ));
+        assert!(reusable.has_same_credentials(&LdapSimpleCredential::new(
+            "alice".to_owned(),
+            "xxxx".to_owned()
+        )));
+        assert!(!reusable.has_same_credentials(&LdapSimpleCredential::new(
+            "bob".to_owned(),
+            "xxxx".to_owned()
+        )));
+    }
+}
@@ -3,6 +3,7 @@ use crate::ldap::{
 };
 
 use super::*;
+use crate::{cli::CommonUserFields, Entity};
 use maplit::hashmap;
 
 struct ExpectedLdapPaths {
@@ -148,3 +149,33 @@ fn should_produce_table_from_ldap_search() {
     let actual = text_list_output::ldap_search_to_pretty_table(&ldap_search_result);
     insta::assert_display_snapshot!(actual);
 }
+
+#[test]
+fn should_search_only_replaced_attributes_for_modification() {
+    let config = MgmtConfig {
+        ldap_add_slurm_limits: true,
+        ldap_slurm_account_attribute: Some("slurmAccount".to_owned()),
+        ..Default::default()
+    };
+    let changes = |fields: CommonUserFields| {
+        let entity = Entity::new(Some("Janet".try_into().unwrap()), None, fields, &config).unwrap();
+        ChangesToUser::try_new(entity).unwrap()
+    };
+
+    let only_name = changes(CommonUserFields::new("jane".try_into().unwrap()));
+    let search = LdapSearch::for_modification(&only_name, &config);
+    assert_eq!("(uid=jane)", search.filter);
+    assert_eq!(vec!["dn"], search.attributes);
+
+    let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+    fields.qos = vec!["basic".to_owned()];
+    fields.default_qos = Some("basic".try_into().unwrap());
+    fields.limits = vec!["MaxJobs=10".parse().unwrap()];
+    fields.accounts = vec!["gpu".parse().unwrap()];
+    let everything = changes(fields);
+    let search = LdapSearch::for_modification(&everything, &config);
+    assert_eq!(
+        vec![SLURM_QOS, SLURM_LIMIT, "slurmAccount"],
+        search.attributes
+    );
+}
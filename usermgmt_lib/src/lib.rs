@@ -5,6 +5,7 @@ pub use entity::Entity;
 pub use new_entity::NewEntity;
 
 pub mod app_error;
+pub mod backend;
 pub mod changes_to_user;
 pub mod cli;
 pub mod config;
@@ -14,6 +15,8 @@ pub mod entity;
 pub mod ldap;
 pub mod logging;
 pub mod new_entity;
+#[cfg(feature = "async")]
+pub mod non_blocking;
 pub mod operations;
 pub mod slurm;
 pub mod ssh;
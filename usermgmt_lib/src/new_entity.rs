@@ -1,14 +1,17 @@
+use anyhow::ensure;
 use log::warn;
 
 use crate::{
     cli::UserToAdd,
-    config::MgmtConfig,
+    config::{ClusterConfig, MgmtConfig},
     prelude::AppResult,
+    slurm::{AccountMembership, SlurmLimit},
     util::{ResolvedGid, TrimmedNonEmptyText, ValidGroupOfQos, ValidQos},
     Entity, Group,
 };
 
 /// Contains attributes used for adding users in various systems like LDAP or slurm database
+#[derive(Clone)]
 pub struct NewEntity {
     pub username: TrimmedNonEmptyText,
     pub firstname: TrimmedNonEmptyText,
@@ -18,12 +21,17 @@ pub struct NewEntity {
     pub default_qos: ValidQos,
     pub publickey: Option<TrimmedNonEmptyText>,
     pub qos: ValidGroupOfQos,
+    pub limits: Vec<SlurmLimit>,
+    /// Associations with accounts in Slurm. Those with the default account come first.
+    pub accounts: Vec<AccountMembership>,
+    pub default_account: TrimmedNonEmptyText,
 }
 
 impl NewEntity {
     /// # Errors
     ///
     /// - If first or last name is not provided.
+    /// - If the default account is none of the accounts of the user.
     pub fn new(entity: Entity, config: &MgmtConfig) -> AppResult<Self> {
         let (firstname, lastname) = match (entity.firstname, entity.lastname) {
             (Some(first), Some(last)) => Ok((first, last)),
@@ -44,6 +52,26 @@ impl NewEntity {
             .map(Ok)
             .unwrap_or_else(|| ValidGroupOfQos::from_group(group.id(), config))?;
 
+        let mut accounts = if entity.accounts.is_empty() {
+            vec![AccountMembership::new(TrimmedNonEmptyText::try_from(
+                group.id().to_string(),
+            )?)]
+        } else {
+            entity.accounts
+        };
+        let default_account = entity
+            .default_account
+            .unwrap_or_else(|| accounts[0].account.clone());
+        ensure!(
+            accounts
+                .iter()
+                .any(|membership| membership.account == default_account),
+            "Default account {} is none of the accounts of the user",
+            default_account
+        );
+        // Slurm takes the account of the first association as default account of a new user.
+        accounts.sort_by_key(|membership| membership.account != default_account);
+
         if publickey.is_none() {
             warn!("No public key was supplied for new user. Remember to add it later via modification");
         }
@@ -57,6 +85,26 @@ impl NewEntity {
             mail,
             publickey,
             qos,
+            limits: entity.limits,
+            accounts,
+            default_account,
+        })
+    }
+
+    /// Returns the user with the accounts as they are named on `cluster`.
+    ///
+    /// # Errors
+    ///
+    /// - If an account is mapped to an invalid name. See [`ClusterConfig::map_account`]
+    pub fn for_cluster(&self, cluster: &ClusterConfig) -> AppResult<Self> {
+        Ok(Self {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|membership| cluster.map_membership(membership))
+                .collect::<AppResult<_>>()?,
+            default_account: cluster.map_account(&self.default_account)?,
+            ..self.clone()
         })
     }
 
@@ -0,0 +1,322 @@
+//! Non-blocking API for adding, deleting, modifying and listing users.
+//! Only available with the cargo feature "async".
+//!
+//! Adding, deleting and modifying execute the blocking operations of [`crate::operations`]
+//! on the blocking thread pool of tokio, so they behave exactly like their blocking versions.
+//! Listing fetches users from LDAP over the asynchronous connection of the `ldap3` crate
+//! while Slurm is queried on the blocking thread pool.
+//! All functions need to be called within a tokio runtime.
+//!
+//! Every operation can be cancelled via a [`CancellationToken`].
+//! A cancelled operation returns the error [`Cancelled`].
+//! An operation on the blocking thread pool is only cancelled if it has not started yet.
+//! Once started, it finishes.
+
+mod async_ldap;
+mod async_ldap_session;
+
+pub use async_ldap::{
+    add_ldap_user, delete_ldap_user, find_dn_by_uid, find_next_available_uid, find_qos_by_uid,
+    find_slurm_limits_by_uid, list_ldap_users, modify_ldap_user, username_exists,
+};
+pub use async_ldap_session::AsyncLdapSession;
+pub use tokio_util::sync::CancellationToken;
+
+use std::{fmt::Display, future::Future};
+
+use crate::{
+    backend,
+    cli::{OnWhichSystem, UserToAdd},
+    config::MgmtConfig,
+    ldap::LdapCredential,
+    operations::{self, ActiveJobsDecision, ActiveJobsHandler, ListedUsers},
+    prelude::*,
+    slurm::QueuedJob,
+    ssh::{SshConnection, SshCredentials},
+    ChangesToUser,
+};
+
+/// Error of an operation which was cancelled via its [`CancellationToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Asynchronous version of [`crate::operations::add_user`].
+/// The blocking operation is executed on the blocking thread pool. See [`run_blocking`].
+///
+/// # Errors
+///
+/// - If the operation was cancelled before it started. See [`Cancelled`]
+/// - If adding the user fails. See [`operations::add_user`]
+pub async fn add_user<T, C>(
+    to_add: UserToAdd,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    ssh_credentials: C,
+    cancel: &CancellationToken,
+) -> AppResult
+where
+    T: LdapCredential + Send + 'static,
+    C: SshCredentials + 'static,
+{
+    let (on_which_sys, config) = (on_which_sys.clone(), config.clone());
+    run_blocking(cancel, move || {
+        operations::add_user(
+            to_add,
+            &on_which_sys,
+            &config,
+            ldap_credentials,
+            ssh_credentials,
+        )
+    })
+    .await
+}
+
+/// Asynchronous version of [`crate::operations::delete_user`].
+/// The blocking operation is executed on the blocking thread pool. See [`run_blocking`].
+///
+/// If the user has active jobs, `on_active_jobs` decides how to proceed
+/// as an [`operations::ActiveJobsHandler`] would.
+/// The check for active jobs is skipped if `on_active_jobs` is `None`.
+///
+/// # Errors
+///
+/// - If the operation was cancelled before it started. See [`Cancelled`]
+/// - If deleting the user fails. See [`operations::delete_user`]
+pub async fn delete_user<T, C>(
+    user: &str,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    ssh_credentials: C,
+    on_active_jobs: Option<ActiveJobsDecision>,
+    cancel: &CancellationToken,
+) -> AppResult
+where
+    T: LdapCredential + Send + 'static,
+    C: SshCredentials + 'static,
+{
+    let (user, on_which_sys, config) = (user.to_owned(), on_which_sys.clone(), config.clone());
+    run_blocking(cancel, move || {
+        let decide = on_active_jobs.map(|decision| {
+            move |_: &[QueuedJob]| -> AppResult<ActiveJobsDecision> { Ok(decision) }
+        });
+        operations::delete_user(
+            &user,
+            &on_which_sys,
+            &config,
+            ldap_credentials,
+            ssh_credentials,
+            decide.as_ref().map(|decide| decide as ActiveJobsHandler),
+        )
+    })
+    .await
+}
+
+/// Asynchronous version of [`crate::operations::modify_user`].
+/// The blocking operation is executed on the blocking thread pool. See [`run_blocking`].
+///
+/// # Errors
+///
+/// - If the operation was cancelled before it started. See [`Cancelled`]
+/// - If modifying the user fails. See [`operations::modify_user`]
+pub async fn modify_user<T, C>(
+    modifiable: ChangesToUser,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    ssh_credentials: C,
+    cancel: &CancellationToken,
+) -> AppResult
+where
+    T: LdapCredential + Send + 'static,
+    C: SshCredentials + 'static,
+{
+    let (on_which_sys, config) = (on_which_sys.clone(), config.clone());
+    run_blocking(cancel, move || {
+        operations::modify_user(
+            modifiable,
+            &on_which_sys,
+            &config,
+            ldap_credentials,
+            ssh_credentials,
+        )
+    })
+    .await
+}
+
+/// Asynchronous version of [`crate::operations::print_list_of_users_to_stdout`].
+/// Returns the users instead of printing them.
+/// Users from LDAP and Slurm are fetched concurrently.
+///
+/// # Errors
+///
+/// - If the operation was cancelled. See [`Cancelled`]
+/// - If listing users in LDAP fails. See [`list_ldap_users`]
+/// - If listing associations in Slurm fails. See [`backend::AccountingBackend::list_associations`]
+pub async fn list_users<T, C>(
+    config: &MgmtConfig,
+    on_which_sys: &OnWhichSystem,
+    ldap_credentials: T,
+    ssh_credentials: C,
+    cancel: &CancellationToken,
+) -> AppResult<ListedUsers>
+where
+    T: LdapCredential,
+    C: SshCredentials + Send + 'static,
+{
+    run_cancellable(cancel, async {
+        let ldap = async {
+            if on_which_sys.ldap() {
+                let mut ldap_session =
+                    AsyncLdapSession::from_ldap_readonly_config(config, ldap_credentials)?;
+                list_ldap_users(&mut ldap_session).await.map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let slurm = async {
+            if on_which_sys.slurm() {
+                let config = config.clone();
+                run_blocking(cancel, move || {
+                    let ssh_con = SshConnection::from_head_node(&config, ssh_credentials);
+                    let mut accounting = backend::accounting_from_config(&config, &ssh_con)?;
+                    accounting.list_associations(&config)
+                })
+                .await
+                .map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+
+        let (ldap, slurm) = tokio::try_join!(ldap, slurm)?;
+        Ok(ListedUsers { ldap, slurm })
+    })
+    .await
+}
+
+/// Drives the future `operation` until it finishes or `cancel` is triggered.
+/// The future `operation` is dropped on cancellation.
+///
+/// # Errors
+///
+/// - If the cancellation is triggered first. See [`Cancelled`]
+/// - If `operation` fails.
+pub async fn run_cancellable<RT>(
+    cancel: &CancellationToken,
+    operation: impl Future<Output = AppResult<RT>>,
+) -> AppResult<RT> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(Cancelled.into()),
+        result = operation => result,
+    }
+}
+
+/// Executes the blocking `task` on the blocking thread pool of tokio.
+/// The `task` is not started if `cancel` was already triggered.
+///
+/// # Errors
+///
+/// - If `cancel` was triggered before the task started. See [`Cancelled`]
+/// - If `task` fails or panics.
+pub async fn run_blocking<RT>(
+    cancel: &CancellationToken,
+    task: impl FnOnce() -> AppResult<RT> + Send + 'static,
+) -> AppResult<RT>
+where
+    RT: Send + 'static,
+{
+    bail_if_cancelled(cancel)?;
+    tokio::task::spawn_blocking(task)
+        .await
+        .context("Blocking task for SSH operations did not finish")?
+}
+
+fn bail_if_cancelled(cancel: &CancellationToken) -> AppResult {
+    if cancel.is_cancelled() {
+        Err(Cancelled.into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{ldap::LdapSimpleCredential, ssh::SshGivenCredential};
+
+    /// Futures must be `Send` to be spawned on a multi-threaded runtime.
+    #[test]
+    fn operations_are_send() {
+        fn assert_send<F: Future + Send>(_: F) {}
+
+        let (config, cancel) = (MgmtConfig::default(), CancellationToken::new());
+        let on_which_sys = OnWhichSystem::new(false, false, false, Default::default());
+        let ldap_credentials = || LdapSimpleCredential::new("alice".to_owned(), "xxxx".to_owned());
+        let ssh_credentials = || SshGivenCredential::new("alice", "xxxx", None);
+
+        assert_send(delete_user(
+            "bob",
+            &on_which_sys,
+            &config,
+            ldap_credentials(),
+            ssh_credentials(),
+            Some(ActiveJobsDecision::Abort),
+            &cancel,
+        ));
+        assert_send(list_users(
+            &config,
+            &on_which_sys,
+            ldap_credentials(),
+            ssh_credentials(),
+            &cancel,
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancelled_before_start() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let error = run_cancellable(&cancel, async { Ok(()) })
+            .await
+            .unwrap_err();
+        assert_eq!(Some(&Cancelled), error.downcast_ref::<Cancelled>());
+    }
+
+    #[tokio::test]
+    async fn cancelled_blocking_task_is_not_started() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let error = run_blocking(&cancel, || -> AppResult {
+            panic!("Must not be started after cancellation")
+        })
+        .await
+        .unwrap_err();
+        assert!(error.is::<Cancelled>());
+    }
+
+    #[tokio::test]
+    async fn cancel_pending_operation() {
+        let cancel = CancellationToken::new();
+        let pending = run_cancellable(&cancel, std::future::pending::<AppResult>());
+        cancel.cancel();
+        assert!(pending.await.unwrap_err().is::<Cancelled>());
+    }
+
+    #[tokio::test]
+    async fn returns_result_of_blocking_task() {
+        let cancel = CancellationToken::new();
+        let actual = run_blocking(&cancel, || Ok(42)).await.unwrap();
+        assert_eq!(42, actual);
+    }
+}
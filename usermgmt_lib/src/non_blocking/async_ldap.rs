@@ -0,0 +1,246 @@
+//! Asynchronous counterparts of the LDAP operations in [`crate::ldap`].
+//!
+//! Only the sending of searches and modifications happens here.
+//! Search filters, new attributes, modifications and the parsing of search results
+//! are shared with the blocking operations in [`crate::ldap`].
+
+use ldap3::controls::{MakeCritical, RelaxRules};
+use ldap3::{ResultEntry, Scope};
+use log::{debug, info};
+
+use crate::ldap::{self, LdapCredential, LdapSearch, LdapSearchResult};
+use crate::prelude::*;
+use crate::{ChangesToUser, Group, MgmtConfig, NewEntity};
+
+use super::AsyncLdapSession;
+
+/// # Errors
+///
+/// See [`crate::ldap::add_ldap_user`]
+pub async fn add_ldap_user<T>(
+    entity: &NewEntity,
+    config: &MgmtConfig,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult
+where
+    T: LdapCredential,
+{
+    let username: &str = entity.username.as_ref();
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session).await?;
+    if !ldap::is_new_user(username, found) {
+        return Ok(());
+    }
+
+    let uid_entries = search(&LdapSearch::uid_numbers(), ldap_session).await?;
+    let (dn, fields) =
+        ldap::new_user_entry(entity, config, ldap_session.config().base(), uid_entries)?;
+    ldap_session
+        .action(|mut connection| {
+            let (dn, fields) = (&dn, fields.clone());
+            async move {
+                let result = connection.add(dn, fields).await;
+                ldap::ldap_is_success(result).context("Unable to create LDAP user!")?;
+                Ok(())
+            }
+        })
+        .await?;
+
+    info!("Added LDAP user {}", username);
+    Ok(())
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::delete_ldap_user`]
+pub async fn delete_ldap_user<T>(
+    username: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult
+where
+    T: LdapCredential,
+{
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session).await?;
+    let dn_to_delete = ldap::dn_from_search(username, found)
+        .with_context(|| format!("No DN found for username {}!", username))?;
+
+    ldap_session
+        .action(|mut connection| {
+            let dn_to_delete = &dn_to_delete;
+            async move {
+                let result = connection.delete(dn_to_delete).await;
+                ldap::ldap_is_success(result)?;
+                Ok(())
+            }
+        })
+        .await?;
+    info!("Successfully deleted DN {}", dn_to_delete);
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::modify_ldap_user`]
+pub async fn modify_ldap_user<T>(
+    modifiable: &ChangesToUser,
+    config: &MgmtConfig,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult
+where
+    T: LdapCredential,
+{
+    let found = search(
+        &LdapSearch::for_modification(modifiable, config),
+        ldap_session,
+    )
+    .await?;
+    let (dn, mod_vec) = ldap::modification_from_search(modifiable, config, found)?;
+
+    ldap_session
+        .action(|mut connection| {
+            let (dn, mod_vec) = (&dn, mod_vec.clone());
+            async move {
+                let result = connection
+                    .with_controls(RelaxRules.critical())
+                    .modify(dn, mod_vec)
+                    .await;
+                ldap::ldap_is_success(result).context("User modification in LDAP failed!")
+            }
+        })
+        .await?;
+
+    info!("Successfully modified user {} in LDAP", modifiable.username);
+    Ok(())
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::list_ldap_users`]
+pub async fn list_ldap_users<T>(
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<LdapSearchResult>
+where
+    T: LdapCredential,
+{
+    debug!(
+        "Search for all users under {}",
+        ldap_session.config().base()
+    );
+    let found = search(&LdapSearch::listing(), ldap_session).await?;
+    Ok(ldap::users_from_search(found))
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::find_next_available_uid`]
+pub async fn find_next_available_uid<T>(
+    ldap_session: &mut AsyncLdapSession<T>,
+    group: Group,
+) -> AppResult<u32>
+where
+    T: LdapCredential,
+{
+    debug!("Search for UIDs under {}", ldap_session.config().base());
+    let found = search(&LdapSearch::uid_numbers(), ldap_session).await?;
+    ldap::next_uid_from_search(found, group)
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::find_dn_by_uid`]
+pub async fn find_dn_by_uid<T>(
+    username: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<Option<String>>
+where
+    T: LdapCredential,
+{
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session).await?;
+    ldap::dn_from_search(username, found).map(Some)
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::find_qos_by_uid`]
+pub async fn find_qos_by_uid<T>(
+    username: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    find_attribute_by_uid(username, ldap::SLURM_QOS, ldap_session).await
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::find_slurm_limits_by_uid`]
+pub async fn find_slurm_limits_by_uid<T>(
+    username: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    find_attribute_by_uid(username, ldap::SLURM_LIMIT, ldap_session).await
+}
+
+/// # Errors
+///
+/// See [`crate::ldap::username_exists`]
+pub async fn username_exists<T>(
+    username: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<bool>
+where
+    T: LdapCredential,
+{
+    let found = search(&LdapSearch::by_uid(username, vec!["dn"]), ldap_session).await?;
+    Ok(ldap::user_exists_in_search(username, found))
+}
+
+async fn find_attribute_by_uid<T>(
+    username: &str,
+    attribute: &str,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    let found = search(&LdapSearch::by_uid(username, vec![attribute]), ldap_session).await?;
+    Ok(ldap::values_from_search(found, attribute))
+}
+
+/// Sends `to_search` over the asynchronous connection of the session
+/// and returns the raw entries found.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails. See [`AsyncLdapSession::action`]
+/// - If the searching in LDAP failed
+async fn search<T>(
+    to_search: &LdapSearch<'_>,
+    ldap_session: &mut AsyncLdapSession<T>,
+) -> AppResult<Vec<ResultEntry>>
+where
+    T: LdapCredential,
+{
+    let base = ldap_session.config().base().to_owned();
+    let found = ldap_session
+        .read_only_action(|mut connection| {
+            let base = base.as_str();
+            async move {
+                connection
+                    .search(
+                        base,
+                        Scope::OneLevel,
+                        &to_search.filter,
+                        &to_search.attributes,
+                    )
+                    .await
+                    .with_context(|| format!("LDAP search with filter {} failed", to_search.filter))
+            }
+        })
+        .await?;
+    Ok(found.0)
+}
@@ -0,0 +1,136 @@
+use std::future::Future;
+
+use ldap3::{Ldap, LdapConnAsync};
+use log::{debug, warn};
+
+use crate::config::MgmtConfig;
+use crate::ldap::{self, LDAPConfig, LdapCredential};
+use crate::prelude::*;
+
+/// Same as [`crate::ldap::LdapSession`] but over the asynchronous connection of the `ldap3` crate.
+/// Needs to be used within a tokio runtime
+/// because the connection is driven by a spawned tokio task.
+pub struct AsyncLdapSession<T> {
+    config: LDAPConfig<T>,
+    connection: Option<Ldap>,
+}
+
+impl<T> AsyncLdapSession<T>
+where
+    T: LdapCredential,
+{
+    /// # Errors
+    ///
+    /// - If username or password for an LDAP session could not be retrieved.
+    pub fn new(config: &MgmtConfig, credentials: T) -> AppResult<Self> {
+        let config = LDAPConfig::new(config, credentials)?;
+        Ok(Self {
+            config,
+            connection: None,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// - If username or password for an LDAP session could not be retrieved.
+    pub fn from_ldap_readonly_config(config: &MgmtConfig, credentials: T) -> AppResult<Self> {
+        let config = LDAPConfig::new_readonly(config, credentials)?;
+        Ok(Self {
+            config,
+            connection: None,
+        })
+    }
+
+    pub fn config(&self) -> &LDAPConfig<T> {
+        &self.config
+    }
+
+    /// Executes `action` with a handle to the connection of this session.
+    /// If the request of `action` could not be sent because the connection was lost,
+    /// the session reconnects and executes the `action` another time.
+    /// A request which was sent is not repeated because LDAP may have applied it already.
+    ///
+    /// # Errors
+    ///
+    /// - If establishing of connection to the LDAP fails
+    /// - If `action` fails. After a lost connection, the error of the retried `action` is returned.
+    pub async fn action<RT, F, Fut>(&mut self, action: F) -> AppResult<RT>
+    where
+        F: FnMut(Ldap) -> Fut,
+        Fut: Future<Output = AppResult<RT>>,
+    {
+        self.action_with_retry(action, ldap::is_unsent_request)
+            .await
+    }
+
+    /// Same as [`AsyncLdapSession::action`] but the `action` is also executed another time
+    /// if the connection was lost after its request was sent.
+    /// Only for actions which do not change anything in LDAP like searches.
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncLdapSession::action`]
+    pub async fn read_only_action<RT, F, Fut>(&mut self, action: F) -> AppResult<RT>
+    where
+        F: FnMut(Ldap) -> Fut,
+        Fut: Future<Output = AppResult<RT>>,
+    {
+        self.action_with_retry(action, ldap::is_lost_connection)
+            .await
+    }
+
+    /// Establishes a connection if there is none or the current one is closed.
+    /// Returns a handle to the connection.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection to the LDAP instance fails.
+    /// - If the binding as the user fails aka authentication
+    pub async fn establish_connection(&mut self) -> AppResult<Ldap> {
+        if let Some(connection) = self.connection.as_mut() {
+            if !connection.is_closed() {
+                return Ok(connection.clone());
+            }
+            debug!("LDAP connection is closed. Establishing a new one.");
+        }
+
+        let (driver, mut connection) = LdapConnAsync::new(self.config.ldap_server()).await?;
+        ldap3::drive!(driver);
+        let _ = connection
+            .simple_bind(self.config.bind(), self.config.password()?)
+            .await
+            .and_then(|result| result.success())
+            .with_context(|| {
+                format!(
+                    "Failed to establish ldap connection via the bind {}",
+                    self.config.bind()
+                )
+            })?;
+        Ok(self.connection.insert(connection).clone())
+    }
+
+    async fn action_with_retry<RT, F, Fut>(
+        &mut self,
+        mut action: F,
+        may_retry: fn(&AppError) -> bool,
+    ) -> AppResult<RT>
+    where
+        F: FnMut(Ldap) -> Fut,
+        Fut: Future<Output = AppResult<RT>>,
+    {
+        let connection = self.establish_connection().await?;
+        match action(connection).await {
+            Err(error) if may_retry(&error) => {
+                warn!(
+                    "LDAP connection to {} was lost. Reconnecting and trying again.\nDetails: {}",
+                    self.config.ldap_server(),
+                    error
+                );
+                self.connection = None;
+                let connection = self.establish_connection().await?;
+                action(connection).await
+            }
+            result => result,
+        }
+    }
+}
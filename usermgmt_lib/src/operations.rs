@@ -1,19 +1,37 @@
-use log::info;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use log::{info, warn};
 
 use crate::{
-    cli::{OnWhichSystem, UserToAdd},
-    config::MgmtConfig,
-    dir,
-    ldap::{self, text_list_output, LdapCredential, LdapSession},
-    slurm,
-    ssh::{SshConnection, SshCredentials},
+    backend::{self, AccountingBackend, Backends, DirectoryBackend, SshStorage, StorageBackend},
+    cli::{AccountCommand, CoordinatorCommand, OnWhichSystem, UsageArgs, UserToAdd},
+    config::{ClusterConfig, MgmtConfig},
+    constants::{ACTIVE_JOBS_POLL_SECS, DEFAULT_ACTIVE_JOBS_TIMEOUT_SECS},
+    ldap::{
+        text_list_output, LdapCredential, LdapSearchResult, LdapSimpleCredential,
+        ReusableLdapSession,
+    },
+    slurm::{self, QueuedJob, SlurmAssociation},
+    ssh::{SshConnection, SshConnectionPool, SshCredentials},
+    util::ResultAccumulator,
     AppResult, ChangesToUser, NewEntity,
 };
 
+/// Users found on the systems selected for listing.
+/// A field is `None` if the respective system was not selected.
+#[derive(Debug, Default)]
+pub struct ListedUsers {
+    pub ldap: Option<LdapSearchResult>,
+    pub slurm: Option<Vec<SlurmAssociation>>,
+}
+
 /// # Errors
 ///
-/// - If the attributes of the parameter `to_add` is not compatible with fields of parameter `config`. See [`NewEntity::new_user_addition_conf`].
-/// - If adding a user fails. See [`perform_action_on_context`].
+/// See [`add_user_keep_ldap_session`].
 pub fn add_user<T, C>(
     to_add: UserToAdd,
     on_which_sys: &OnWhichSystem,
@@ -21,6 +39,33 @@ pub fn add_user<T, C>(
     ldap_credentials: T,
     ssh_credentials: C,
 ) -> AppResult
+where
+    T: LdapCredential + Clone,
+    C: SshCredentials + Clone,
+{
+    add_user_keep_ldap_session(
+        to_add,
+        on_which_sys,
+        config,
+        &mut ReusableLdapSession::new(ldap_credentials),
+        ssh_credentials,
+    )
+}
+
+/// Same as [`add_user`] but the LDAP session is taken from or stored into parameter `ldap_session`.
+/// This way several operations can be performed over the same bound LDAP connection.
+///
+/// # Errors
+///
+/// - If the attributes of the parameter `to_add` is not compatible with fields of parameter `config`. See [`NewEntity::new_user_addition_conf`].
+/// - If adding a user fails. See [`perform_action_on_context`].
+pub fn add_user_keep_ldap_session<T, C>(
+    to_add: UserToAdd,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_session: &mut ReusableLdapSession<T>,
+    ssh_credentials: C,
+) -> AppResult
 where
     T: LdapCredential + Clone,
     C: SshCredentials + Clone,
@@ -29,14 +74,17 @@ where
 
     let entity = NewEntity::new_user_addition_conf(to_add, config)?;
 
-    perform_action_on_context(
+    perform_action_on_clusters(
         on_which_sys,
         config,
-        ldap_credentials.clone(),
+        ldap_session,
         &ssh_credentials,
-        |session| ldap::add_ldap_user(&entity, config, session),
-        |ssh_con| slurm::add_slurm_user(&entity, config, ssh_con),
-        |_| dir::add_user_directories(&entity, config, &ssh_credentials),
+        true,
+        false,
+        |config, cluster, backends| match cluster {
+            Some(cluster) => add_user_to_backends(&entity.for_cluster(cluster)?, config, backends),
+            None => add_user_to_backends(&entity, config, backends),
+        },
     )?;
 
     info!("Finished adding user");
@@ -44,15 +92,62 @@ where
     Ok(())
 }
 
+/// What to do with the running or pending jobs of a user before the user is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveJobsDecision {
+    /// Cancel all jobs of the user via scancel and delete the user once they are gone.
+    Cancel,
+    /// Wait until all jobs of the user have finished and delete the user afterwards.
+    Wait,
+    /// Do not delete the user.
+    Abort,
+}
+
+/// Called with the active jobs of a user to decide how to proceed with the deletion.
+pub type ActiveJobsHandler<'a> = &'a dyn Fn(&[QueuedJob]) -> AppResult<ActiveJobsDecision>;
+
 /// # Errors
 ///
-/// - When user deletion fails. See [`perform_action_on_context`].
+/// See [`delete_user_keep_ldap_session`].
 pub fn delete_user<T, C>(
     user: &str,
     on_which_sys: &OnWhichSystem,
     config: &MgmtConfig,
     ldap_credentials: T,
     credentials: C,
+    on_active_jobs: Option<ActiveJobsHandler>,
+) -> AppResult
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    delete_user_keep_ldap_session(
+        user,
+        on_which_sys,
+        config,
+        &mut ReusableLdapSession::new(ldap_credentials),
+        credentials,
+        on_active_jobs,
+    )
+}
+
+/// Same as [`delete_user`] but the LDAP session is taken from or stored into parameter `ldap_session`.
+///
+/// Before anything is deleted, the running and pending jobs of the user are looked up via squeue
+/// if Slurm or user directories are affected. See [`ensure_no_active_jobs`].
+/// The check is skipped if `on_active_jobs` is `None`, for example when deletion is forced.
+///
+/// # Errors
+///
+/// - If the user still has active jobs and the deletion is aborted. See [`ensure_no_active_jobs`].
+/// - When user deletion fails. See [`perform_action_on_context`].
+pub fn delete_user_keep_ldap_session<T, C>(
+    user: &str,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_session: &mut ReusableLdapSession<T>,
+    credentials: C,
+    on_active_jobs: Option<ActiveJobsHandler>,
 ) -> AppResult
 where
     T: LdapCredential,
@@ -60,23 +155,108 @@ where
 {
     info!("Start deleting user {}", user);
 
-    perform_action_on_context(
+    match on_active_jobs {
+        Some(decide) if on_which_sys.slurm() || on_which_sys.dirs() => {
+            if config.clusters.is_empty() {
+                ensure_no_active_jobs(user, config, credentials.clone(), decide)?
+            } else {
+                for cluster in config.selected_clusters(on_which_sys.clusters())? {
+                    let cluster_config = config.for_cluster(cluster);
+                    ensure_no_active_jobs(user, &cluster_config, credentials.clone(), decide)
+                        .with_context(|| format!("On cluster {}", cluster.name))?
+                }
+            }
+        }
+        Some(_) => (),
+        None => warn!("Skipping the check for active jobs of user {}", user),
+    }
+
+    perform_action_on_clusters(
         on_which_sys,
         config,
-        ldap_credentials.clone(),
+        ldap_session,
         &credentials,
-        |ldap_session| ldap::delete_ldap_user(user, ldap_session),
-        |ssh_connection| slurm::delete_slurm_user(user, config, ssh_connection),
-        |_| dir::delete_user_directories(user, config, &credentials),
+        true,
+        false,
+        |config, _, backends| delete_user_from_backends(user, config, backends),
     )?;
 
     info!("Finished deleting user {}", user);
     Ok(())
 }
 
+/// Looks up the running and pending jobs of `user` via squeue and lets `decide` choose
+/// how to proceed if there are any.
+/// Returns once the user has no active jobs anymore.
+///
 /// # Errors
 ///
-/// - If changing a user fails. See [`perform_action_on_context`].
+/// - If listing or cancelling the jobs fails. See [`slurm::list_active_jobs`] and [`slurm::cancel_jobs`].
+/// - If `decide` returns an error or [`ActiveJobsDecision::Abort`].
+/// - If jobs are still active after `active_jobs_timeout_secs` of the configuration.
+pub fn ensure_no_active_jobs<C>(
+    user: &str,
+    config: &MgmtConfig,
+    ssh_credentials: C,
+    decide: ActiveJobsHandler,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let session = SshConnection::from_head_node(config, ssh_credentials);
+    let jobs = slurm::list_active_jobs(user, config, &session)?;
+    if jobs.is_empty() {
+        return Ok(());
+    }
+
+    match decide(&jobs)? {
+        ActiveJobsDecision::Abort => bail!(
+            "Aborted deletion of user {} because of {} active job(s): {}",
+            user,
+            jobs.len(),
+            jobs.iter()
+                .map(|job| job.job_id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ActiveJobsDecision::Cancel => slurm::cancel_jobs(user, config, &session)?,
+        ActiveJobsDecision::Wait => (),
+    }
+
+    let timeout = Duration::from_secs(
+        config
+            .active_jobs_timeout_secs
+            .unwrap_or(DEFAULT_ACTIVE_JOBS_TIMEOUT_SECS)
+            .into(),
+    );
+    let started = Instant::now();
+    // Cancelled jobs may linger in state COMPLETING for a while.
+    loop {
+        let remaining = slurm::list_active_jobs(user, config, &session)?;
+        if remaining.is_empty() {
+            info!("User {} has no active jobs anymore", user);
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            bail!(
+                "Aborted deletion of user {} because {} job(s) are still active after {} seconds",
+                user,
+                remaining.len(),
+                timeout.as_secs()
+            );
+        }
+        info!(
+            "Waiting for {} active job(s) of user {} to finish",
+            remaining.len(),
+            user
+        );
+        thread::sleep(Duration::from_secs(ACTIVE_JOBS_POLL_SECS));
+    }
+}
+
+/// # Errors
+///
+/// See [`modify_user_keep_ldap_session`].
 pub fn modify_user<T, C>(
     modifiable: ChangesToUser,
     on_which_sys: &OnWhichSystem,
@@ -84,20 +264,50 @@ pub fn modify_user<T, C>(
     ldap_credentials: T,
     credential: C,
 ) -> AppResult
+where
+    C: SshCredentials,
+    T: LdapCredential,
+{
+    modify_user_keep_ldap_session(
+        modifiable,
+        on_which_sys,
+        config,
+        &mut ReusableLdapSession::new(ldap_credentials),
+        credential,
+    )
+}
+
+/// Same as [`modify_user`] but the LDAP session is taken from or stored into parameter `ldap_session`.
+///
+/// # Errors
+///
+/// - If changing a user fails. See [`perform_action_on_context`].
+pub fn modify_user_keep_ldap_session<T, C>(
+    modifiable: ChangesToUser,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_session: &mut ReusableLdapSession<T>,
+    credential: C,
+) -> AppResult
 where
     C: SshCredentials,
     T: LdapCredential,
 {
     info!("Start modifying user {}", modifiable.username);
 
-    perform_action_context_no_dirs(
+    perform_action_on_clusters(
         on_which_sys,
         config,
-        ldap_credentials,
+        ldap_session,
         &credential,
         false,
-        |ldap_session| ldap::modify_ldap_user(&modifiable, ldap_session),
-        |ssh_connection| slurm::modify_slurm_user(&modifiable, config, ssh_connection),
+        false,
+        |config, cluster, backends| match cluster {
+            Some(cluster) => {
+                modify_user_on_backends(&modifiable.for_cluster(cluster)?, config, backends)
+            }
+            None => modify_user_on_backends(&modifiable, config, backends),
+        },
     )?;
 
     info!("Finished modifying user {}", modifiable.username);
@@ -107,7 +317,7 @@ where
 /// # Errors
 ///
 /// - When listing users fails. See [`perform_action_on_context`].
-/// - When the execution of the Slurm command fails. See [`slurm::list_users`].
+/// - When the execution of the Slurm command fails. See [`slurm::list_associations`].
 /// - When the execution of the LDAP command fails. See [`ldap::list_ldap_users`].
 pub fn print_list_of_users_to_stdout<T, C>(
     config: &MgmtConfig,
@@ -120,27 +330,27 @@ where
     T: LdapCredential,
     C: SshCredentials,
 {
-    perform_action_context_no_dirs(
+    perform_action_on_clusters(
         on_which_sys,
         config,
-        ldap_credentials.clone(),
+        &mut ReusableLdapSession::new(ldap_credentials),
         &credentials,
+        false,
         true,
-        |ldap_session| {
-            let ldap_config = ldap_session.config();
-            let search_result_data = ldap::list_ldap_users(ldap_config)?;
-
-            let output = if simple_output_ldap {
-                text_list_output::ldap_simple_output(&search_result_data)
-            } else {
-                text_list_output::ldap_search_to_pretty_table(&search_result_data)
-            };
-            println!("{}", &output);
-            Ok(())
-        },
-        |ssh_connection| {
-            let output = slurm::list_users(config, ssh_connection, false)?;
-            println!("{}", output);
+        |config, _, backends| {
+            if let Some(directory) = backends.directory {
+                let search_result_data = directory.list_users(config)?;
+                let output = if simple_output_ldap {
+                    text_list_output::ldap_simple_output(&search_result_data)
+                } else {
+                    text_list_output::ldap_search_to_pretty_table(&search_result_data)
+                };
+                println!("{}", &output);
+            }
+            if let Some(accounting) = backends.accounting {
+                let associations = accounting.list_associations(config)?;
+                println!("{}", slurm::associations_to_pretty_table(&associations));
+            }
             Ok(())
         },
     )?;
@@ -148,84 +358,900 @@ where
     Ok(())
 }
 
+/// Adds, deletes, modifies or lists accounts or their coordinators in Slurm as given by `command`.
+/// This happens on each cluster selected by `clusters`. See [`on_each_slurm_cluster`].
+/// Given account names are used as they are on every cluster.
+/// Only the accounts added by bootstrap are named according to `account_mapping` of the cluster.
+/// Listed accounts and coordinators are printed to stdout.
+///
+/// # Errors
+///
+/// - If the operation on accounts fails in Slurm.
+///   See [`slurm::add_account`], [`slurm::delete_account`], [`slurm::modify_account`],
+///   [`slurm::list_accounts`] and [`slurm::bootstrap_accounts`].
+/// - If the operation on coordinators fails. See [`manage_slurm_coordinators`]
+/// - If the operation fails on any cluster. See [`on_each_slurm_cluster`]
+pub fn manage_slurm_accounts<C>(
+    command: AccountCommand,
+    clusters: &[String],
+    config: &MgmtConfig,
+    ssh_credentials: C,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    if let AccountCommand::Coordinator { command } = command {
+        return manage_slurm_coordinators(command, clusters, config, ssh_credentials);
+    }
+    on_each_slurm_cluster(config, clusters, |config, cluster| {
+        let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
+        match &command {
+            AccountCommand::Add { to_add, .. } => slurm::add_account(to_add, config, &ssh_session)?,
+            AccountCommand::Delete { name, .. } => {
+                slurm::delete_account(name.as_ref(), config, &ssh_session)?
+            }
+            AccountCommand::Modify { changes, .. } => {
+                slurm::modify_account(changes, config, &ssh_session)?
+            }
+            AccountCommand::List { .. } => {
+                let accounts = slurm::list_accounts(config, &ssh_session)?;
+                println!("{}", slurm::accounts_to_pretty_table(&accounts));
+            }
+            AccountCommand::Bootstrap { parent, .. } => {
+                let added =
+                    slurm::bootstrap_accounts(parent.as_ref(), cluster, config, &ssh_session)?;
+                if added.is_empty() {
+                    info!("All groups in valid_slurm_groups exist already as accounts in Slurm");
+                } else {
+                    info!("Added accounts {} to Slurm", added.join(", "));
+                }
+            }
+            AccountCommand::Coordinator { .. } => {
+                unreachable!("Coordinators are managed before accounts")
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Prints the CPU and GPU hours used by users and accounts to stdout as tables or as JSON.
+/// The usage is reported for each cluster selected in `args`. See [`on_each_slurm_cluster`].
+///
+/// # Errors
+///
+/// - If the period given in `args` is invalid. See [`UsageArgs::period`]
+/// - If reporting the usage fails. See [`slurm::report_usage`]
+/// - If reporting fails on any cluster. See [`on_each_slurm_cluster`]
+pub fn print_usage<C>(args: &UsageArgs, config: &MgmtConfig, ssh_credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    let period = args.period()?;
+    on_each_slurm_cluster(config, &args.clusters.clusters, |config, _| {
+        let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
+        let report = slurm::report_usage(&args.filter(), &period, config, &ssh_session)?;
+        if args.json {
+            let json = serde_json::to_string_pretty(&report)
+                .context("Could not convert the usage report into JSON")?;
+            println!("{}", json);
+        } else {
+            println!("{}", report.to_pretty_tables());
+        }
+        Ok(())
+    })
+}
+
+/// Adds, removes or lists coordinators of accounts on each cluster selected by `clusters`.
+/// Only Slurm is touched, via sacctmgr or slurmrestd depending on the configuration.
+///
+/// # Errors
+///
+/// - If establishing the connection to Slurm fails. See [`perform_action_on_context`]
+/// - If the operation on coordinators fails. See [`manage_coordinators_on_backends`]
+/// - If the operation fails on any cluster. See [`on_each_slurm_cluster`]
+pub fn manage_slurm_coordinators<C>(
+    command: CoordinatorCommand,
+    clusters: &[String],
+    config: &MgmtConfig,
+    ssh_credentials: C,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let on_which_sys = OnWhichSystem::slurm_only(command.ssh_path().clone());
+    on_each_slurm_cluster(config, clusters, |config, _| {
+        let listed = perform_action_context_no_dirs(
+            &on_which_sys,
+            config,
+            &mut ReusableLdapSession::new(LdapSimpleCredential::default()),
+            &ssh_credentials,
+            false,
+            |backends| manage_coordinators_on_backends(&command, config, backends),
+        )?;
+        if let Some(listed) = listed {
+            println!("{}", slurm::coordinators_to_pretty_table(&listed));
+        }
+        Ok(())
+    })
+}
+
+/// Performs `command` on the accounting system of `backends`.
+/// Returns the coordinators of all accounts if `command` lists them.
+///
+/// # Errors
+///
+/// - If `backends` has no accounting system.
+/// - If the operation fails in the accounting system.
+pub fn manage_coordinators_on_backends(
+    command: &CoordinatorCommand,
+    config: &MgmtConfig,
+    backends: Backends<'_>,
+) -> AppResult<Option<Vec<slurm::AccountCoordinators>>> {
+    let Some(accounting) = backends.accounting else {
+        bail!("Coordinators can only be managed in Slurm but Slurm is not selected");
+    };
+    match command {
+        CoordinatorCommand::Add { coordinator, .. } => accounting.add_coordinator(
+            coordinator.account.as_ref(),
+            coordinator.user.as_ref(),
+            config,
+        )?,
+        CoordinatorCommand::Remove { coordinator, .. } => accounting.remove_coordinator(
+            coordinator.account.as_ref(),
+            coordinator.user.as_ref(),
+            config,
+        )?,
+        CoordinatorCommand::List { .. } => return accounting.list_coordinators(config).map(Some),
+    }
+    Ok(None)
+}
+
+/// Adds a user on every system given by parameter `backends`.
+/// Systems are handled in the order: directory, accounting and storage.
+///
+/// # Errors
+///
+/// - If adding the user fails on one of the systems. Following systems are not touched then.
+pub fn add_user_to_backends(
+    entity: &NewEntity,
+    config: &MgmtConfig,
+    backends: Backends<'_>,
+) -> AppResult {
+    if let Some(directory) = backends.directory {
+        directory.add_user(entity, config)?;
+    }
+    if let Some(accounting) = backends.accounting {
+        accounting.add_user(entity, config)?;
+    }
+    if let Some(storage) = backends.storage {
+        storage.add_user_directories(entity, config)?;
+    }
+    Ok(())
+}
+
+/// Deletes a user on every system given by parameter `backends`.
+/// Systems are handled in the order: directory, accounting and storage.
+///
+/// # Errors
+///
+/// - If deleting the user fails on one of the systems. Following systems are not touched then.
+pub fn delete_user_from_backends(
+    username: &str,
+    config: &MgmtConfig,
+    backends: Backends<'_>,
+) -> AppResult {
+    if let Some(directory) = backends.directory {
+        directory.delete_user(username, config)?;
+    }
+    if let Some(accounting) = backends.accounting {
+        accounting.delete_user(username, config)?;
+    }
+    if let Some(storage) = backends.storage {
+        storage.delete_user_directories(username, config)?;
+    }
+    Ok(())
+}
+
+/// Modifies a user on the directory and accounting system given by parameter `backends`.
+/// Storage is not affected by modifications.
+///
+/// # Errors
+///
+/// - If modifying the user fails on one of the systems. Following systems are not touched then.
+pub fn modify_user_on_backends(
+    modifiable: &ChangesToUser,
+    config: &MgmtConfig,
+    backends: Backends<'_>,
+) -> AppResult {
+    if let Some(directory) = backends.directory {
+        directory.modify_user(modifiable, config)?;
+    }
+    if let Some(accounting) = backends.accounting {
+        accounting.modify_user(modifiable, config)?;
+    }
+    Ok(())
+}
+
+/// Lists all users of the directory and the associations of the accounting system
+/// given by parameter `backends`.
+///
+/// # Errors
+///
+/// - If listing the users fails on one of the systems.
+pub fn list_users_on_backends(
+    config: &MgmtConfig,
+    backends: Backends<'_>,
+) -> AppResult<ListedUsers> {
+    let ldap = backends
+        .directory
+        .map(|directory| directory.list_users(config))
+        .transpose()?;
+    let slurm = backends
+        .accounting
+        .map(|accounting| accounting.list_associations(config))
+        .transpose()?;
+    Ok(ListedUsers { ldap, slurm })
+}
+
 /// Performs an action on all the three systems on the cluster.
 ///
 /// - LDAP
 /// - Slurm
 /// - Directory management
 ///
+/// The systems are handed to parameter `action` as backends.
+/// A system not selected by parameter `on_which_sys` is left out.
+/// The LDAP session is reused if parameter `ldap_session` already holds an opened one.
+///
 /// # Errors
 ///
-/// - If getting of credentials for LDAP fails. See [`LdapSession::new`]
-/// - If establishing the ssh connection fails
-/// - If one of three actions fails `on_ldap_action`, `on_slurm_action` or `on_dir_action`.
-pub fn perform_action_on_context<T, C>(
+/// - If getting of credentials for LDAP fails. See [`ReusableLdapSession::get_or_open`]
+/// - If establishing the LDAP or ssh connection fails
+/// - If `action` fails.
+pub fn perform_action_on_context<T, C, R>(
     on_which_sys: &OnWhichSystem,
     config: &MgmtConfig,
-    ldap_credentials: T,
+    ldap_session: &mut ReusableLdapSession<T>,
+    ssh_credentials: &C,
+    action: impl FnOnce(Backends<'_>) -> AppResult<R>,
+) -> AppResult<R>
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    perform_action_with_backends(
+        on_which_sys,
+        config,
+        ldap_session,
+        ssh_credentials,
+        true,
+        false,
+        action,
+    )
+}
+
+/// Same as [`perform_action_on_context`] except no directory management is performed.
+/// A new LDAP session is opened with the readonly configuration if `readonly` is true.
+fn perform_action_context_no_dirs<T, C, R>(
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_session: &mut ReusableLdapSession<T>,
     ssh_credentials: &C,
-    on_ldap_action: impl FnOnce(&mut LdapSession<T>) -> AppResult,
-    on_slurm_action: impl FnOnce(&SshConnection<C>) -> AppResult,
-    mut on_dir_action: impl FnMut(&SshConnection<C>) -> AppResult,
+    readonly: bool,
+    action: impl FnOnce(Backends<'_>) -> AppResult<R>,
+) -> AppResult<R>
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    perform_action_with_backends(
+        on_which_sys,
+        config,
+        ldap_session,
+        ssh_credentials,
+        false,
+        readonly,
+        action,
+    )
+}
+
+/// Same as [`perform_action_on_context`] but Slurm is handled on every cluster selected
+/// by `on_which_sys` if the configuration has [`MgmtConfig::clusters`].
+///
+/// The systems are handled in the same order as without clusters: LDAP, Slurm and directories.
+/// LDAP and directories are handled only once.
+/// In between, `action` is performed on the Slurm of each cluster like [`on_each_slurm_cluster`] does.
+/// Without clusters, `action` is performed once on all systems without a cluster.
+/// LDAP is opened with the readonly configuration if `readonly` is true.
+///
+/// # Errors
+///
+/// - If a selected cluster is not in the configuration. See [`MgmtConfig::selected_clusters`]
+/// - If the action fails on LDAP. No cluster and no directory is touched then.
+/// - If the action fails on any cluster. See [`on_each_slurm_cluster`]. Directories are not touched then.
+/// - If the action fails on directories.
+fn perform_action_on_clusters<T, C>(
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_session: &mut ReusableLdapSession<T>,
+    ssh_credentials: &C,
+    with_dirs: bool,
+    readonly: bool,
+    action: impl Fn(&MgmtConfig, Option<&ClusterConfig>, Backends<'_>) -> AppResult,
 ) -> AppResult
 where
     T: LdapCredential,
     C: SshCredentials,
 {
+    if !on_which_sys.slurm() || config.clusters.is_empty() {
+        if !on_which_sys.clusters().is_empty() {
+            config.selected_clusters(on_which_sys.clusters())?;
+        }
+        return perform_action_with_backends(
+            on_which_sys,
+            config,
+            ldap_session,
+            ssh_credentials,
+            with_dirs,
+            readonly,
+            |backends| action(config, None, backends),
+        );
+    }
+
+    // Fails early for unknown clusters before anything is changed.
+    config.selected_clusters(on_which_sys.clusters())?;
+    let ssh_path = on_which_sys.ssh_path();
     if on_which_sys.ldap() {
-        let mut ldap_session = LdapSession::new(config, ldap_credentials)?;
-        ldap_session.establish_connection()?;
-        on_ldap_action(&mut ldap_session)?;
+        perform_action_with_backends(
+            &OnWhichSystem::new(false, true, false, ssh_path.clone()),
+            config,
+            ldap_session,
+            ssh_credentials,
+            false,
+            readonly,
+            |backends| action(config, None, backends),
+        )?;
     }
 
-    if on_which_sys.slurm() || on_which_sys.dirs() {
-        let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
-        ssh_session.establish_connection()?;
-        if on_which_sys.slurm() {
-            on_slurm_action(&ssh_session)?;
-        }
+    let slurm_only = OnWhichSystem::slurm_only(ssh_path.clone());
+    on_each_slurm_cluster(
+        config,
+        on_which_sys.clusters(),
+        |cluster_config, cluster| {
+            perform_action_with_backends(
+                &slurm_only,
+                cluster_config,
+                ldap_session,
+                ssh_credentials,
+                false,
+                readonly,
+                |backends| action(cluster_config, cluster, backends),
+            )
+        },
+    )?;
 
-        if on_which_sys.dirs() {
-            on_dir_action(&ssh_session)?;
-        }
+    if with_dirs && on_which_sys.dirs() {
+        perform_action_with_backends(
+            &OnWhichSystem::new(false, false, true, ssh_path.clone()),
+            config,
+            ldap_session,
+            ssh_credentials,
+            true,
+            readonly,
+            |backends| action(config, None, backends),
+        )?;
     }
-
     Ok(())
 }
 
-/// Same as [`perform_action_on_context`] except no directory management is performed.
-fn perform_action_context_no_dirs<T, C>(
+/// Performs `action` on each cluster selected by `selection` with the configuration of the cluster,
+/// see [`MgmtConfig::for_cluster`], and the cluster itself. An empty `selection` selects all clusters.
+/// Without `[[clusters]]` in the configuration, `action` is performed once with `config` and no cluster.
+///
+/// The name of each cluster is printed before its action to stderr.
+/// A failure on one cluster does not stop the others.
+/// At the end, a summary with the outcome of each cluster is printed to stderr.
+///
+/// # Errors
+///
+/// - If a selected cluster is not in the configuration. See [`MgmtConfig::selected_clusters`]
+/// - If the action fails without clusters or on any cluster. The error lists every failed cluster.
+pub fn on_each_slurm_cluster(
+    config: &MgmtConfig,
+    selection: &[String],
+    mut action: impl FnMut(&MgmtConfig, Option<&ClusterConfig>) -> AppResult,
+) -> AppResult {
+    let clusters = config.selected_clusters(selection)?;
+    if clusters.is_empty() {
+        return action(config, None);
+    }
+
+    let mut summary = Vec::new();
+    let mut failed = ResultAccumulator::new("Failed on some Slurm clusters".to_owned());
+    for cluster in clusters {
+        eprintln!("Cluster {}:", cluster.name);
+        let cluster_config = config.for_cluster(cluster);
+        match action(&cluster_config, Some(cluster)) {
+            Ok(()) => {
+                info!("Cluster {}: succeeded", cluster.name);
+                summary.push(format!("  {}: succeeded", cluster.name));
+            }
+            Err(error) => {
+                warn!("Cluster {}: failed: {:?}", cluster.name, error);
+                summary.push(format!("  {}: failed", cluster.name));
+                failed.add_err(format!("Cluster {}: {:?}", cluster.name, error));
+            }
+        }
+    }
+    eprintln!("Summary of clusters:\n{}", summary.join("\n"));
+    failed.into()
+}
+
+/// Opens the connections to the selected systems and hands them to `action` as backends.
+/// Directory management is only included if `with_dirs` is true.
+/// LDAP is opened with the readonly configuration if `readonly` is true.
+fn perform_action_with_backends<T, C, R>(
     on_which_sys: &OnWhichSystem,
     config: &MgmtConfig,
-    ldap_credentials: T,
+    ldap_session: &mut ReusableLdapSession<T>,
     ssh_credentials: &C,
+    with_dirs: bool,
     readonly: bool,
-    on_ldap_action: impl FnOnce(&mut LdapSession<T>) -> AppResult,
-    on_slurm_action: impl FnOnce(&SshConnection<C>) -> AppResult,
-) -> AppResult
+    action: impl FnOnce(Backends<'_>) -> AppResult<R>,
+) -> AppResult<R>
 where
     T: LdapCredential,
     C: SshCredentials,
 {
+    let directory = if on_which_sys.ldap() {
+        let ldap_session = if readonly {
+            ldap_session.get_or_open_readonly(config)?
+        } else {
+            ldap_session.get_or_open(config)?
+        };
+        ldap_session.establish_connection()?;
+        Some(ldap_session)
+    } else {
+        None
+    };
+
+    let (slurm, dirs) = (on_which_sys.slurm(), with_dirs && on_which_sys.dirs());
     let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
-    let mut ldap_session = if readonly {
-        LdapSession::from_ldap_readonly_config(config, ldap_credentials)?
+    if slurm && !config.use_slurmrestd && config.run_slurm_remote {
+        ssh_session.ensure_connected()?;
+    }
+    let mut accounting = if slurm {
+        Some(backend::accounting_from_config(config, &ssh_session)?)
     } else {
-        LdapSession::new(config, ldap_credentials)?
+        None
     };
+    let pool = SshConnectionPool::new(config, ssh_credentials.clone());
+    let mut storage = dirs.then(|| SshStorage::new(&pool));
 
-    if on_which_sys.slurm() {
-        ssh_session.establish_connection()?;
+    action(Backends {
+        directory: directory.map(|directory| directory as &mut dyn DirectoryBackend),
+        accounting: accounting
+            .as_deref_mut()
+            .map(|accounting| accounting as &mut dyn AccountingBackend),
+        storage: storage
+            .as_mut()
+            .map(|storage| storage as &mut dyn StorageBackend),
+    })
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{
+        backend::{AccountedUser, InMemoryAccounting, InMemoryDirectory, InMemoryStorage},
+        cli::{CommonUserFields, SlurmCoordinator},
+        slurm::{fake_tools::FakeSlurmTools, AccountCoordinators, AccountMembership},
+        ssh::SshGivenCredential,
+        Entity,
+    };
+
+    struct InMemoryCluster {
+        directory: InMemoryDirectory,
+        accounting: InMemoryAccounting,
+        storage: InMemoryStorage,
     }
 
-    if on_which_sys.ldap() {
-        ldap_session.establish_connection()?;
-        on_ldap_action(&mut ldap_session)?;
+    impl InMemoryCluster {
+        fn new() -> Self {
+            Self {
+                directory: InMemoryDirectory::new(),
+                accounting: InMemoryAccounting::new(),
+                storage: InMemoryStorage::new(),
+            }
+        }
+
+        fn backends(&mut self) -> Backends<'_> {
+            Backends {
+                directory: Some(&mut self.directory),
+                accounting: Some(&mut self.accounting),
+                storage: Some(&mut self.storage),
+            }
+        }
     }
 
-    if on_which_sys.slurm() {
-        on_slurm_action(&ssh_session)?;
+    /// Accounting whose every operation fails like an unreachable Slurm database
+    struct UnreachableAccounting;
+
+    impl UnreachableAccounting {
+        fn fail<R>() -> AppResult<R> {
+            bail!("Slurm database is not reachable")
+        }
     }
 
-    Ok(())
+    impl AccountingBackend for UnreachableAccounting {
+        fn add_user(&mut self, _: &NewEntity, _: &MgmtConfig) -> AppResult {
+            Self::fail()
+        }
+        fn delete_user(&mut self, _: &str, _: &MgmtConfig) -> AppResult {
+            Self::fail()
+        }
+        fn modify_user(&mut self, _: &ChangesToUser, _: &MgmtConfig) -> AppResult {
+            Self::fail()
+        }
+        fn list_associations(&mut self, _: &MgmtConfig) -> AppResult<Vec<SlurmAssociation>> {
+            Self::fail()
+        }
+        fn add_coordinator(&mut self, _: &str, _: &str, _: &MgmtConfig) -> AppResult {
+            Self::fail()
+        }
+        fn remove_coordinator(&mut self, _: &str, _: &str, _: &MgmtConfig) -> AppResult {
+            Self::fail()
+        }
+        fn list_coordinators(&mut self, _: &MgmtConfig) -> AppResult<Vec<AccountCoordinators>> {
+            Self::fail()
+        }
+    }
+
+    fn new_staff(username: &str, config: &MgmtConfig) -> NewEntity {
+        let mut fields = CommonUserFields::new(username.try_into().unwrap());
+        fields.group = Some("staff".try_into().unwrap());
+        let to_add = UserToAdd::new(
+            "Jane".try_into().unwrap(),
+            "Doe".try_into().unwrap(),
+            fields,
+        );
+        NewEntity::new_user_addition_conf(to_add, config).unwrap()
+    }
+
+    /// Fake `squeue` and `scancel` which are not waited for if jobs remain active.
+    fn fake_slurm_tools(name: &str, squeue: &str, scancel: &str) -> FakeSlurmTools {
+        FakeSlurmTools::new(name, &[("squeue", squeue), ("scancel", scancel)])
+            .with_config(|config| config.active_jobs_timeout_secs = Some(0))
+    }
+
+    #[test]
+    fn stops_deletion_if_jobs_can_not_be_listed_or_cancelled() {
+        let fake = fake_slurm_tools(
+            "failing_squeue",
+            "echo 'slurm_load_jobs error: Unable to contact slurm controller' >&2; exit 1",
+            "exit 0",
+        );
+        let actual = ensure_no_active_jobs(
+            "jane",
+            fake.config(),
+            SshGivenCredential::default(),
+            &|_| Ok(ActiveJobsDecision::Cancel),
+        );
+        assert!(format!("{:?}", actual.unwrap_err()).contains("Unable to contact slurm controller"));
+
+        let fake = fake_slurm_tools(
+            "failing_scancel",
+            "echo '42|jane|RUNNING|cpu|1:00|train'",
+            "echo 'scancel: error: Access/permission denied' >&2; exit 1",
+        );
+        let actual = ensure_no_active_jobs(
+            "jane",
+            fake.config(),
+            SshGivenCredential::default(),
+            &|_| Ok(ActiveJobsDecision::Cancel),
+        );
+        assert!(format!("{:?}", actual.unwrap_err()).contains("Access/permission denied"));
+    }
+
+    #[test]
+    fn stops_waiting_for_active_jobs_after_timeout() {
+        let fake = fake_slurm_tools(
+            "lasting_jobs",
+            "echo '42|jane|RUNNING|cpu|1:00|train'",
+            "exit 0",
+        );
+        let actual = ensure_no_active_jobs(
+            "jane",
+            fake.config(),
+            SshGivenCredential::default(),
+            &|_| Ok(ActiveJobsDecision::Wait),
+        );
+        assert_eq!(
+            "Aborted deletion of user jane because 1 job(s) are still active after 0 seconds",
+            actual.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn adds_user_on_all_backends() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+
+        add_user_to_backends(&new_staff("jane", &config), &config, cluster.backends()).unwrap();
+        add_user_to_backends(&new_staff("john", &config), &config, cluster.backends()).unwrap();
+
+        assert_eq!(
+            vec!["1002"],
+            cluster.directory.attribute("john", "uidNumber")
+        );
+        assert_eq!(
+            Some(&AccountedUser {
+                default_account: "staff".to_owned(),
+                accounts: vec![AccountMembership::new("staff".try_into().unwrap())],
+                default_qos: "advanced".to_owned(),
+                qos: vec!["interactive".to_owned(), "advanced".to_owned()],
+                limits: Default::default(),
+            }),
+            cluster.accounting.user("jane")
+        );
+        assert!(cluster.storage.has_directories("jane"));
+
+        let listed = list_users_on_backends(&config, cluster.backends()).unwrap();
+        insta::assert_debug_snapshot!(listed);
+    }
+
+    #[test]
+    fn continues_on_other_clusters_after_failure() {
+        let config = MgmtConfig {
+            clusters: ["hpc", "gpu", "old"]
+                .into_iter()
+                .map(|name| ClusterConfig {
+                    name: name.to_owned(),
+                    head_node: Some(format!("{}.head", name)),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut visited = Vec::new();
+        let result = on_each_slurm_cluster(&config, &[], |cluster_config, cluster| {
+            visited.push(cluster_config.head_node.clone());
+            if cluster.is_some_and(|cluster| cluster.name == "gpu") {
+                bail!("sacctmgr is not reachable");
+            }
+            Ok(())
+        });
+
+        assert_eq!(vec!["hpc.head", "gpu.head", "old.head"], visited);
+        let error = format!("{:?}", result.unwrap_err());
+        assert!(error.contains("Cluster gpu"));
+        assert!(!error.contains("Cluster hpc"));
+
+        let mut visited = Vec::new();
+        on_each_slurm_cluster(&config, &["old".to_owned()], |_, cluster| {
+            visited.push(cluster.map(|cluster| cluster.name.clone()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(vec![Some("old".to_owned())], visited);
+        assert!(on_each_slurm_cluster(&config, &["unknown".to_owned()], |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn runs_once_without_clusters() {
+        let config = MgmtConfig::default();
+        let mut runs = 0;
+        on_each_slurm_cluster(&config, &[], |_, cluster| {
+            assert!(cluster.is_none());
+            runs += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(1, runs);
+        assert!(on_each_slurm_cluster(&config, &["gpu".to_owned()], |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn leaves_out_not_selected_backends() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+
+        let only_accounting = Backends {
+            accounting: Some(&mut cluster.accounting),
+            ..Default::default()
+        };
+        add_user_to_backends(&new_staff("jane", &config), &config, only_accounting).unwrap();
+
+        assert!(cluster.directory.user("jane").is_none());
+        assert!(cluster.accounting.user("jane").is_some());
+        assert!(!cluster.storage.has_directories("jane"));
+    }
+
+    #[test]
+    fn stops_deletion_at_first_failing_backend() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+        add_user_to_backends(&new_staff("jane", &config), &config, cluster.backends()).unwrap();
+
+        let with_failing_accounting = Backends {
+            directory: Some(&mut cluster.directory),
+            accounting: Some(&mut UnreachableAccounting),
+            storage: Some(&mut cluster.storage),
+        };
+        let result = delete_user_from_backends("jane", &config, with_failing_accounting);
+
+        assert!(result.is_err());
+        assert!(cluster.directory.user("jane").is_none());
+        assert!(cluster.storage.has_directories("jane"));
+    }
+
+    #[test]
+    fn deletes_user_missing_on_some_backends() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+        let only_accounting = Backends {
+            accounting: Some(&mut cluster.accounting),
+            ..Default::default()
+        };
+        add_user_to_backends(&new_staff("jane", &config), &config, only_accounting).unwrap();
+
+        delete_user_from_backends("jane", &config, cluster.backends()).unwrap();
+
+        assert!(cluster.accounting.user("jane").is_none());
+    }
+
+    #[test]
+    fn modifies_user_on_directory_and_accounting() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+        add_user_to_backends(&new_staff("jane", &config), &config, cluster.backends()).unwrap();
+
+        let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+        fields.qos = vec!["basic".to_owned()];
+        fields.default_qos = Some("basic".try_into().unwrap());
+        let entity = Entity::new(Some("Janet".try_into().unwrap()), None, fields, &config).unwrap();
+        let changes = ChangesToUser::try_new(entity).unwrap();
+        modify_user_on_backends(&changes, &config, cluster.backends()).unwrap();
+
+        assert_eq!(
+            vec!["Janet"],
+            cluster.directory.attribute("jane", "givenName")
+        );
+        assert_eq!(
+            vec!["basic"],
+            cluster.directory.attribute("jane", "slurmQos")
+        );
+        assert_eq!(
+            vec!["basic"],
+            cluster.directory.attribute("jane", "slurmDefaultQos")
+        );
+        let accounted = cluster.accounting.user("jane").unwrap();
+        assert_eq!(
+            (vec!["basic".to_owned()], "basic"),
+            (accounted.qos.clone(), accounted.default_qos.as_str())
+        );
+
+        delete_user_from_backends("jane", &config, cluster.backends()).unwrap();
+        assert!(cluster.directory.user("jane").is_none());
+        assert!(!cluster.storage.has_directories("jane"));
+    }
+
+    #[test]
+    fn sets_limits_and_mirrors_them_into_directory() {
+        let config = MgmtConfig {
+            ldap_add_slurm_limits: true,
+            ..Default::default()
+        };
+        let mut cluster = InMemoryCluster::new();
+        let mut jane = new_staff("jane", &config);
+        jane.limits = vec!["MaxJobs=10".parse().unwrap()];
+        add_user_to_backends(&jane, &config, cluster.backends()).unwrap();
+
+        let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+        fields.limits = ["maxjobs=20", "GrpTRES=cpu=8", "MaxWall=-1"]
+            .into_iter()
+            .map(|limit| limit.parse().unwrap())
+            .collect();
+        let entity = Entity::new(None, None, fields, &config).unwrap();
+        let changes = ChangesToUser::try_new(entity).unwrap();
+        modify_user_on_backends(&changes, &config, cluster.backends()).unwrap();
+
+        assert_eq!(
+            vec!["GrpTRES=cpu=8", "MaxJobs=20"],
+            cluster.directory.attribute("jane", "slurmLimit")
+        );
+        let accounted = cluster.accounting.user("jane").unwrap();
+        assert_eq!(
+            Some("20"),
+            accounted.limits.get("MaxJobs").map(String::as_str)
+        );
+        assert_eq!(2, accounted.limits.len());
+    }
+
+    #[test]
+    fn adds_and_removes_accounts_and_mirrors_them_into_directory() {
+        let config = MgmtConfig {
+            ldap_slurm_account_attribute: Some("slurmAccount".to_owned()),
+            ..Default::default()
+        };
+        let mut cluster = InMemoryCluster::new();
+        let jane = new_staff("jane", &config);
+        add_user_to_backends(&jane, &config, cluster.backends()).unwrap();
+
+        let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+        fields.accounts = vec!["chemistry".parse().unwrap(), "gpu:a100".parse().unwrap()];
+        fields.default_account = Some("chemistry".try_into().unwrap());
+        let entity = Entity::new(None, None, fields, &config).unwrap();
+        let changes = ChangesToUser::try_new(entity).unwrap();
+        modify_user_on_backends(&changes, &config, cluster.backends()).unwrap();
+        assert_eq!(
+            vec!["chemistry", "gpu", "staff"],
+            cluster.directory.attribute("jane", "slurmAccount")
+        );
+
+        let mut entity = Entity::new(
+            None,
+            None,
+            CommonUserFields::new("jane".try_into().unwrap()),
+            &config,
+        )
+        .unwrap();
+        entity.remove_accounts = vec!["staff".parse().unwrap(), "gpu:a100".parse().unwrap()];
+        let changes = ChangesToUser::try_new(entity).unwrap();
+        modify_user_on_backends(&changes, &config, cluster.backends()).unwrap();
+
+        assert_eq!(
+            vec!["chemistry", "gpu"],
+            cluster.directory.attribute("jane", "slurmAccount")
+        );
+        let accounted = cluster.accounting.user("jane").unwrap();
+        assert_eq!("chemistry", accounted.default_account);
+        assert_eq!(
+            vec![AccountMembership::new("chemistry".try_into().unwrap())],
+            accounted.accounts
+        );
+    }
+
+    #[test]
+    fn adds_lists_and_removes_coordinators_only_in_accounting() {
+        let config = MgmtConfig::default();
+        let mut cluster = InMemoryCluster::new();
+        add_user_to_backends(&new_staff("jane", &config), &config, cluster.backends()).unwrap();
+        let coordinator = SlurmCoordinator {
+            account: "staff".try_into().unwrap(),
+            user: "jane".try_into().unwrap(),
+        };
+        let add = CoordinatorCommand::Add {
+            coordinator: coordinator.clone(),
+            ssh: Default::default(),
+        };
+        let list = CoordinatorCommand::List {
+            ssh: Default::default(),
+        };
+
+        assert_eq!(
+            None,
+            manage_coordinators_on_backends(&add, &config, cluster.backends()).unwrap()
+        );
+        assert_eq!(
+            Some(vec![AccountCoordinators {
+                account: "staff".to_owned(),
+                coordinators: vec!["jane".to_owned()],
+            }]),
+            manage_coordinators_on_backends(&list, &config, cluster.backends()).unwrap()
+        );
+
+        let remove = CoordinatorCommand::Remove {
+            coordinator,
+            ssh: Default::default(),
+        };
+        manage_coordinators_on_backends(&remove, &config, cluster.backends()).unwrap();
+        assert_eq!(
+            Some(vec![]),
+            manage_coordinators_on_backends(&list, &config, cluster.backends()).unwrap()
+        );
+        assert!(manage_coordinators_on_backends(&remove, &config, cluster.backends()).is_err());
+        assert!(manage_coordinators_on_backends(&list, &config, Backends::default()).is_err());
+    }
 }
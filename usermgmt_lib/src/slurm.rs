@@ -1,18 +1,63 @@
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Context};
-use log::{debug, info};
+use anyhow::{bail, Context};
+use log::{debug, info, warn};
 
+mod account;
+mod association;
 mod command_builder;
-mod listed_user;
-use crate::{config::MgmtConfig, prelude::AppResult, ssh};
+mod dump;
+#[cfg(test)]
+pub(crate) mod fake_tools;
+mod limit;
+mod qos;
+mod queue;
+mod slurmrestd;
+mod usage;
+use crate::{
+    cli::{SlurmAccountChanges, SlurmAccountFields, SlurmAccountToAdd},
+    config::{ClusterConfig, MgmtConfig},
+    prelude::AppResult,
+    ssh,
+};
 
-use self::command_builder::CommandBuilder;
+use self::command_builder::{quote_for_shell, CommandBuilder};
 
-use crate::ssh::{SshConnection, SshCredentials};
+use crate::ssh::{RemoteOutput, SshConnection, SshCredentials};
+use crate::util::TrimmedNonEmptyText;
 use crate::{ChangesToUser, NewEntity};
 
-pub use listed_user::ListedUser;
+pub use account::{
+    accounts_to_pretty_table, coordinators_to_pretty_table, parse_accounts, parse_coordinators,
+    AccountCoordinators, AccountMembership, SlurmAccount, ACCOUNT_FIELDS, COORDINATOR_FIELDS,
+};
+pub use association::{
+    associations_to_pretty_table, parse_associations, SlurmAssociation, ASSOCIATION_FIELDS,
+    LIMIT_FIELDS,
+};
+pub use dump::{
+    dump_args, load_args, DumpAccount, DumpAssociation, DumpDiff, SlurmDump, REMOTE_DUMP_DIRECTORY,
+};
+pub use limit::SlurmLimit;
+pub use qos::{diff_qos, parse_qos, qos_fields, QosAttributes, QosChange, QosDifference};
+pub use queue::{
+    jobs_to_pretty_table, parse_queued_jobs, scancel_args, squeue_args, QueuedJob, SQUEUE_FORMAT,
+};
+pub use slurmrestd::{SlurmRestd, DEFAULT_API_VERSION, JWT_ENV_VARIABLE};
+pub use usage::{
+    parse_account_usage, parse_job_usage, sacct_args, sreport_args, AccountUsage, JobUsage,
+    UsageFilter, UsagePeriod, UsageReport, SACCT_FIELDS, SREPORT_FIELDS,
+};
+
+const SREPORT_NAME: &str = "sreport";
+const SACCT_NAME: &str = "sacct";
+const SQUEUE_NAME: &str = "squeue";
+const SCANCEL_NAME: &str = "scancel";
 
 /// Creates a user in a slurm database on a remote machine over ssh
 pub fn add_slurm_user<C>(
@@ -25,21 +70,23 @@ where
 {
     let action = CommandBuilder::new_add(
         entity.username.to_string(),
-        entity.group.id(),
+        entity.accounts.clone(),
         entity.default_qos.to_string(),
         entity.qos.clone().into(),
+        entity.limits.clone(),
     );
+    let accounts = memberships_to_text(&entity.accounts);
 
     run_slurm_action(action, config, session).with_context(|| {
         format!(
-            "Failed to add user {} with account {} to Slurm",
-            entity.username, entity.group
+            "Failed to add user {} with accounts {} to Slurm",
+            entity.username, accounts
         )
     })?;
 
     info!(
-        "Added user {} with account {}, QoS {:?} and default QoS {} to Slurm",
-        entity.username, entity.group, entity.qos, entity.default_qos
+        "Added user {} with accounts {}, QoS {:?} and default QoS {} to Slurm",
+        entity.username, accounts, entity.qos, entity.default_qos
     );
 
     Ok(())
@@ -66,7 +113,8 @@ where
 }
 
 /// Modifies a user in a slurm database via SSH session on a remote machine
-/// It currently only modifies the quality of services of a user !
+/// It only modifies the associations with accounts, the quality of services and the limits of a user !
+/// Associations are added before and removed after the other changes.
 ///
 /// # Errors
 ///
@@ -79,6 +127,21 @@ pub fn modify_slurm_user<C>(
 where
     C: SshCredentials,
 {
+    let username = modifiable.username.to_string();
+    if !modifiable.accounts.is_empty() || modifiable.default_account.is_some() {
+        let action = CommandBuilder::new_add_associations(
+            username.clone(),
+            modifiable.accounts.clone(),
+            modifiable.default_account.as_ref().map(ToString::to_string),
+        );
+        run_slurm_action(action, config, session)
+            .with_context(|| format!("Failed to add associations of user {} in Slurm", username))?;
+        info!(
+            "Added associations with accounts {} to user {} in Slurm",
+            memberships_to_text(&modifiable.accounts),
+            username
+        );
+    }
     if let Some((qos, default_qos)) = modifiable.may_qos_and_default_qos() {
         let action = CommandBuilder::new_modify_qos_default_qos(
             modifiable.username.to_string(),
@@ -88,26 +151,338 @@ where
 
         run_slurm_action(action, config, session)?;
     }
+    if !modifiable.limits.is_empty() {
+        let action = CommandBuilder::new_modify_limits(
+            modifiable.username.to_string(),
+            modifiable.limits.clone(),
+        );
+        run_slurm_action(action, config, session).with_context(|| {
+            format!(
+                "Failed to set limits of user {} in Slurm",
+                modifiable.username
+            )
+        })?;
+        info!(
+            "Set limits {} of user {} in Slurm",
+            limits_to_text(&modifiable.limits),
+            modifiable.username
+        );
+    }
+    if !modifiable.remove_accounts.is_empty() {
+        let action = CommandBuilder::new_remove_associations(
+            username.clone(),
+            modifiable.remove_accounts.clone(),
+        );
+        run_slurm_action(action, config, session).with_context(|| {
+            format!(
+                "Failed to remove associations of user {} in Slurm",
+                username
+            )
+        })?;
+        info!(
+            "Removed associations with accounts {} from user {} in Slurm",
+            memberships_to_text(&modifiable.remove_accounts),
+            username
+        );
+    }
     Ok(())
 }
 
-/// Lists all users in slurm database
+/// Returns the memberships as a comma separated list.
+fn memberships_to_text(memberships: &[AccountMembership]) -> String {
+    memberships
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the limits as a space separated list of key=value pairs.
+fn limits_to_text(limits: &[SlurmLimit]) -> String {
+    limits
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lists the associations of all users and accounts in the slurm database.
 ///
 /// # Errors
 ///
-/// See [`run_slurm_action`]
-pub fn list_users<T>(
+/// - See [`run_slurm_action`]
+/// - If the output of sacctmgr is malformed. See [`parse_associations`]
+pub fn list_associations<T>(
     config: &MgmtConfig,
     session: &SshConnection<T>,
-    parseable: bool,
-) -> AppResult<String>
+) -> AppResult<Vec<SlurmAssociation>>
 where
     T: SshCredentials,
 {
-    let action = CommandBuilder::new_show(parseable);
+    let action = CommandBuilder::new_show_associations(None);
     let output = run_slurm_action(action, config, session)?;
+    parse_associations(&output)
+}
 
-    Ok(output)
+/// Creates an account in a slurm database with the attributes which are given in `to_add`.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn add_account<C>(
+    to_add: &SlurmAccountToAdd,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let name = to_add.name.to_string();
+    let action = CommandBuilder::new_add_account(name, to_add.fields.clone());
+    run_slurm_action(action, config, session)
+        .with_context(|| format!("Failed to add account {} to Slurm", to_add.name))?;
+    info!("Added account {} to Slurm", to_add.name);
+    Ok(())
+}
+
+/// Deletes an account in a slurm database.
+/// Slurm refuses to delete an account as long as users are associated with it.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn delete_account<C>(name: &str, config: &MgmtConfig, session: &SshConnection<C>) -> AppResult
+where
+    C: SshCredentials,
+{
+    let action = CommandBuilder::new_delete_account(name.to_owned());
+    run_slurm_action(action, config, session)
+        .with_context(|| format!("Failed to delete account {} from Slurm", name))?;
+    info!("Deleted account {} from Slurm", name);
+    Ok(())
+}
+
+/// Sets the attributes which are given in `changes` on an account in a slurm database.
+///
+/// # Errors
+///
+/// - If no attribute to change is given.
+/// - See [`run_slurm_action`]
+pub fn modify_account<C>(
+    changes: &SlurmAccountChanges,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    if changes.fields.is_empty() {
+        bail!("Nothing to change for account {}", changes.name);
+    }
+    let action =
+        CommandBuilder::new_modify_account(changes.name.to_string(), changes.fields.clone());
+    run_slurm_action(action, config, session)
+        .with_context(|| format!("Failed to modify account {} in Slurm", changes.name))?;
+    info!("Modified account {} in Slurm", changes.name);
+    Ok(())
+}
+
+/// Lists all accounts in a slurm database.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+/// - If the output of sacctmgr is malformed. See [`parse_accounts`]
+pub fn list_accounts<C>(
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<Vec<SlurmAccount>>
+where
+    C: SshCredentials,
+{
+    let output = run_slurm_action(CommandBuilder::new_show_accounts(), config, session)?;
+    parse_accounts(&output)
+}
+
+/// Adds an account for every group in `valid_slurm_groups` of the configuration
+/// which does not exist in the slurm database yet.
+/// The accounts are named according to `account_mapping` of `cluster` if given.
+/// Returns the names of the added accounts.
+///
+/// # Errors
+///
+/// - If listing the existing accounts fails. See [`list_accounts`]
+/// - If a group is mapped to an invalid name. See [`ClusterConfig::map_account`]
+/// - If adding an account fails. See [`add_account`]
+pub fn bootstrap_accounts<C>(
+    parent: Option<&TrimmedNonEmptyText>,
+    cluster: Option<&ClusterConfig>,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<Vec<String>>
+where
+    C: SshCredentials,
+{
+    let existing = list_accounts(config, session)?;
+    let mut added = Vec::new();
+    for group in config.valid_slurm_groups.iter() {
+        let group = TrimmedNonEmptyText::try_from(group.as_str())
+            .with_context(|| format!("Invalid group {:?} in valid_slurm_groups", group))?;
+        let name = match cluster {
+            Some(cluster) => cluster.map_account(&group)?,
+            None => group,
+        };
+        if existing
+            .iter()
+            .any(|account| &account.name == name.as_ref())
+        {
+            debug!("Account {} exists already in Slurm", name);
+            continue;
+        }
+        let to_add = SlurmAccountToAdd {
+            name: name.clone(),
+            fields: SlurmAccountFields {
+                parent: parent.cloned(),
+                ..Default::default()
+            },
+        };
+        add_account(&to_add, config, session)?;
+        added.push(name.to_string());
+    }
+    Ok(added)
+}
+
+/// Makes a user a coordinator of an account in a slurm database.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn add_coordinator<C>(
+    account: &str,
+    username: &str,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let action = CommandBuilder::new_add_coordinator(username.to_owned(), account.to_owned());
+    run_slurm_action(action, config, session).with_context(|| {
+        format!(
+            "Failed to add {} as coordinator of account {} to Slurm",
+            username, account
+        )
+    })?;
+    info!(
+        "Added {} as coordinator of account {} to Slurm",
+        username, account
+    );
+    Ok(())
+}
+
+/// Removes a user as coordinator of an account in a slurm database.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn remove_coordinator<C>(
+    account: &str,
+    username: &str,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let action = CommandBuilder::new_remove_coordinator(username.to_owned(), account.to_owned());
+    run_slurm_action(action, config, session).with_context(|| {
+        format!(
+            "Failed to remove {} as coordinator of account {} from Slurm",
+            username, account
+        )
+    })?;
+    info!(
+        "Removed {} as coordinator of account {} from Slurm",
+        username, account
+    );
+    Ok(())
+}
+
+/// Lists all accounts which have coordinators in a slurm database.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+/// - If the output of sacctmgr is malformed. See [`parse_coordinators`]
+pub fn list_coordinators<C>(
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<Vec<AccountCoordinators>>
+where
+    C: SshCredentials,
+{
+    let output = run_slurm_action(CommandBuilder::new_show_coordinators(), config, session)?;
+    parse_coordinators(&output)
+}
+
+/// Lists all QOS in a slurm database with the attributes which can be defined in the configuration.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+/// - If the output of sacctmgr is malformed. See [`parse_qos`]
+pub fn list_qos<C>(
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<BTreeMap<String, QosAttributes>>
+where
+    C: SshCredentials,
+{
+    let output = run_slurm_action(CommandBuilder::new_show_qos(), config, session)?;
+    parse_qos(&output)
+}
+
+/// Returns the changes which are needed so that the QOS in the slurm database
+/// match the QOS defined in the field `qos` of the configuration.
+///
+/// # Errors
+///
+/// - If listing the QOS in the slurm database fails. See [`list_qos`]
+pub fn plan_qos_sync<C>(
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<Vec<QosChange>>
+where
+    C: SshCredentials,
+{
+    let in_slurm = list_qos(config, session)?;
+    Ok(diff_qos(&config.qos, &in_slurm))
+}
+
+/// Creates or modifies QOS in a slurm database as given by `changes`.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn apply_qos_changes<C>(
+    changes: &[QosChange],
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    for change in changes {
+        let (name, attributes) = (change.name().to_owned(), change.attributes_to_set());
+        let action = match change {
+            QosChange::Create { .. } => CommandBuilder::new_add_qos(name, attributes),
+            QosChange::Modify { .. } => CommandBuilder::new_modify_qos(name, attributes),
+        };
+        run_slurm_action(action, config, session)
+            .with_context(|| format!("Failed to sync QOS {} with Slurm", change.name()))?;
+        info!("Synced QOS {} with Slurm", change.name());
+    }
+    Ok(())
 }
 
 /// Runs the slurm command on a local machine or remotely somewhere else.
@@ -115,8 +490,7 @@ where
 ///
 /// # Errors
 ///
-/// - If running the command remotely fails. See [`run_remote_report_slurm_cmd`]
-/// - If running the command on the local machine. See [`run_remote_report_slurm_cmd`]
+/// - If running the command fails. See [`run_slurm_cmd`]
 pub fn run_slurm_action<C>(
     mut actions: CommandBuilder,
     config: &MgmtConfig,
@@ -129,51 +503,429 @@ where
     actions = actions
         .immediate(true)
         .sacctmgr_path(config.sacctmgr_path.clone());
+    for cmd in actions.shell_commands() {
+        let next_output = run_slurm_cmd(&cmd, config, session)?;
+        output.push_str(&next_output);
+    }
+    Ok(output)
+}
+
+/// Writes the accounting tree of `cluster` via `sacctmgr dump` into the local file at `file`.
+/// If Slurm commands run remotely, the dump is written into a private temporary directory
+/// on the head node, downloaded via SFTP and deleted together with the directory afterwards.
+/// See [`create_remote_dump_directory`].
+///
+/// # Errors
+///
+/// - If the temporary directory could not be created. See [`create_remote_dump_directory`]
+/// - If running sacctmgr fails. See [`run_slurm_tool`]
+/// - If the dump could not be downloaded. See [`SshConnection::download`]
+pub fn backup_accounting<C>(
+    cluster: &str,
+    file: &Path,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
     if config.run_slurm_remote {
-        for cmd in actions.remote_commands() {
-            debug!("Running remote Slurm command: {}", &cmd);
-            let next_output = run_remote_report_slurm_cmd(session, &cmd)?;
-            output.push_str(&next_output);
-        }
+        let directory = create_remote_dump_directory(session)?;
+        let remote = directory.join(format!("{}.cfg", cluster));
+        let dump = dump_args(cluster, &remote.to_string_lossy());
+        let downloaded = run_slurm_tool(&config.sacctmgr_path, dump, config, session)
+            .with_context(|| format!("Failed to dump the accounting tree of cluster {}", cluster))
+            .and_then(|_| session.download(&remote, file));
+        remove_remote_dump_directory(&directory, session);
+        downloaded?;
     } else {
-        for cmd in actions.local_commands() {
-            let next_output = run_local_and_report_slurm_cmd(cmd)?;
-            output.push_str(&next_output);
-        }
+        let dump = dump_args(cluster, &file.to_string_lossy());
+        run_slurm_tool(&config.sacctmgr_path, dump, config, session).with_context(|| {
+            format!("Failed to dump the accounting tree of cluster {}", cluster)
+        })?;
     }
-    Ok(output)
+    info!("Saved accounting tree of cluster {} to {:?}", cluster, file);
+    Ok(())
 }
 
+/// Reads the dump created by [`backup_accounting`] at the local path `file`.
+///
 /// # Errors
 ///
-/// - If execution of the command fails. See [`SshConnection::exec`].
-/// - If the exit code of executed command is an error code.
-pub fn run_remote_report_slurm_cmd<C>(session: &SshConnection<C>, cmd: &str) -> AppResult<String>
+/// - If the dump could not be read or parsed. See [`SlurmDump::parse`]
+pub fn read_dump(file: &Path) -> AppResult<SlurmDump> {
+    let content =
+        fs::read_to_string(file).with_context(|| format!("Could not read dump at {:?}", file))?;
+    SlurmDump::parse(&content).with_context(|| format!("Could not parse dump at {:?}", file))
+}
+
+/// Compares the accounts and associations of the dump `to_restore`
+/// with the current ones of the cluster named in the dump.
+///
+/// # Errors
+///
+/// - If the local temporary directory could not be created. See [`create_local_dump_directory`]
+/// - If the current accounting tree could not be dumped. See [`backup_accounting`]
+pub fn plan_restore<C>(
+    to_restore: &SlurmDump,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<DumpDiff>
 where
     C: SshCredentials,
 {
-    let (exit_code, output) = ssh::run_remote_command(session, cmd)
-        .with_context(|| format!("Error during remote Slurm command execution ({}).", cmd,))?;
+    let directory = create_local_dump_directory()?;
+    let current_file = directory.join(format!("{}.cfg", to_restore.cluster));
+    let current = backup_accounting(&to_restore.cluster, &current_file, config, session)
+        .and_then(|_| fs::read_to_string(&current_file).context("Could not read the current dump"));
+    if let Err(error) = fs::remove_dir_all(&directory) {
+        warn!(
+            "Could not delete temporary directory of dump at {:?}: {}",
+            directory, error
+        );
+    }
+    let current = SlurmDump::parse(&current?).context("Could not parse the current dump")?;
 
-    if exit_code == 0 {
-        debug!("Successfully executed remote Slurm command:{}", cmd);
-        Ok(output)
+    Ok(DumpDiff::new(&current, to_restore))
+}
+
+/// Loads the dump at the local path `file` via `sacctmgr load`.
+/// If Slurm commands run remotely, the dump is uploaded via SFTP into a private temporary directory
+/// on the head node first and deleted together with the directory afterwards.
+/// See [`create_remote_dump_directory`].
+/// With `clean`, everything of the cluster which is not in the dump is removed from Slurm.
+///
+/// # Errors
+///
+/// - If the temporary directory could not be created. See [`create_remote_dump_directory`]
+/// - If the dump could not be uploaded. See [`SshConnection::upload`]
+/// - If running sacctmgr fails. See [`run_slurm_tool`]
+pub fn restore_accounting<C>(
+    file: &Path,
+    clean: bool,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    if config.run_slurm_remote {
+        let directory = create_remote_dump_directory(session)?;
+        let remote = directory.join("restore.cfg");
+        let loaded = session
+            .upload(file, &remote, Some(0o600), None)
+            .and_then(|_| {
+                let load = load_args(&remote.to_string_lossy(), clean);
+                run_slurm_tool(&config.sacctmgr_path, load, config, session)
+                    .with_context(|| format!("Failed to load dump from {:?}", file))
+            });
+        remove_remote_dump_directory(&directory, session);
+        loaded?;
     } else {
-        Err(anyhow!(
-            "Error during remote Slurm command execution! Command '{}' returned exit code {}",
-            cmd,
-            exit_code
+        let load = load_args(&file.to_string_lossy(), clean);
+        run_slurm_tool(&config.sacctmgr_path, load, config, session)
+            .with_context(|| format!("Failed to load dump from {:?}", file))?;
+    }
+    info!("Restored accounting tree from {:?}", file);
+    Ok(())
+}
+
+/// Creates a temporary directory for a dump in transfer on the head node via `mktemp -d`.
+/// Only the SSH user can access the directory, so that other users can neither read
+/// nor replace the dump.
+///
+/// # Errors
+///
+/// - If `mktemp` fails or prints no directory.
+fn create_remote_dump_directory<C>(session: &SshConnection<C>) -> AppResult<PathBuf>
+where
+    C: SshCredentials,
+{
+    let template = format!("{}/usermgmt_dump.XXXXXXXXXX", REMOTE_DUMP_DIRECTORY);
+    let output = session
+        .exec(&format!("mktemp -d {}", quote_for_shell(template)))?
+        .check_success("Creation of a temporary directory for the dump")?;
+    let directory = output.stdout.trim();
+    if directory.is_empty() {
+        bail!("mktemp printed no temporary directory for the dump");
+    }
+    Ok(PathBuf::from(directory))
+}
+
+/// Deletes a temporary directory of [`create_remote_dump_directory`] with the dump inside.
+/// A failure is only logged.
+fn remove_remote_dump_directory<C>(directory: &Path, session: &SshConnection<C>)
+where
+    C: SshCredentials,
+{
+    let removed = session
+        .exec(&format!(
+            "rm -rf -- {}",
+            quote_for_shell(directory.to_string_lossy().into_owned())
         ))
+        .and_then(|output| output.check_success("Deletion of the temporary dump"));
+    if let Err(error) = removed {
+        warn!(
+            "Could not delete temporary dump directory {:?} on the head node: {:?}",
+            directory, error
+        );
     }
 }
 
+/// Creates a local temporary directory for a dump which only the current user can access.
+/// The name is unique so that concurrent runs do not use the same directory.
+///
 /// # Errors
 ///
-/// - If output of command could not be retrieved
-pub fn run_local_and_report_slurm_cmd(mut command: Command) -> AppResult<String> {
-    let output = command.output().context(
-        "Unable to execute sacctmgr command. Is the path specified in your config correct?",
+/// - If the directory could not be created, for example because it exists already.
+fn create_local_dump_directory() -> AppResult<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or_default();
+    let directory = std::env::temp_dir().join(format!("usermgmt_dump_{}_{}", process::id(), nanos));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&directory)
+        .with_context(|| format!("Could not create temporary directory {:?}", directory))?;
+    Ok(directory)
+}
+
+/// Reports the CPU and GPU hours used within `period` per account and user via sreport
+/// and per job via sacct.
+/// Both tools are expected in the same directory as sacctmgr, see `sacctmgr_path` in the configuration.
+///
+/// # Errors
+///
+/// - If running sreport or sacct fails. See [`run_slurm_tool`]
+/// - If the output of sreport or sacct is malformed. See [`parse_account_usage`] and [`parse_job_usage`]
+pub fn report_usage<C>(
+    filter: &UsageFilter,
+    period: &UsagePeriod,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<UsageReport>
+where
+    C: SshCredentials,
+{
+    let sreport = slurm_tool_path(config, SREPORT_NAME);
+    let output = run_slurm_tool(
+        &sreport,
+        usage::sreport_args(filter, period),
+        config,
+        session,
+    )
+    .context("Failed to report the usage of accounts via sreport")?;
+    let accounts = parse_account_usage(&output)?;
+
+    let sacct = slurm_tool_path(config, SACCT_NAME);
+    let output = run_slurm_tool(&sacct, usage::sacct_args(filter, period), config, session)
+        .context("Failed to list the jobs via sacct")?;
+    let jobs = parse_job_usage(&output)?;
+
+    Ok(UsageReport {
+        start: period.start(),
+        end: period.end(),
+        accounts,
+        jobs,
+    })
+}
+
+/// Lists the running and pending jobs of a user via squeue.
+/// squeue is expected in the same directory as the sacctmgr of `sacctmgr_path` in the configuration.
+///
+/// # Errors
+///
+/// - If running squeue fails. See [`run_slurm_tool`]
+/// - If the output of squeue is malformed. See [`parse_queued_jobs`]
+pub fn list_active_jobs<C>(
+    username: &str,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<Vec<QueuedJob>>
+where
+    C: SshCredentials,
+{
+    let squeue = slurm_tool_path(config, SQUEUE_NAME);
+    let output = run_slurm_tool(&squeue, squeue_args(username), config, session)
+        .with_context(|| format!("Failed to list the jobs of user {} via squeue", username))?;
+    parse_queued_jobs(&output)
+}
+
+/// Cancels all jobs of a user via scancel.
+/// scancel is expected in the same directory as the sacctmgr of `sacctmgr_path` in the configuration.
+///
+/// # Errors
+///
+/// - If running scancel fails. See [`run_slurm_tool`]
+pub fn cancel_jobs<C>(username: &str, config: &MgmtConfig, session: &SshConnection<C>) -> AppResult
+where
+    C: SshCredentials,
+{
+    let scancel = slurm_tool_path(config, SCANCEL_NAME);
+    run_slurm_tool(&scancel, scancel_args(username), config, session)
+        .with_context(|| format!("Failed to cancel the jobs of user {} via scancel", username))?;
+    info!("Cancelled all jobs of user {}", username);
+    Ok(())
+}
+
+/// Runs the Slurm tool at `path` with the arguments `args`
+/// on the head node or locally like [`run_slurm_action`] does.
+///
+/// # Errors
+///
+/// - If running the tool fails. See [`run_slurm_cmd`]
+pub fn run_slurm_tool<C>(
+    path: &str,
+    args: Vec<String>,
+    config: &MgmtConfig,
+    session: &SshConnection<C>,
+) -> AppResult<String>
+where
+    C: SshCredentials,
+{
+    let cmd = std::iter::once(path.to_owned())
+        .chain(args.into_iter().map(quote_for_shell))
+        .collect::<Vec<_>>()
+        .join(" ");
+    run_slurm_cmd(&cmd, config, session)
+}
+
+/// Runs the shell command `cmd` on the head node if `run_slurm_remote` is set in `config`.
+/// Otherwise it runs on the local machine the same way as commands for hosts with `local = true`.
+/// Returns the stdout of the command.
+///
+/// # Errors
+///
+/// - If running the command remotely fails. See [`run_remote_report_slurm_cmd`]
+/// - If running the command on the local machine fails. See [`run_local_report_slurm_cmd`]
+fn run_slurm_cmd<C>(cmd: &str, config: &MgmtConfig, session: &SshConnection<C>) -> AppResult<String>
+where
+    C: SshCredentials,
+{
+    if config.run_slurm_remote {
+        debug!("Running remote Slurm command: {}", cmd);
+        run_remote_report_slurm_cmd(session, cmd)
+    } else {
+        debug!("Running local Slurm command: {}", cmd);
+        run_local_report_slurm_cmd(cmd, config)
+    }
+}
+
+/// Returns the path of a Slurm tool like sreport which is installed next to sacctmgr.
+fn slurm_tool_path(config: &MgmtConfig, tool: &str) -> String {
+    match Path::new(&config.sacctmgr_path)
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+    {
+        Some(directory) => directory.join(tool).to_string_lossy().into_owned(),
+        None => tool.to_owned(),
+    }
+}
+
+/// # Errors
+///
+/// - If execution of the command fails. See [`SshConnection::exec`].
+/// - If the command failed. See [`slurm_command_stdout`]
+pub fn run_remote_report_slurm_cmd<C>(session: &SshConnection<C>, cmd: &str) -> AppResult<String>
+where
+    C: SshCredentials,
+{
+    let output = ssh::run_remote_command(session, cmd)
+        .with_context(|| format!("Error during remote Slurm command execution ({}).", cmd,))?;
+    slurm_command_stdout(output, cmd)
+}
+
+/// # Errors
+///
+/// - If execution of the command fails. See [`ssh::run_local_command`].
+/// - If the command failed. See [`slurm_command_stdout`]
+pub fn run_local_report_slurm_cmd(cmd: &str, config: &MgmtConfig) -> AppResult<String> {
+    let output = ssh::run_local_command(cmd, config).context(
+        "Unable to execute Slurm command. Is the path of sacctmgr specified in your config correct?",
     )?;
+    slurm_command_stdout(output, cmd)
+}
+
+/// Returns the stdout of the Slurm command `cmd` if it succeeded.
+///
+/// # Errors
+///
+/// - If the command failed. See [`RemoteOutput::check_success`]
+fn slurm_command_stdout(output: RemoteOutput, cmd: &str) -> AppResult<String> {
+    let output = output.check_success(&format!("Slurm command '{}'", cmd))?;
+    debug!(
+        "Successfully executed Slurm command '{}' in {:?}",
+        cmd, output.duration
+    );
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::ssh::SshGivenCredential;
+    use fake_tools::FakeSlurmTools;
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    #[test]
+    fn returns_stdout_only_for_successful_slurm_commands() {
+        let output = RemoteOutput {
+            stdout: "jane|staff\n".to_owned(),
+            stderr: "sacctmgr: error: Unknown option: --parseable3\n".to_owned(),
+            exit_code: 1,
+            ..Default::default()
+        };
+        let success = RemoteOutput {
+            exit_code: 0,
+            ..output.clone()
+        };
+        let without_stderr = RemoteOutput {
+            stderr: String::new(),
+            ..output.clone()
+        };
+
+        assert_eq!(
+            "jane|staff\n",
+            slurm_command_stdout(success, "sacctmgr show user").unwrap()
+        );
+        assert_eq!(
+            "Slurm command 'sacctmgr show user' returned exit code 1 with error output: sacctmgr: error: Unknown option: --parseable3",
+            slurm_command_stdout(output, "sacctmgr show user")
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "Slurm command 'sacctmgr show user' returned exit code 1",
+            slurm_command_stdout(without_stderr, "sacctmgr show user")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn fails_backup_and_restore_if_sacctmgr_fails() {
+        let fake = FakeSlurmTools::new(
+            "failing_sacctmgr",
+            &[(
+                "sacctmgr",
+                "echo 'sacctmgr: error: Problem talking to the database' >&2; exit 1",
+            )],
+        );
+        let config = fake.config();
+        let session = SshConnection::from_head_node(config, SshGivenCredential::default());
+        let file = fake.directory().join("hpc.cfg");
+
+        let backup = backup_accounting("hpc", &file, config, &session);
+        fs::write(&file, "Cluster - 'hpc'\n").unwrap();
+        let restore = restore_accounting(&file, false, config, &session);
+
+        for outcome in [backup, restore] {
+            let error = format!("{:?}", outcome.unwrap_err());
+            assert!(
+                error.contains("Problem talking to the database"),
+                "{}",
+                error
+            );
+        }
+    }
 }
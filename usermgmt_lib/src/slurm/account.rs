@@ -0,0 +1,291 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::util::TrimmedNonEmptyText;
+
+/// Character which splits cells in a row of the output of sacctmgr with the option --parsable2
+const SPLIT_BETWEEN_CELLS: char = '|';
+/// Character which splits account and partition of an [`AccountMembership`] given as text
+const SPLIT_ACCOUNT_PARTITION: char = ':';
+
+/// Fields which sacctmgr is asked for when listing accounts.
+/// The output of sacctmgr contains the fields in exactly this order.
+/// The field `User` is only needed to filter out the associations of users with an account.
+pub const ACCOUNT_FIELDS: [&str; 6] =
+    ["Account", "Descr", "Org", "ParentName", "Fairshare", "User"];
+
+/// Fields which sacctmgr is asked for when listing the coordinators of accounts.
+pub const COORDINATOR_FIELDS: [&str; 2] = ["Account", "Coordinators"];
+/// Character which splits the names of several coordinators of an account
+const SPLIT_BETWEEN_COORDINATORS: char = ',';
+
+/// Account (bank) in the Slurm database to which users are associated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlurmAccount {
+    pub name: String,
+    pub description: String,
+    pub organization: String,
+    /// `None` for the account root
+    pub parent: Option<String>,
+    pub fairshare: String,
+}
+
+impl SlurmAccount {
+    /// Returns the values of the account in the order of [`SlurmAccount::headers`].
+    pub fn cells(&self) -> [&str; 5] {
+        [
+            &self.name,
+            &self.description,
+            &self.organization,
+            self.parent.as_deref().unwrap_or_default(),
+            &self.fairshare,
+        ]
+    }
+
+    pub fn headers() -> [&'static str; 5] {
+        [
+            "Account",
+            "Description",
+            "Organization",
+            "Parent",
+            "Fairshare",
+        ]
+    }
+}
+
+/// Users who coordinate an account. A coordinator can manage the users of the account and its sub accounts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountCoordinators {
+    pub account: String,
+    pub coordinators: Vec<String>,
+}
+
+/// Association of a user with an account, optionally only for one partition.
+/// Given as text in the form `account` or `account:partition`, e.g. `proj1` or `proj1:gpu`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMembership {
+    pub account: TrimmedNonEmptyText,
+    pub partition: Option<TrimmedNonEmptyText>,
+}
+
+impl AccountMembership {
+    /// Membership for all partitions of the account
+    pub fn new(account: TrimmedNonEmptyText) -> Self {
+        Self {
+            account,
+            partition: None,
+        }
+    }
+}
+
+impl FromStr for AccountMembership {
+    type Err = AppError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (account, partition) = match text.split_once(SPLIT_ACCOUNT_PARTITION) {
+            Some((account, partition)) => (account, Some(partition)),
+            None => (text, None),
+        };
+        let account = TrimmedNonEmptyText::try_from(account)
+            .with_context(|| format!("No account given in '{}'", text))?;
+        let partition = partition
+            .map(TrimmedNonEmptyText::try_from)
+            .transpose()
+            .with_context(|| format!("No partition given after ':' in '{}'", text))?;
+        Ok(Self { account, partition })
+    }
+}
+
+impl Display for AccountMembership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.partition {
+            Some(partition) => write!(
+                f,
+                "{}{}{}",
+                self.account, SPLIT_ACCOUNT_PARTITION, partition
+            ),
+            None => write!(f, "{}", self.account),
+        }
+    }
+}
+
+/// Parses the output of sacctmgr with the options `--parsable2 --noheader` and the fields [`ACCOUNT_FIELDS`]
+/// into accounts. Rows of users associated with an account are skipped.
+/// Every account is returned once even if it exists on several clusters.
+///
+/// # Errors
+///
+/// - If the number of fields in a line is not the number of [`ACCOUNT_FIELDS`].
+pub fn parse_accounts(output: &str) -> AppResult<Vec<SlurmAccount>> {
+    let mut accounts: Vec<SlurmAccount> = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let [name, description, organization, parent, fairshare, user] = cells.as_slice() else {
+            bail!(
+                "Malformed account in line {} of sacctmgr output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                ACCOUNT_FIELDS.len(),
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        if !user.is_empty() || accounts.iter().any(|known| known.name == *name) {
+            continue;
+        }
+        accounts.push(SlurmAccount {
+            name: name.to_string(),
+            description: description.to_string(),
+            organization: organization.to_string(),
+            parent: (!parent.is_empty()).then(|| parent.to_string()),
+            fairshare: fairshare.to_string(),
+        });
+    }
+    Ok(accounts)
+}
+
+/// Parses the output of sacctmgr with the options `--parsable2 --noheader` and the fields [`COORDINATOR_FIELDS`].
+/// Accounts without coordinators are skipped.
+/// Every account is returned once even if it exists on several clusters.
+///
+/// # Errors
+///
+/// - If the number of fields in a line is not the number of [`COORDINATOR_FIELDS`].
+pub fn parse_coordinators(output: &str) -> AppResult<Vec<AccountCoordinators>> {
+    let mut all: Vec<AccountCoordinators> = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let [account, coordinators] = cells.as_slice() else {
+            bail!(
+                "Malformed coordinators in line {} of sacctmgr output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                COORDINATOR_FIELDS.len(),
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        let coordinators: Vec<String> = coordinators
+            .split(SPLIT_BETWEEN_COORDINATORS)
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        if coordinators.is_empty() || all.iter().any(|known| known.account == *account) {
+            continue;
+        }
+        all.push(AccountCoordinators {
+            account: account.to_string(),
+            coordinators,
+        });
+    }
+    Ok(all)
+}
+
+/// Returns a pretty ASCII table with one account and its coordinators per row.
+pub fn coordinators_to_pretty_table(coordinators: &[AccountCoordinators]) -> String {
+    use prettytable::{Row, Table};
+
+    let mut table = Table::new();
+    table.set_titles(Row::from(["Account", "Coordinators"]));
+    for account in coordinators {
+        table.add_row(Row::from([
+            account.account.as_str(),
+            &account.coordinators.join(", "),
+        ]));
+    }
+    table.to_string()
+}
+
+/// Returns a pretty ASCII table with one account per row.
+pub fn accounts_to_pretty_table(accounts: &[SlurmAccount]) -> String {
+    use prettytable::{Row, Table};
+
+    let mut table = Table::new();
+    table.set_titles(Row::from(SlurmAccount::headers()));
+    for account in accounts {
+        table.add_row(Row::from(account.cells()));
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_accounts_without_user_rows() {
+        let input = "root|default root account|root||1|\n\
+                     root|default root account|root||1|root\n\
+                     staff|Staff members|thn|root|10|\n\
+                     staff|Staff members|thn|root|1|jane\n\
+                     student|student|student|root|1|\n\
+                     staff|Staff members|thn|root|10|\n";
+
+        let actual = parse_accounts(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+    }
+
+    #[test]
+    fn parses_account_memberships() {
+        let actual: AccountMembership = "proj1:gpu".parse().unwrap();
+        assert_eq!(
+            ("proj1".to_owned(), Some("gpu".to_owned())),
+            (
+                actual.account.to_string(),
+                actual.partition.as_ref().map(ToString::to_string)
+            )
+        );
+        assert_eq!("proj1:gpu", actual.to_string());
+        assert_eq!(
+            "staff",
+            "staff".parse::<AccountMembership>().unwrap().to_string()
+        );
+        assert!("proj1:".parse::<AccountMembership>().is_err());
+        assert!(":gpu".parse::<AccountMembership>().is_err());
+    }
+
+    #[test]
+    fn parses_coordinators_of_accounts() {
+        let input = "root|\n\
+                     staff|jane,john\n\
+                     student|\n\
+                     proj1|zoe\n\
+                     staff|jane,john\n";
+
+        let actual = parse_coordinators(input).unwrap();
+        assert_eq!(
+            vec![
+                AccountCoordinators {
+                    account: "staff".to_owned(),
+                    coordinators: vec!["jane".to_owned(), "john".to_owned()],
+                },
+                AccountCoordinators {
+                    account: "proj1".to_owned(),
+                    coordinators: vec!["zoe".to_owned()],
+                },
+            ],
+            actual
+        );
+        assert!(parse_coordinators("staff|jane|john\n").is_err());
+    }
+
+    #[test]
+    fn reports_malformed_line() {
+        let input = "root|default root account|root||1|\nstaff|Staff members\n";
+
+        let actual = parse_accounts(input).unwrap_err().to_string();
+        assert_eq!(
+            "Malformed account in line 2 of sacctmgr output: 'staff|Staff members': \
+             Expected 6 fields separated by '|' but found 2",
+            actual
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+/// Character which splits cells in a row of the output of sacctmgr with the option --parsable2
+const SPLIT_BETWEEN_CELLS: char = '|';
+/// Character which splits the values of a list within a cell like the QOS
+const SPLIT_IN_CELL: char = ',';
+
+/// Fields which sacctmgr is asked for when listing associations.
+/// The output of sacctmgr contains the fields in exactly this order.
+pub const ASSOCIATION_FIELDS: [&str; 5 + LIMIT_FIELDS.len()] = [
+    "User",
+    "Account",
+    "Partition",
+    "DefaultQOS",
+    "QOS",
+    LIMIT_FIELDS[0],
+    LIMIT_FIELDS[1],
+    LIMIT_FIELDS[2],
+    LIMIT_FIELDS[3],
+    LIMIT_FIELDS[4],
+    LIMIT_FIELDS[5],
+    LIMIT_FIELDS[6],
+];
+
+/// Fields of an association which limit the resources of a user.
+/// They are listed after the other fields in [`ASSOCIATION_FIELDS`].
+pub const LIMIT_FIELDS: [&str; 7] = [
+    "GrpTRES",
+    "GrpJobs",
+    "GrpSubmit",
+    "MaxTRES",
+    "MaxJobs",
+    "MaxSubmit",
+    "MaxWall",
+];
+
+/// Association of a user with an account in the Slurm database.
+/// An association without a user is the association of the account itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlurmAssociation {
+    pub user: String,
+    pub account: String,
+    pub default_qos: String,
+    pub qos: Vec<String>,
+    /// `None` if the association applies to all partitions
+    pub partition: Option<String>,
+    /// Limits which are set, keyed by their name in [`LIMIT_FIELDS`]
+    pub limits: BTreeMap<String, String>,
+}
+
+impl SlurmAssociation {
+    /// Parses one line of the output of sacctmgr with the options `--parsable2 --noheader`.
+    /// The line must contain the fields in the order of [`ASSOCIATION_FIELDS`].
+    ///
+    /// # Errors
+    ///
+    /// - If the number of fields in the line is not the number of [`ASSOCIATION_FIELDS`].
+    pub fn from_parsable_line(line: &str) -> AppResult<Self> {
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let (user, account, partition, default_qos, qos, limits) = match cells.as_slice() {
+            [user, account, partition, default_qos, qos, limits @ ..]
+                if limits.len() == LIMIT_FIELDS.len() =>
+            {
+                (user, account, partition, default_qos, qos, limits)
+            }
+            _ => bail!(
+                "Expected {} fields separated by '{}' but found {}",
+                ASSOCIATION_FIELDS.len(),
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            ),
+        };
+
+        let qos = qos
+            .split(SPLIT_IN_CELL)
+            .filter(|qos| !qos.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        let partition = (!partition.is_empty()).then(|| partition.to_string());
+        let limits = LIMIT_FIELDS
+            .iter()
+            .zip(limits)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Ok(Self {
+            user: user.to_string(),
+            account: account.to_string(),
+            default_qos: default_qos.to_string(),
+            qos,
+            partition,
+            limits,
+        })
+    }
+
+    /// Returns the values of the association in the order of [`ASSOCIATION_FIELDS`].
+    /// Limits which are not in [`LIMIT_FIELDS`] are left out.
+    pub fn cells(&self) -> Vec<String> {
+        let mut cells = vec![
+            self.user.clone(),
+            self.account.clone(),
+            self.partition.clone().unwrap_or_default(),
+            self.default_qos.clone(),
+            self.qos.join(&SPLIT_IN_CELL.to_string()),
+        ];
+        cells.extend(
+            LIMIT_FIELDS
+                .iter()
+                .map(|name| self.limits.get(*name).cloned().unwrap_or_default()),
+        );
+        cells
+    }
+
+    /// Returns the association as one line in the same form as [`SlurmAssociation::from_parsable_line`] expects it.
+    pub fn to_parsable_line(&self) -> String {
+        self.cells().join(&SPLIT_BETWEEN_CELLS.to_string())
+    }
+}
+
+/// Parses the output of sacctmgr with the options `--parsable2 --noheader` into associations.
+/// Empty lines are skipped.
+///
+/// # Errors
+///
+/// - If a line is malformed. See [`SlurmAssociation::from_parsable_line`]
+pub fn parse_associations(output: &str) -> AppResult<Vec<SlurmAssociation>> {
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            SlurmAssociation::from_parsable_line(line).with_context(|| {
+                format!(
+                    "Malformed association in line {} of sacctmgr output: '{}'",
+                    index + 1,
+                    line
+                )
+            })
+        })
+        .collect()
+}
+
+/// Returns a pretty ASCII table with the fields of [`ASSOCIATION_FIELDS`] as titles and one association per row.
+pub fn associations_to_pretty_table(associations: &[SlurmAssociation]) -> String {
+    use prettytable::{Row, Table};
+
+    let mut table = Table::new();
+    table.set_titles(Row::from(ASSOCIATION_FIELDS));
+    for association in associations {
+        table.add_row(Row::from(association.cells()));
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_associations() {
+        let input = "\
+            |root||normal|normal|||||||\n\
+            jane|staff||advanced|advanced,interactive|||||||\n\
+            \n\
+            john|student|gpu|basic|basic|cpu=8|||gres/gpu=2|10|20|1-00:00:00\n";
+
+        let actual = parse_associations(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+    }
+
+    #[test]
+    fn renders_associations_as_table() {
+        let input = "\
+            |root||normal|normal|||||||\n\
+            john|student|gpu|basic|basic,interactive|cpu=8|||gres/gpu=2|10|20|1-00:00:00\n";
+
+        let associations = parse_associations(input).unwrap();
+        insta::assert_snapshot!(associations_to_pretty_table(&associations));
+    }
+
+    #[test]
+    fn round_trips_between_text_and_association() {
+        let lines = [
+            "|root||normal|normal|||||||",
+            "jane|staff||advanced|advanced,interactive|||||||",
+            "john|student|gpu|basic|basic|cpu=8|||gres/gpu=2|10|20|1-00:00:00",
+            "anna|staff||||||||||",
+        ];
+        for line in lines {
+            let association = SlurmAssociation::from_parsable_line(line).unwrap();
+            assert_eq!(line, association.to_parsable_line());
+            assert_eq!(
+                association,
+                SlurmAssociation::from_parsable_line(&association.to_parsable_line()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_last_field_without_trailing_separator() {
+        let association =
+            SlurmAssociation::from_parsable_line("jane|staff||basic|basic|||||||2-00:00:00")
+                .unwrap();
+        assert_eq!(
+            Some(&"2-00:00:00".to_owned()),
+            association.limits.get("MaxWall")
+        );
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let too_few = "jane|staff||basic|basic|||||||\n\
+                       john|staff||basic|basic|||||\n";
+        let error = parse_associations(too_few).unwrap_err();
+        insta::assert_snapshot!(format!("{:#}", error));
+
+        let too_many = "jane|staff||basic|basic||||||||\n";
+        assert!(parse_associations(too_many).is_err());
+    }
+}
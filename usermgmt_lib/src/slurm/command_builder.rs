@@ -1,7 +1,10 @@
-use crate::Group;
+use super::{
+    qos_fields, AccountMembership, SlurmLimit, ACCOUNT_FIELDS, ASSOCIATION_FIELDS,
+    COORDINATOR_FIELDS,
+};
+use crate::cli::SlurmAccountFields;
 use std::collections::HashMap;
 use std::iter;
-use std::process::Command;
 
 const SACCTMG_NAME: &str = "sacctmgr";
 const IMMEDIATE: &str = "--immediate";
@@ -14,28 +17,85 @@ const SET: &str = "set";
 const ASSOCIATION: &str = "assoc";
 const USER: &str = "User";
 const ACCOUNT: &str = "Account";
+const ACCOUNT_ENTITY: &str = "account";
+const PARTITION: &str = "Partition";
+const DEFAULT_ACCOUNT: &str = "DefaultAccount";
+const WITH_ASSOCIATIONS: &str = "withassoc";
+const COORDINATOR_ENTITY: &str = "coordinator";
+const WITH_COORDINATORS: &str = "withcoordinator";
+const NAMES: &str = "Names";
+const NAME: &str = "Name";
+const PARENT: &str = "Parent";
+const DESCRIPTION: &str = "Description";
+const ORGANIZATION: &str = "Organization";
+const FAIRSHARE: &str = "Fairshare";
+const QOS_ENTITY: &str = "qos";
 const DEFAULT_QOS: &str = "DefaultQOS";
 const QOS: &str = "QOS";
-const SLURM_PRASEABLE_ARG: &str = "--parsable";
+const SLURM_PRASEABLE_WITHOUT_TRAILING_ARG: &str = "--parsable2";
+const NO_HEADER_ARG: &str = "--noheader";
+const WHERE: &str = "where";
 
 enum SlurmSubCommand {
-    Add { group: Group },
+    Add(AccountMembership),
     Delete,
+    /// Removes the associations of a user with an account, only for one partition if given
+    RemoveAssociation(AccountMembership),
     Modify(HashMap<&'static str, Vec<String>>),
-    Show { parseable: bool },
+    /// Lists associations with the fields [`ASSOCIATION_FIELDS`] for the typed parsing
+    ShowAssociations,
+    AddAccount {
+        name: String,
+        fields: SlurmAccountFields,
+    },
+    DeleteAccount {
+        name: String,
+    },
+    ModifyAccount {
+        name: String,
+        fields: SlurmAccountFields,
+    },
+    /// Lists accounts with the fields [`ACCOUNT_FIELDS`] for the typed parsing
+    ShowAccounts,
+    /// Makes the user a coordinator of the account
+    AddCoordinator {
+        account: String,
+    },
+    RemoveCoordinator {
+        account: String,
+    },
+    /// Lists accounts with the fields [`COORDINATOR_FIELDS`] for the typed parsing
+    ShowCoordinators,
+    AddQos {
+        name: String,
+        attributes: Vec<(&'static str, String)>,
+    },
+    ModifyQos {
+        name: String,
+        attributes: Vec<(&'static str, String)>,
+    },
+    /// Lists QOS with the fields of [`qos_fields`] for the typed parsing
+    ShowQos,
 }
 
 fn from_username(value: SlurmSubCommand, username: String) -> Vec<String> {
     match value {
-        SlurmSubCommand::Add { group } => {
-            vec![
-                SUB_COMMAND_ADD.into(),
+        SlurmSubCommand::Add(membership) => {
+            let mut command = vec![SUB_COMMAND_ADD.into(), USER.into(), username];
+            command.extend(membership_args(membership));
+            command
+        }
+        SlurmSubCommand::Delete => vec![SUB_COMMAND_DELETE.into(), USER.into(), username],
+        SlurmSubCommand::RemoveAssociation(membership) => {
+            let mut command = vec![
+                SUB_COMMAND_DELETE.into(),
                 USER.into(),
                 username,
-                format!("{}={}", ACCOUNT, group.to_string()),
-            ]
+                WHERE.into(),
+            ];
+            command.extend(membership_args(membership));
+            command
         }
-        SlurmSubCommand::Delete => vec![SUB_COMMAND_DELETE.into(), USER.into(), username],
         SlurmSubCommand::Modify(map) => {
             let mut to_set: Vec<String> = map
                 .into_iter()
@@ -47,25 +107,142 @@ fn from_username(value: SlurmSubCommand, username: String) -> Vec<String> {
                 .chain(to_set)
                 .collect()
         }
-        SlurmSubCommand::Show { parseable } => {
-            let mut command = if parseable {
-                vec![SLURM_PRASEABLE_ARG.to_owned()]
-            } else {
-                Vec::new()
-            };
-            command.extend_from_slice(&[
+        SlurmSubCommand::ShowAssociations => {
+            let mut command = vec![
+                SLURM_PRASEABLE_WITHOUT_TRAILING_ARG.into(),
+                NO_HEADER_ARG.into(),
                 SUB_COMMAND_SHOW.into(),
                 ASSOCIATION.into(),
-                format!("format={}%30,{},{},{}%80", USER, ACCOUNT, DEFAULT_QOS, QOS),
-            ]);
+            ];
+            if !username.is_empty() {
+                command.extend([WHERE.into(), format!("{}={}", USER, username)]);
+            }
+            command.push(format!("format={}", ASSOCIATION_FIELDS.join(",")));
             command
         }
+        SlurmSubCommand::AddAccount { name, fields } => {
+            vec![SUB_COMMAND_ADD.into(), ACCOUNT_ENTITY.into(), name]
+                .into_iter()
+                .chain(account_field_args(fields))
+                .collect()
+        }
+        SlurmSubCommand::DeleteAccount { name } => {
+            vec![SUB_COMMAND_DELETE.into(), ACCOUNT_ENTITY.into(), name]
+        }
+        SlurmSubCommand::ModifyAccount { name, fields } => vec![
+            SUB_COMMAND_MODIFY.into(),
+            ACCOUNT_ENTITY.into(),
+            WHERE.into(),
+            format!("{}={}", NAME, name),
+            SET.into(),
+        ]
+        .into_iter()
+        .chain(account_field_args(fields))
+        .collect(),
+        SlurmSubCommand::ShowAccounts => vec![
+            SLURM_PRASEABLE_WITHOUT_TRAILING_ARG.into(),
+            NO_HEADER_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            ACCOUNT_ENTITY.into(),
+            WITH_ASSOCIATIONS.into(),
+            format!("format={}", ACCOUNT_FIELDS.join(",")),
+        ],
+        SlurmSubCommand::AddCoordinator { account } => vec![
+            SUB_COMMAND_ADD.into(),
+            COORDINATOR_ENTITY.into(),
+            format!("{}={}", ACCOUNT, account),
+            format!("{}={}", NAMES, username),
+        ],
+        SlurmSubCommand::RemoveCoordinator { account } => vec![
+            SUB_COMMAND_DELETE.into(),
+            COORDINATOR_ENTITY.into(),
+            format!("{}={}", ACCOUNT, account),
+            format!("{}={}", NAMES, username),
+        ],
+        SlurmSubCommand::ShowCoordinators => vec![
+            SLURM_PRASEABLE_WITHOUT_TRAILING_ARG.into(),
+            NO_HEADER_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            ACCOUNT_ENTITY.into(),
+            WITH_COORDINATORS.into(),
+            format!("format={}", COORDINATOR_FIELDS.join(",")),
+        ],
+        SlurmSubCommand::AddQos { name, attributes } => {
+            vec![SUB_COMMAND_ADD.into(), QOS_ENTITY.into(), name]
+                .into_iter()
+                .chain(key_value_args(attributes))
+                .collect()
+        }
+        SlurmSubCommand::ModifyQos { name, attributes } => vec![
+            SUB_COMMAND_MODIFY.into(),
+            QOS_ENTITY.into(),
+            WHERE.into(),
+            format!("{}={}", NAME, name),
+            SET.into(),
+        ]
+        .into_iter()
+        .chain(key_value_args(attributes))
+        .collect(),
+        SlurmSubCommand::ShowQos => vec![
+            SLURM_PRASEABLE_WITHOUT_TRAILING_ARG.into(),
+            NO_HEADER_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            QOS_ENTITY.into(),
+            format!("format={}", qos_fields().join(",")),
+        ],
+    }
+}
+
+/// Returns the key value pairs for the account and the partition if given.
+fn membership_args(membership: AccountMembership) -> Vec<String> {
+    let AccountMembership { account, partition } = membership;
+    iter::once(format!("{}={}", ACCOUNT, account))
+        .chain(partition.map(|partition| format!("{}={}", PARTITION, partition)))
+        .collect()
+}
+
+fn key_value_args(attributes: Vec<(&'static str, String)>) -> impl Iterator<Item = String> {
+    attributes
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+}
+
+/// Returns key value pairs for all attributes of an account which are present.
+fn account_field_args(fields: SlurmAccountFields) -> Vec<String> {
+    let SlurmAccountFields {
+        parent,
+        description,
+        organization,
+        fairshare,
+    } = fields;
+    [
+        (PARENT, parent.map(|parent| parent.to_string())),
+        (DESCRIPTION, description.map(|descr| descr.to_string())),
+        (ORGANIZATION, organization.map(|org| org.to_string())),
+        (FAIRSHARE, fairshare.map(|shares| shares.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+    .collect()
+}
+
+/// Quotes an argument for a shell if it contains characters with a special meaning for the shell.
+/// Single quotes within the argument are escaped.
+pub(super) fn quote_for_shell(argument: String) -> String {
+    let is_plain = argument.chars().all(|next| {
+        next.is_ascii_alphanumeric()
+            || matches!(next, '-' | '_' | '=' | ',' | '.' | '/' | '%' | ':')
+    });
+    if is_plain && !argument.is_empty() {
+        argument
+    } else {
+        format!("'{}'", argument.replace('\'', "'\\''"))
     }
 }
 
-/// Builder to construct slurm commands for execution as local process commands or as strings for
-/// ssh remote execution.
-/// One or more commands are added before the call of [`remote_commands`] for remote or [`local_commanded`] for local
+/// Builder to construct slurm commands as lines for a shell, which run over SSH on a remote machine
+/// or on the local machine.
+/// One or more commands are added before the call of [`CommandBuilder::shell_commands`]
 pub struct CommandBuilder {
     sub_commands: Vec<SlurmSubCommand>,
     username: String,
@@ -78,18 +255,109 @@ impl CommandBuilder {
         Self::new_inner(username, vec![SlurmSubCommand::Delete])
     }
 
-    pub fn new_show(parseable: bool) -> Self {
+    /// Lists the associations of the user `username` or of all users and accounts if `username` is `None`.
+    pub fn new_show_associations(username: Option<String>) -> Self {
+        Self::new_inner(
+            username.unwrap_or_default(),
+            vec![SlurmSubCommand::ShowAssociations],
+        )
+    }
+
+    pub fn new_add_account(name: String, fields: SlurmAccountFields) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::AddAccount { name, fields }],
+        )
+    }
+
+    pub fn new_delete_account(name: String) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::DeleteAccount { name }],
+        )
+    }
+
+    pub fn new_modify_account(name: String, fields: SlurmAccountFields) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ModifyAccount { name, fields }],
+        )
+    }
+
+    pub fn new_show_accounts() -> Self {
+        Self::new_inner(Default::default(), vec![SlurmSubCommand::ShowAccounts])
+    }
+
+    pub fn new_add_coordinator(username: String, account: String) -> Self {
+        Self::new_inner(username, vec![SlurmSubCommand::AddCoordinator { account }])
+    }
+
+    pub fn new_remove_coordinator(username: String, account: String) -> Self {
+        Self::new_inner(
+            username,
+            vec![SlurmSubCommand::RemoveCoordinator { account }],
+        )
+    }
+
+    pub fn new_show_coordinators() -> Self {
+        Self::new_inner(Default::default(), vec![SlurmSubCommand::ShowCoordinators])
+    }
+
+    pub fn new_add_qos(name: String, attributes: Vec<(&'static str, String)>) -> Self {
         Self::new_inner(
             Default::default(),
-            vec![SlurmSubCommand::Show { parseable }],
+            vec![SlurmSubCommand::AddQos { name, attributes }],
         )
     }
 
+    pub fn new_modify_qos(name: String, attributes: Vec<(&'static str, String)>) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ModifyQos { name, attributes }],
+        )
+    }
+
+    pub fn new_show_qos() -> Self {
+        Self::new_inner(Default::default(), vec![SlurmSubCommand::ShowQos])
+    }
+
     #[cfg(test)]
     pub fn new_modify(username: String, modifier: HashMap<&'static str, Vec<String>>) -> Self {
         Self::new_inner(username, vec![SlurmSubCommand::Modify(modifier)])
     }
 
+    /// Adds a command to set the limits on the association of the user `username`
+    pub fn new_modify_limits(username: String, limits: Vec<SlurmLimit>) -> Self {
+        let command = SlurmSubCommand::Modify(Self::limits_to_modify_map(limits));
+        Self::new_inner(username, vec![command])
+    }
+
+    /// Adds commands to associate the user `username` with the accounts in `to_add`
+    /// and to change its default account afterwards if `default_account` is given.
+    pub fn new_add_associations(
+        username: String,
+        to_add: Vec<AccountMembership>,
+        default_account: Option<String>,
+    ) -> Self {
+        let commands = to_add
+            .into_iter()
+            .map(SlurmSubCommand::Add)
+            .chain(default_account.map(|account| {
+                SlurmSubCommand::Modify(HashMap::from_iter([(DEFAULT_ACCOUNT, vec![account])]))
+            }))
+            .collect();
+        Self::new_inner(username, commands)
+    }
+
+    /// Adds commands to remove the associations of the user `username` with the accounts in `to_remove`
+    pub fn new_remove_associations(username: String, to_remove: Vec<AccountMembership>) -> Self {
+        let commands = to_remove
+            .into_iter()
+            .map(SlurmSubCommand::RemoveAssociation)
+            .collect();
+        Self::new_inner(username, commands)
+    }
+
     /// Adds a command to modify the default quality of service for user aka parameter `username`
     pub fn new_modify_qos_default_qos(
         username: String,
@@ -100,11 +368,27 @@ impl CommandBuilder {
         Self::new_inner(username, vec![command])
     }
 
-    pub fn new_add(username: String, group: Group, default_qos: String, qos: Vec<String>) -> Self {
+    /// Adds the user `username` with one association per account in `accounts`.
+    /// Slurm takes the first account as default account of the user.
+    pub fn new_add(
+        username: String,
+        accounts: Vec<AccountMembership>,
+        default_qos: String,
+        qos: Vec<String>,
+        limits: Vec<SlurmLimit>,
+    ) -> Self {
         // Note: The order of execution is important here!
         // Slurm expects the user to have QOS, before it can set the default QOS
-        let mod_command = Self::create_modify_command(default_qos, qos);
-        Self::new_inner(username, vec![SlurmSubCommand::Add { group }, mod_command])
+        let mut mod_command = Self::create_modify_command(default_qos, qos);
+        if let SlurmSubCommand::Modify(map) = &mut mod_command {
+            map.extend(Self::limits_to_modify_map(limits));
+        }
+        let commands = accounts
+            .into_iter()
+            .map(SlurmSubCommand::Add)
+            .chain(iter::once(mod_command))
+            .collect();
+        Self::new_inner(username, commands)
     }
 
     pub fn immediate(mut self, immediate: bool) -> Self {
@@ -117,29 +401,19 @@ impl CommandBuilder {
         self
     }
 
-    pub fn remote_commands(self) -> Vec<String> {
+    /// Returns one line per command with arguments quoted for a shell.
+    pub fn shell_commands(self) -> Vec<String> {
         let args = Self::construct_args(self.username, self.immediate, self.sub_commands);
         args.into_iter()
             .map(|args| {
                 let mut command = Vec::with_capacity(args.len() + 1);
                 command.push(self.sacctmgr_path.to_owned());
-                command.extend(args);
+                command.extend(args.into_iter().map(quote_for_shell));
                 command.join(" ")
             })
             .collect()
     }
 
-    pub fn local_commands(self) -> Vec<Command> {
-        let args = Self::construct_args(self.username, self.immediate, self.sub_commands);
-        args.into_iter()
-            .map(|args| {
-                let mut command = Command::new(&self.sacctmgr_path);
-                command.args(args);
-                command
-            })
-            .collect()
-    }
-
     fn new_inner(username: String, sub_commands: Vec<SlurmSubCommand>) -> Self {
         Self {
             sub_commands,
@@ -154,6 +428,13 @@ impl CommandBuilder {
         SlurmSubCommand::Modify(map)
     }
 
+    fn limits_to_modify_map(limits: Vec<SlurmLimit>) -> HashMap<&'static str, Vec<String>> {
+        limits
+            .into_iter()
+            .map(|limit| (limit.key(), vec![limit.value().to_owned()]))
+            .collect()
+    }
+
     fn construct_args(
         username: String,
         immediate: bool,
@@ -178,29 +459,35 @@ impl CommandBuilder {
 #[cfg(test)]
 mod testing {
     use super::*;
+
+    fn membership(text: &str) -> AccountMembership {
+        text.parse().unwrap()
+    }
     #[test]
     fn produce_add_username_with_account_and_qos() {
         let input = CommandBuilder::new_add(
             "somebody".to_owned(),
-            Group::Staff,
+            vec![membership("staff")],
             "student".to_owned(),
             vec!["student".into(), "worker".into()],
+            Vec::new(),
         );
 
-        let actual = input.remote_commands();
+        let actual = input.shell_commands();
         insta::assert_yaml_snapshot!(actual);
     }
     #[test]
     fn produce_immediate_add_username_with_account_and_qos() {
         let input = CommandBuilder::new_add(
             "somebody".to_owned(),
-            Group::Staff,
+            vec![membership("staff")],
             "student".to_owned(),
             vec!["student".into(), "worker".into()],
+            Vec::new(),
         )
         .immediate(true);
 
-        let actual = input.remote_commands();
+        let actual = input.shell_commands();
         insta::assert_yaml_snapshot!(actual);
     }
 
@@ -208,29 +495,71 @@ mod testing {
     fn produce_delete_user_with_separate_path() {
         let input = CommandBuilder::new_delete("somebody".to_owned())
             .sacctmgr_path("some_path/sacctmgr".to_owned());
-        let actual = input.remote_commands();
+        let actual = input.shell_commands();
         insta::assert_yaml_snapshot!(actual);
     }
 
     #[test]
-    fn produce_delete_user_with_local_command() {
-        let input = CommandBuilder::new_delete("somebody".to_owned())
-            .sacctmgr_path("some_path/sacctmgr".to_owned());
-        let actual = input.local_commands();
+    fn list_associations() {
+        let all = CommandBuilder::new_show_associations(None).shell_commands();
+        let of_user =
+            CommandBuilder::new_show_associations(Some("somebody".to_owned())).shell_commands();
+        insta::assert_debug_snapshot!((all, of_user));
+    }
+
+    #[test]
+    fn manage_accounts() {
+        let fields = SlurmAccountFields {
+            parent: Some("root".try_into().unwrap()),
+            description: Some("Staff members of 'THN'".try_into().unwrap()),
+            organization: Some("thn".try_into().unwrap()),
+            fairshare: Some(10),
+        };
+        let actual: Vec<String> = [
+            CommandBuilder::new_add_account("staff".to_owned(), fields),
+            CommandBuilder::new_modify_account(
+                "staff".to_owned(),
+                SlurmAccountFields {
+                    fairshare: Some(20),
+                    ..Default::default()
+                },
+            ),
+            CommandBuilder::new_delete_account("staff".to_owned()),
+            CommandBuilder::new_show_accounts(),
+            CommandBuilder::new_add_coordinator("jane".to_owned(), "staff".to_owned()),
+            CommandBuilder::new_remove_coordinator("jane".to_owned(), "staff".to_owned()),
+            CommandBuilder::new_show_coordinators(),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.immediate(true).shell_commands())
+        .collect();
         insta::assert_debug_snapshot!(actual);
     }
 
     #[test]
-    fn list_user() {
-        let input = CommandBuilder::new_show(false).sacctmgr_path("some_path/sacctmgr".to_owned());
-        let actual = input.remote_commands();
+    fn keeps_description_with_spaces_as_one_argument() {
+        let fields = SlurmAccountFields {
+            description: Some("Staff members".try_into().unwrap()),
+            ..Default::default()
+        };
+        let actual = CommandBuilder::new_add_account("staff".to_owned(), fields).shell_commands();
         insta::assert_debug_snapshot!(actual);
     }
 
     #[test]
-    fn list_user_parserable() {
-        let input = CommandBuilder::new_show(true).sacctmgr_path("some_path/sacctmgr".to_owned());
-        let actual = input.remote_commands();
+    fn manage_qos() {
+        let attributes = vec![
+            ("Priority", "10".to_owned()),
+            ("MaxTRESPerUser", "cpu=8,gres/gpu=1".to_owned()),
+        ];
+        let actual: Vec<String> = [
+            CommandBuilder::new_add_qos("basic".to_owned(), attributes.clone()),
+            CommandBuilder::new_modify_qos("basic".to_owned(), attributes),
+            CommandBuilder::new_show_qos(),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.shell_commands())
+        .collect();
         insta::assert_debug_snapshot!(actual);
     }
 
@@ -241,7 +570,59 @@ mod testing {
             (DEFAULT_QOS, vec!["basic".to_string()]),
         ]);
         let input = CommandBuilder::new_modify("somebody".to_owned(), map);
-        let actual = input.remote_commands();
+        let actual = input.shell_commands();
+        insta::assert_debug_snapshot!(actual);
+    }
+
+    #[test]
+    fn add_and_modify_user_with_limits() {
+        let limits: Vec<SlurmLimit> = [
+            "MaxJobs=10",
+            "GrpTRES=cpu=8,gres/gpu=1",
+            "MaxWall=1-00:00:00",
+        ]
+        .into_iter()
+        .map(|limit| limit.parse().unwrap())
+        .collect();
+        let actual: Vec<String> = [
+            CommandBuilder::new_add(
+                "somebody".to_owned(),
+                vec![membership("student")],
+                "basic".to_owned(),
+                vec!["basic".into()],
+                limits.clone(),
+            ),
+            CommandBuilder::new_modify_limits("somebody".to_owned(), limits),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.shell_commands())
+        .collect();
+        insta::assert_debug_snapshot!(actual);
+    }
+
+    #[test]
+    fn add_and_remove_associations_with_partitions() {
+        let actual: Vec<String> = [
+            CommandBuilder::new_add(
+                "somebody".to_owned(),
+                vec![membership("proj1"), membership("proj2:gpu")],
+                "basic".to_owned(),
+                vec!["basic".into()],
+                Vec::new(),
+            ),
+            CommandBuilder::new_add_associations(
+                "somebody".to_owned(),
+                vec![membership("proj3")],
+                Some("proj3".to_owned()),
+            ),
+            CommandBuilder::new_remove_associations(
+                "somebody".to_owned(),
+                vec![membership("proj1"), membership("proj2:gpu")],
+            ),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.shell_commands())
+        .collect();
         insta::assert_debug_snapshot!(actual);
     }
 }
@@ -0,0 +1,303 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use log::warn;
+
+use crate::prelude::*;
+
+/// Directory on the head node in which private temporary directories for dumps are created
+/// while the dumps are transferred.
+pub const REMOTE_DUMP_DIRECTORY: &str = "/tmp";
+
+/// Account of a dump together with its parent account.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DumpAccount {
+    pub name: String,
+    pub parent: String,
+}
+
+impl Display for DumpAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "account {} (parent {})", self.name, self.parent)
+    }
+}
+
+/// Association of a user with an account, and optionally a partition, within a dump.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DumpAssociation {
+    pub user: String,
+    pub account: String,
+    pub partition: Option<String>,
+}
+
+impl Display for DumpAssociation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "user {} in account {}", self.user, self.account)?;
+        if let Some(partition) = &self.partition {
+            write!(f, " on partition {}", partition)?;
+        }
+        Ok(())
+    }
+}
+
+/// Accounts and associations of users as written by `sacctmgr dump`.
+/// Limits, fairshare and other settings of the dump are not considered.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SlurmDump {
+    pub cluster: String,
+    pub accounts: BTreeSet<DumpAccount>,
+    pub associations: BTreeSet<DumpAssociation>,
+}
+
+impl SlurmDump {
+    /// Parses the content of a file written by `sacctmgr dump`.
+    /// Users and accounts belong to the account of the last preceding line `Parent - '<account>'`.
+    ///
+    /// # Errors
+    ///
+    /// - If a line is not of the form `<Kind> - '<name>'[:<Key>=<Value>]*`.
+    /// - If the dump has no line for the cluster.
+    /// - If a user or account appears before any line for its parent.
+    pub fn parse(content: &str) -> AppResult<Self> {
+        let mut dump = Self::default();
+        let mut parent: Option<String> = None;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let DumpLine {
+                kind,
+                name,
+                attributes,
+            } = DumpLine::parse(line)
+                .with_context(|| format!("Malformed line {} in dump: '{}'", index + 1, line))?;
+            let no_parent = || anyhow!("{} in line {} has no parent", kind, index + 1);
+            match kind {
+                "Cluster" => dump.cluster = name,
+                "Parent" => parent = Some(name),
+                "Account" => {
+                    let parent = parent.clone().ok_or_else(no_parent)?;
+                    dump.accounts.insert(DumpAccount { name, parent });
+                }
+                "User" => {
+                    let account = parent.clone().ok_or_else(no_parent)?;
+                    let partition = attributes
+                        .into_iter()
+                        .find_map(|(key, value)| (key == "Partition").then_some(value));
+                    dump.associations.insert(DumpAssociation {
+                        user: name,
+                        account,
+                        partition,
+                    });
+                }
+                other => warn!(
+                    "Ignoring unknown entry {} in line {} of dump",
+                    other,
+                    index + 1
+                ),
+            }
+        }
+        if dump.cluster.is_empty() {
+            bail!("Dump has no line for the cluster");
+        }
+        Ok(dump)
+    }
+}
+
+/// Differences between the accounting tree in Slurm and a dump to be restored.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DumpDiff {
+    pub added_accounts: Vec<DumpAccount>,
+    pub removed_accounts: Vec<DumpAccount>,
+    pub added_associations: Vec<DumpAssociation>,
+    pub removed_associations: Vec<DumpAssociation>,
+}
+
+impl DumpDiff {
+    /// Returns what changes in `current` if `to_restore` replaces it.
+    pub fn new(current: &SlurmDump, to_restore: &SlurmDump) -> Self {
+        Self {
+            added_accounts: to_restore
+                .accounts
+                .difference(&current.accounts)
+                .cloned()
+                .collect(),
+            removed_accounts: current
+                .accounts
+                .difference(&to_restore.accounts)
+                .cloned()
+                .collect(),
+            added_associations: to_restore
+                .associations
+                .difference(&current.associations)
+                .cloned()
+                .collect(),
+            removed_associations: current
+                .associations
+                .difference(&to_restore.associations)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_accounts.is_empty()
+            && self.removed_accounts.is_empty()
+            && self.added_associations.is_empty()
+            && self.removed_associations.is_empty()
+    }
+}
+
+impl Display for DumpDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for account in &self.added_accounts {
+            writeln!(f, "+ {}", account)?;
+        }
+        for account in &self.removed_accounts {
+            writeln!(f, "- {}", account)?;
+        }
+        for association in &self.added_associations {
+            writeln!(f, "+ {}", association)?;
+        }
+        for association in &self.removed_associations {
+            writeln!(f, "- {}", association)?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments for sacctmgr to dump the accounting tree of `cluster` into the file at `file`.
+pub fn dump_args(cluster: &str, file: &str) -> Vec<String> {
+    vec![
+        "--immediate".to_owned(),
+        "dump".to_owned(),
+        cluster.to_owned(),
+        format!("file={}", file),
+    ]
+}
+
+/// Arguments for sacctmgr to load the dump at `file`.
+/// With `clean`, everything of the cluster which is not in the dump is removed.
+pub fn load_args(file: &str, clean: bool) -> Vec<String> {
+    let mut args = vec![
+        "--immediate".to_owned(),
+        "load".to_owned(),
+        format!("file={}", file),
+    ];
+    if clean {
+        args.push("clean".to_owned());
+    }
+    args
+}
+
+/// Line of a dump like `User - 'jane':DefaultAccount='staff':Fairshare=1` with values without quotes.
+struct DumpLine<'a> {
+    kind: &'a str,
+    name: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl<'a> DumpLine<'a> {
+    fn parse(line: &'a str) -> AppResult<Self> {
+        let (kind, rest) = line
+            .split_once(" - ")
+            .ok_or_else(|| anyhow!("Expected ' - ' after the kind of entry"))?;
+        let mut fields = split_outside_quotes(rest).into_iter();
+        let name = fields
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow!("Expected a name after the kind of entry"))?;
+        let attributes = fields
+            .map(|field| match field.split_once('=') {
+                Some((key, value)) => Ok((key.to_owned(), unquote(value))),
+                None => Err(anyhow!("Attribute '{}' has no value", field)),
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        Ok(Self {
+            kind: kind.trim(),
+            name: unquote(&name),
+            attributes,
+        })
+    }
+}
+
+/// Splits at the character ':' unless it is within single quotes.
+fn split_outside_quotes(text: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for character in text.chars() {
+        match character {
+            '\'' => {
+                quoted = !quoted;
+                current.push(character);
+            }
+            ':' if !quoted => fields.push(std::mem::take(&mut current)),
+            _ => current.push(character),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    text.strip_prefix('\'')
+        .and_then(|inner| inner.strip_suffix('\''))
+        .unwrap_or(text)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const CURRENT: &str = "\
+# To edit this file start with a cluster line for the new cluster
+Cluster - 'hpc':Fairshare=1:QOS='normal'
+Parent - 'root'
+User - 'root':DefaultAccount='root':AdminLevel='Administrator':Fairshare=1
+Account - 'staff':Description='Staff: members':Organization='thn':Fairshare=1
+Account - 'student':Description='student':Organization='thn':Fairshare=1
+Parent - 'staff'
+User - 'jane':DefaultAccount='staff':Fairshare=1:QOS='normal'
+User - 'jane':Partition='gpu':Fairshare=1
+Parent - 'student'
+User - 'tom':DefaultAccount='student':Fairshare=1
+";
+
+    #[test]
+    fn parses_dump_of_sacctmgr() {
+        let actual = SlurmDump::parse(CURRENT).unwrap();
+        insta::assert_debug_snapshot!(actual);
+    }
+
+    #[test]
+    fn shows_added_and_removed_accounts_and_associations() {
+        let to_restore = "\
+Cluster - 'hpc'
+Parent - 'root'
+User - 'root':DefaultAccount='root'
+Account - 'staff':Description='staff'
+Account - 'guest':Description='guest'
+Parent - 'staff'
+User - 'jane':DefaultAccount='staff'
+Parent - 'guest'
+User - 'tom':DefaultAccount='guest'
+";
+        let current = SlurmDump::parse(CURRENT).unwrap();
+        let to_restore = SlurmDump::parse(to_restore).unwrap();
+
+        let actual = DumpDiff::new(&current, &to_restore);
+        insta::assert_snapshot!(actual.to_string());
+        assert!(DumpDiff::new(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn errors_for_malformed_dumps() {
+        assert!(SlurmDump::parse("Parent - 'root'\n").is_err());
+        assert!(SlurmDump::parse("Cluster - 'hpc'\nUser - 'jane'\n").is_err());
+        assert!(SlurmDump::parse("Cluster 'hpc'\n").is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Fake Slurm tools as shell scripts for tests which run Slurm commands on the local machine.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::config::MgmtConfig;
+
+/// Temporary directory with shell scripts named like Slurm tools.
+/// The directory is removed once this value is dropped.
+pub struct FakeSlurmTools {
+    directory: PathBuf,
+    config: MgmtConfig,
+}
+
+impl FakeSlurmTools {
+    /// Writes each pair of `tools` as an executable script with the name of the tool
+    /// and the shell code as its body.
+    /// The configuration runs Slurm on the local machine with the tools of the directory.
+    /// The parameter `name` keeps the directories of concurrent tests apart.
+    pub fn new(name: &str, tools: &[(&str, &str)]) -> Self {
+        let directory = std::env::temp_dir().join(format!("usermgmt_{}_{}", name, process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for (tool, script) in tools {
+            let path = directory.join(tool);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let config = MgmtConfig {
+            sacctmgr_path: directory.join("sacctmgr").to_string_lossy().into_owned(),
+            run_slurm_remote: false,
+            ..Default::default()
+        };
+        Self { directory, config }
+    }
+
+    /// Changes the configuration which runs the fake tools.
+    pub fn with_config(mut self, change: impl FnOnce(&mut MgmtConfig)) -> Self {
+        change(&mut self.config);
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn config(&self) -> &MgmtConfig {
+        &self.config
+    }
+}
+
+impl Drop for FakeSlurmTools {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
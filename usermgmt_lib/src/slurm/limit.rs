@@ -0,0 +1,179 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::prelude::*;
+
+use super::qos::wall_time_secs;
+
+/// Value which removes a limit from an association in Slurm
+const UNSET_LIMIT: &str = "-1";
+
+/// Kind of value a limit accepts which determines how the value is validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LimitKind {
+    /// Non-negative integer like for `MaxJobs=10`
+    Count,
+    /// Comma separated list of resources with amounts like `cpu=8,gres/gpu=1`
+    Tres,
+    /// Time limit in one of the formats of Slurm like `1-00:00:00`
+    WallTime,
+    /// Non-negative integer or "parent" to use the fairshare of the parent account
+    Fairshare,
+}
+
+/// Limits which can be set on the association of a user.
+/// The names are the keys which sacctmgr accepts for `modify user ... set`.
+const KNOWN_LIMITS: [(&str, LimitKind); 9] = [
+    ("MaxJobs", LimitKind::Count),
+    ("MaxSubmitJobs", LimitKind::Count),
+    ("MaxTRES", LimitKind::Tres),
+    ("MaxWall", LimitKind::WallTime),
+    ("GrpJobs", LimitKind::Count),
+    ("GrpSubmitJobs", LimitKind::Count),
+    ("GrpTRES", LimitKind::Tres),
+    ("GrpWall", LimitKind::WallTime),
+    ("Fairshare", LimitKind::Fairshare),
+];
+
+/// Resource limit on the association of a user in Slurm given as `key=value`, e.g. `MaxJobs=10`
+/// or `GrpTRES=cpu=8,gres/gpu=1`.
+/// The key is one of the known limits in any casing. The value `-1` removes the limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlurmLimit {
+    key: &'static str,
+    value: String,
+}
+
+impl SlurmLimit {
+    /// # Errors
+    ///
+    /// - If `key` is not one of the known limits.
+    /// - If `value` is not valid for the limit named `key`.
+    pub fn new(key: &str, value: &str) -> AppResult<Self> {
+        let (value, key) = (value.trim(), key.trim());
+        let Some((key, kind)) = KNOWN_LIMITS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(key))
+        else {
+            bail!(
+                "Unknown Slurm limit '{}'. Known limits are {}",
+                key,
+                Self::known_keys().join(", ")
+            );
+        };
+        if value != UNSET_LIMIT && !Self::is_valid_value(*kind, value) {
+            bail!("Invalid value '{}' for the Slurm limit {}", value, key);
+        }
+        Ok(Self {
+            key,
+            value: value.to_owned(),
+        })
+    }
+
+    /// Names of all limits which can be set
+    pub fn known_keys() -> Vec<&'static str> {
+        KNOWN_LIMITS.iter().map(|(key, _)| *key).collect()
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns true if the limit is to be removed from the association.
+    pub fn is_unset(&self) -> bool {
+        self.value == UNSET_LIMIT
+    }
+
+    fn is_valid_value(kind: LimitKind, value: &str) -> bool {
+        let is_count = |value: &str| value.parse::<u64>().is_ok();
+        match kind {
+            LimitKind::Count => is_count(value),
+            LimitKind::Fairshare => is_count(value) || value.eq_ignore_ascii_case("parent"),
+            LimitKind::WallTime => wall_time_secs(value).is_some(),
+            LimitKind::Tres => value.split(',').all(|resource| {
+                resource
+                    .split_once('=')
+                    .is_some_and(|(name, amount)| !name.is_empty() && !amount.is_empty())
+            }),
+        }
+    }
+}
+
+impl FromStr for SlurmLimit {
+    type Err = AppError;
+
+    /// Parses a limit given as `key=value`.
+    /// Only the first '=' separates key and value so TRES like `GrpTRES=cpu=8` keep theirs.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let Some((key, value)) = text.split_once('=') else {
+            bail!(
+                "Slurm limit '{}' is not given in the form key=value like MaxJobs=10",
+                text
+            );
+        };
+        Self::new(key, value)
+    }
+}
+
+impl Display for SlurmLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_known_limits_in_any_casing() {
+        let actual: Vec<String> = [
+            "maxjobs=10",
+            "MaxSubmitJobs=20",
+            "GrpTRES=cpu=8,gres/gpu=1",
+            "MaxWall=1-00:00:00",
+            "grpwall=90",
+            "Fairshare=parent",
+            "MaxJobs=-1",
+        ]
+        .into_iter()
+        .map(|text| SlurmLimit::from_str(text).unwrap().to_string())
+        .collect();
+
+        assert_eq!(
+            vec![
+                "MaxJobs=10",
+                "MaxSubmitJobs=20",
+                "GrpTRES=cpu=8,gres/gpu=1",
+                "MaxWall=1-00:00:00",
+                "GrpWall=90",
+                "Fairshare=parent",
+                "MaxJobs=-1"
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_limits_and_invalid_values() {
+        for invalid in [
+            "MaxJobs",
+            "MaxCookies=10",
+            "MaxJobs=ten",
+            "GrpTRES=cpu",
+            "GrpTRES=cpu=8,",
+            "MaxWall=tomorrow",
+            "Fairshare=-5",
+        ] {
+            assert!(
+                SlurmLimit::from_str(invalid).is_err(),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use crate::{
+    config::{QosAttributeKind, QosDefinition},
+    prelude::*,
+};
+
+/// Character which splits cells in a row of the output of sacctmgr with the option --parsable2
+const SPLIT_BETWEEN_CELLS: char = '|';
+
+/// Attributes of a QOS as listed by sacctmgr, keyed by their name in [`QosDefinition::ATTRIBUTES`]
+pub type QosAttributes = BTreeMap<String, String>;
+
+/// Fields which sacctmgr is asked for when listing QOS.
+/// The output of sacctmgr contains the name first and then the attributes of [`QosDefinition::ATTRIBUTES`].
+pub fn qos_fields() -> Vec<&'static str> {
+    std::iter::once("Name")
+        .chain(QosDefinition::ATTRIBUTES.iter().map(|(name, _)| *name))
+        .collect()
+}
+
+/// Parses the output of sacctmgr with the options `--parsable2 --noheader` and the fields [`qos_fields`].
+///
+/// # Errors
+///
+/// - If the number of fields in a line is not the number of [`qos_fields`].
+pub fn parse_qos(output: &str) -> AppResult<BTreeMap<String, QosAttributes>> {
+    let expected = QosDefinition::ATTRIBUTES.len() + 1;
+    let mut all_qos = BTreeMap::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let Some((name, values)) = cells.split_first().filter(|_| cells.len() == expected) else {
+            bail!(
+                "Malformed QOS in line {} of sacctmgr output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                expected,
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        let attributes = QosDefinition::ATTRIBUTES
+            .iter()
+            .zip(values)
+            .map(|((attribute, _), value)| (attribute.to_string(), value.to_string()))
+            .collect();
+        all_qos.insert(name.to_string(), attributes);
+    }
+    Ok(all_qos)
+}
+
+/// Difference of one attribute between Slurm and the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QosDifference {
+    pub attribute: &'static str,
+    pub current: String,
+    pub desired: String,
+}
+
+/// Change which is needed to bring a QOS in Slurm in line with its definition in the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QosChange {
+    Create {
+        name: String,
+        attributes: Vec<(&'static str, String)>,
+    },
+    Modify {
+        name: String,
+        differences: Vec<QosDifference>,
+    },
+}
+
+impl QosChange {
+    /// Attributes with their values to be set in Slurm
+    pub fn attributes_to_set(&self) -> Vec<(&'static str, String)> {
+        match self {
+            QosChange::Create { attributes, .. } => attributes.clone(),
+            QosChange::Modify { differences, .. } => differences
+                .iter()
+                .map(|difference| (difference.attribute, difference.desired.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            QosChange::Create { name, .. } | QosChange::Modify { name, .. } => name,
+        }
+    }
+}
+
+impl Display for QosChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QosChange::Create { name, attributes } => {
+                writeln!(f, "+ qos {}", name)?;
+                for (attribute, value) in attributes {
+                    writeln!(f, "+   {}={}", attribute, value)?;
+                }
+            }
+            QosChange::Modify { name, differences } => {
+                writeln!(f, "~ qos {}", name)?;
+                for QosDifference {
+                    attribute,
+                    current,
+                    desired,
+                } in differences
+                {
+                    writeln!(f, "~   {}: '{}' -> '{}'", attribute, current, desired)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the changes needed so that every QOS in `defined` exists in Slurm with the defined attributes.
+/// QOS in Slurm which are not defined and attributes which are not defined are left out.
+pub fn diff_qos(
+    defined: &BTreeMap<String, QosDefinition>,
+    in_slurm: &BTreeMap<String, QosAttributes>,
+) -> Vec<QosChange> {
+    defined
+        .iter()
+        .filter_map(|(name, definition)| {
+            let attributes = definition.attributes();
+            match in_slurm.get(name) {
+                None => Some(QosChange::Create {
+                    name: name.clone(),
+                    attributes,
+                }),
+                Some(current) => {
+                    let differences: Vec<QosDifference> = attributes
+                        .into_iter()
+                        .filter_map(|(attribute, desired)| {
+                            let current = current.get(attribute).cloned().unwrap_or_default();
+                            (!is_same_value(attribute, &current, &desired)).then_some(
+                                QosDifference {
+                                    attribute,
+                                    current,
+                                    desired,
+                                },
+                            )
+                        })
+                        .collect();
+                    (!differences.is_empty()).then(|| QosChange::Modify {
+                        name: name.clone(),
+                        differences,
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+fn is_same_value(attribute: &str, current: &str, desired: &str) -> bool {
+    let kind = QosDefinition::ATTRIBUTES
+        .iter()
+        .find(|(name, _)| *name == attribute)
+        .map(|(_, kind)| *kind)
+        .unwrap_or(QosAttributeKind::Text);
+    match kind {
+        QosAttributeKind::Text => current == desired,
+        QosAttributeKind::List => {
+            let as_set = |list: &str| -> BTreeSet<String> {
+                list.split(',')
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.trim().to_lowercase())
+                    .collect()
+            };
+            as_set(current) == as_set(desired)
+        }
+        QosAttributeKind::WallTime => match (wall_time_secs(current), wall_time_secs(desired)) {
+            (Some(current), Some(desired)) => current == desired,
+            _ => current == desired,
+        },
+    }
+}
+
+/// Converts a time limit in one of the formats of Slurm into seconds.
+/// Formats are "minutes", "minutes:seconds", "hours:minutes:seconds", "days-hours",
+/// "days-hours:minutes" and "days-hours:minutes:seconds".
+/// Returns `None` if the time limit is not in one of the formats.
+pub(super) fn wall_time_secs(time: &str) -> Option<u64> {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    let parse_numbers = |text: &str| -> Option<Vec<u64>> {
+        text.split(':')
+            .map(|number| number.parse::<u64>().ok())
+            .collect()
+    };
+    match time.split_once('-') {
+        Some((days, rest)) => {
+            let days: u64 = days.parse().ok()?;
+            let secs = match parse_numbers(rest)?.as_slice() {
+                [hours] => hours * HOUR,
+                [hours, minutes] => hours * HOUR + minutes * MINUTE,
+                [hours, minutes, seconds] => hours * HOUR + minutes * MINUTE + seconds,
+                _ => return None,
+            };
+            Some(days * DAY + secs)
+        }
+        None => match parse_numbers(time)?.as_slice() {
+            [minutes] => Some(minutes * MINUTE),
+            [minutes, seconds] => Some(minutes * MINUTE + seconds),
+            [hours, minutes, seconds] => Some(hours * HOUR + minutes * MINUTE + seconds),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn defined_qos() -> BTreeMap<String, QosDefinition> {
+        let basic = QosDefinition {
+            priority: Some(10),
+            max_wall: Some("24:00:00".to_owned()),
+            max_tres_per_user: Some("gres/gpu=1,cpu=8".to_owned()),
+            flags: Some(vec!["DenyOnLimit".to_owned()]),
+            ..Default::default()
+        };
+        let advanced = QosDefinition {
+            description: Some("Staff with more resources".to_owned()),
+            priority: Some(20),
+            max_wall: Some("2-00:00:00".to_owned()),
+            preempt: Some(vec!["basic".to_owned()]),
+            preempt_mode: Some("requeue".to_owned()),
+            ..Default::default()
+        };
+        BTreeMap::from_iter([
+            ("basic".to_owned(), basic),
+            ("advanced".to_owned(), advanced),
+        ])
+    }
+
+    #[test]
+    fn parses_qos() {
+        let input = "normal||0|||||||||\n\
+                     basic|basic|10|1-00:00:00|cpu=8,gres/gpu=1||||||cluster|DenyOnLimit\n";
+        let actual = parse_qos(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+
+        assert!(parse_qos("basic|basic|10\n").is_err());
+    }
+
+    #[test]
+    fn creates_missing_and_modifies_differing_qos() {
+        let in_slurm = parse_qos(
+            "normal||0|||||||||\n\
+             basic|basic|10|1-00:00:00|cpu=8,gres/gpu=1||||||cluster|DenyOnLimit\n",
+        )
+        .unwrap();
+        let mut defined = defined_qos();
+
+        let actual = diff_qos(&defined, &in_slurm);
+        insta::assert_snapshot!(actual.iter().map(ToString::to_string).collect::<String>());
+
+        defined.get_mut("basic").unwrap().priority = Some(15);
+        let actual = diff_qos(&defined, &in_slurm);
+        assert_eq!(
+            Some(&QosChange::Modify {
+                name: "basic".to_owned(),
+                differences: vec![QosDifference {
+                    attribute: "Priority",
+                    current: "10".to_owned(),
+                    desired: "15".to_owned()
+                }]
+            }),
+            actual.iter().find(|change| change.name() == "basic")
+        );
+    }
+
+    #[test]
+    fn compares_wall_time_in_all_formats() {
+        assert_eq!(Some(90 * 60), wall_time_secs("90"));
+        assert_eq!(Some(90 * 60 + 30), wall_time_secs("90:30"));
+        assert_eq!(wall_time_secs("1-00:00:00"), wall_time_secs("24:00:00"));
+        assert_eq!(wall_time_secs("1-12"), wall_time_secs("36:00:00"));
+        assert_eq!(wall_time_secs("2-01:30"), wall_time_secs("2-01:30:00"));
+        assert_eq!(None, wall_time_secs("UNLIMITED"));
+    }
+}
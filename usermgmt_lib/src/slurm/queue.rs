@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+use crate::prelude::*;
+
+/// Character which splits cells in a row of the output of squeue with the format [`SQUEUE_FORMAT`]
+const SPLIT_BETWEEN_CELLS: char = '|';
+/// Format of squeue for job id, user, state, partition, elapsed time and name.
+/// The name comes last because it may contain the character '|'.
+pub const SQUEUE_FORMAT: &str = "%i|%u|%T|%P|%M|%j";
+/// States of jobs which still occupy or wait for resources
+const ACTIVE_STATES: &str = "PENDING,RUNNING,SUSPENDED,COMPLETING,CONFIGURING";
+
+/// Job which is running or waits to be run as listed by squeue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedJob {
+    pub job_id: String,
+    pub user: String,
+    pub state: String,
+    pub partition: String,
+    pub time: String,
+    pub name: String,
+}
+
+impl Display for QueuedJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}) on {} since {}",
+            self.job_id, self.name, self.state, self.partition, self.time
+        )
+    }
+}
+
+/// Arguments for squeue to list the active jobs of `username` in the format [`SQUEUE_FORMAT`].
+pub fn squeue_args(username: &str) -> Vec<String> {
+    vec![
+        "--noheader".to_owned(),
+        format!("--user={}", username),
+        format!("--states={}", ACTIVE_STATES),
+        format!("--format={}", SQUEUE_FORMAT),
+    ]
+}
+
+/// Arguments for scancel to cancel all jobs of `username`.
+pub fn scancel_args(username: &str) -> Vec<String> {
+    vec![format!("--user={}", username)]
+}
+
+/// Parses the output of squeue with the arguments of [`squeue_args`].
+///
+/// # Errors
+///
+/// - If a line has less fields than [`SQUEUE_FORMAT`].
+pub fn parse_queued_jobs(output: &str) -> AppResult<Vec<QueuedJob>> {
+    let expected = SQUEUE_FORMAT.split(SPLIT_BETWEEN_CELLS).count();
+    let mut jobs = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.splitn(expected, SPLIT_BETWEEN_CELLS).collect();
+        let [job_id, user, state, partition, time, name] = cells.as_slice() else {
+            bail!(
+                "Malformed job in line {} of squeue output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                expected,
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        jobs.push(QueuedJob {
+            job_id: job_id.trim().to_string(),
+            user: user.to_string(),
+            state: state.to_string(),
+            partition: partition.to_string(),
+            time: time.to_string(),
+            name: name.to_string(),
+        });
+    }
+    Ok(jobs)
+}
+
+/// Returns a pretty ASCII table with one job per row.
+pub fn jobs_to_pretty_table(jobs: &[QueuedJob]) -> String {
+    use prettytable::{Row, Table};
+
+    let mut table = Table::new();
+    table.set_titles(Row::from([
+        "Job",
+        "User",
+        "State",
+        "Partition",
+        "Time",
+        "Name",
+    ]));
+    for job in jobs {
+        table.add_row(Row::from([
+            &job.job_id,
+            &job.user,
+            &job.state,
+            &job.partition,
+            &job.time,
+            &job.name,
+        ]));
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_queued_jobs_with_separator_in_name() {
+        let input = "  101|jane|RUNNING|gpu|1:02:03|train|resnet\n\
+                     102|jane|PENDING|cpu|0:00|prepare\n";
+
+        let actual = parse_queued_jobs(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+
+        assert!(parse_queued_jobs("103|jane|RUNNING\n").is_err());
+    }
+}
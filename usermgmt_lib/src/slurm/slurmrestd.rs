@@ -0,0 +1,734 @@
+//! Client for the accounting endpoints (slurmdb) of slurmrestd.
+//!
+//! It is an alternative to running sacctmgr locally or over SSH.
+//! Authentication is done via a JWT which is sent with every request.
+//! slurmrestd answers with JSON which contains a list of errors if a request failed.
+
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{AccountCoordinators, AccountMembership, SlurmAssociation, SlurmLimit};
+use crate::{config::MgmtConfig, prelude::*, ChangesToUser, NewEntity};
+
+/// Version of the slurmrestd API used if none is given in the configuration
+pub const DEFAULT_API_VERSION: &str = "v0.0.40";
+/// Environment variable from which the JWT is taken if none is given in the configuration.
+/// This is the same variable which `scontrol token` suggests to export.
+pub const JWT_ENV_VARIABLE: &str = "SLURM_JWT";
+
+const HEADER_USER_NAME: &str = "X-SLURM-USER-NAME";
+const HEADER_USER_TOKEN: &str = "X-SLURM-USER-TOKEN";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection details for the accounting endpoints of slurmrestd
+#[derive(Debug, Clone)]
+pub struct SlurmRestd {
+    agent: ureq::Agent,
+    base_url: String,
+    user_name: Option<String>,
+    token: String,
+    cluster: Option<String>,
+}
+
+impl SlurmRestd {
+    /// - `url`: Protocol, host and port of slurmrestd like `http://head.node.de:6820`.
+    /// - `api_version`: Version of the API like `v0.0.40`.
+    /// - `user_name`: Sent as the user on whose behalf the requests are made if given.
+    /// - `token`: JWT to authenticate every request.
+    /// - `cluster`: Cluster of created associations. slurmrestd picks its own cluster if `None`.
+    pub fn new(
+        url: &str,
+        api_version: &str,
+        user_name: Option<String>,
+        token: String,
+        cluster: Option<String>,
+    ) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        let base_url = format!("{}/slurmdb/{}", url.trim_end_matches('/'), api_version);
+        Self {
+            agent,
+            base_url,
+            user_name,
+            token,
+            cluster,
+        }
+    }
+
+    /// Takes the connection details from the fields starting with `slurmrestd_` in the configuration.
+    ///
+    /// # Errors
+    ///
+    /// - If no url for slurmrestd is configured.
+    /// - If no JWT is configured and the environment variable [`JWT_ENV_VARIABLE`] is not set either.
+    pub fn from_config(config: &MgmtConfig) -> AppResult<Self> {
+        let url = config
+            .slurmrestd_url
+            .as_deref()
+            .context("Field slurmrestd_url must be set in the configuration to use slurmrestd")?;
+        let token = match &config.slurmrestd_jwt {
+            Some(token) => token.clone(),
+            None => std::env::var(JWT_ENV_VARIABLE).with_context(|| {
+                format!(
+                    "No JWT for slurmrestd found. Set the field slurmrestd_jwt in the configuration \
+                     or the environment variable {}",
+                    JWT_ENV_VARIABLE
+                )
+            })?,
+        };
+        let api_version = config
+            .slurmrestd_api_version
+            .as_deref()
+            .unwrap_or(DEFAULT_API_VERSION);
+
+        Ok(Self::new(
+            url,
+            api_version,
+            config.slurmrestd_user.clone(),
+            token,
+            config.slurmrestd_cluster.clone(),
+        ))
+    }
+
+    /// Creates the user with an association per account and the given QOS.
+    ///
+    /// # Errors
+    ///
+    /// - If limits are to be set. See [`ensure_no_limits`]
+    /// - See [`SlurmRestd::send`]
+    pub fn add_user(&self, entity: &NewEntity) -> AppResult {
+        ensure_no_limits(&entity.limits)?;
+        let username: &str = entity.username.as_ref();
+        self.set_default_account(username, entity.default_account.as_ref())
+            .with_context(|| format!("Failed to add user {} to Slurm", username))?;
+
+        let qos: Vec<String> = (&entity.qos).into_iter().map(ToString::to_string).collect();
+        let associations: Vec<Association> = entity
+            .accounts
+            .iter()
+            .map(|membership| {
+                self.new_association(username, membership, &qos, &entity.default_qos.to_string())
+            })
+            .collect();
+        self.post_associations(&associations).with_context(|| {
+            format!("Failed to add associations for user {} to Slurm", username)
+        })?;
+
+        info!(
+            "Added user {} with default account {}, QoS {:?} and default QoS {} to Slurm via slurmrestd",
+            entity.username, entity.default_account, entity.qos, entity.default_qos
+        );
+        Ok(())
+    }
+
+    /// Deletes the user together with all its associations.
+    ///
+    /// # Errors
+    ///
+    /// - See [`SlurmRestd::send`]
+    pub fn delete_user(&self, username: &str) -> AppResult {
+        self.send("DELETE", &format!("user/{}", username), &[], None)
+            .with_context(|| format!("Failed to delete user {} from Slurm", username))?;
+        info!("Deleted user {} from Slurm via slurmrestd", username);
+        Ok(())
+    }
+
+    /// Adds or removes associations with accounts, changes the default account
+    /// and sets QOS and default QOS on all associations of the user.
+    /// New associations get the QOS of the existing associations.
+    ///
+    /// # Errors
+    ///
+    /// - If limits are to be set. See [`ensure_no_limits`]
+    /// - If the user has no associations.
+    /// - See [`SlurmRestd::send`]
+    pub fn modify_user(&self, modifiable: &ChangesToUser) -> AppResult {
+        ensure_no_limits(&modifiable.limits)?;
+        let username: &str = modifiable.username.as_ref();
+        if !modifiable.accounts.is_empty() {
+            let existing = self.associations(Some(username))?;
+            let Some(template) = existing.first() else {
+                bail!("No association found for user {} in Slurm", username);
+            };
+            let to_add: Vec<Association> = modifiable
+                .accounts
+                .iter()
+                .map(|membership| {
+                    self.new_association(username, membership, &template.qos, &template.default.qos)
+                })
+                .collect();
+            self.post_associations(&to_add).with_context(|| {
+                format!("Failed to add associations of user {} in Slurm", username)
+            })?;
+            info!(
+                "Added associations of user {} in Slurm via slurmrestd",
+                username
+            );
+        }
+        if let Some(default_account) = &modifiable.default_account {
+            self.set_default_account(username, default_account.as_ref())
+                .with_context(|| {
+                    format!(
+                        "Failed to change default account of user {} in Slurm",
+                        username
+                    )
+                })?;
+        }
+        if let Some((qos, default_qos)) = modifiable.may_qos_and_default_qos() {
+            let mut associations = self.associations(Some(username))?;
+            if associations.is_empty() {
+                bail!("No association found for user {} in Slurm", username);
+            }
+            for association in associations.iter_mut() {
+                association.qos = qos.clone();
+                association.default.qos = default_qos.clone();
+            }
+            self.post_associations(&associations)
+                .with_context(|| format!("Failed to modify QOS of user {} in Slurm", username))?;
+            info!("Modified QOS of user {} in Slurm via slurmrestd", username);
+        }
+        for membership in modifiable.remove_accounts.iter() {
+            let mut query = vec![("user", username), ("account", membership.account.as_ref())];
+            if let Some(partition) = &membership.partition {
+                query.push(("partition", partition.as_ref()));
+            }
+            if let Some(cluster) = &self.cluster {
+                query.push(("cluster", cluster));
+            }
+            self.send("DELETE", "association", &query, None)
+                .with_context(|| {
+                    format!(
+                        "Failed to remove association of user {} with account {} in Slurm",
+                        username, membership
+                    )
+                })?;
+            info!(
+                "Removed association of user {} with account {} in Slurm via slurmrestd",
+                username, membership
+            );
+        }
+        Ok(())
+    }
+
+    /// Creates the user if it does not exist yet and sets its default account.
+    fn set_default_account(&self, username: &str, account: &str) -> AppResult {
+        let user = json!({
+            "users": [{ "name": username, "default": { "account": account } }]
+        });
+        self.send("POST", "users", &[], Some(user))?;
+        Ok(())
+    }
+
+    fn new_association(
+        &self,
+        username: &str,
+        membership: &AccountMembership,
+        qos: &[String],
+        default_qos: &str,
+    ) -> Association {
+        Association {
+            account: membership.account.to_string(),
+            user: username.to_owned(),
+            cluster: self.cluster.clone(),
+            partition: membership.partition.as_ref().map(ToString::to_string),
+            default: AssociationDefault {
+                qos: default_qos.to_owned(),
+            },
+            qos: qos.to_vec(),
+        }
+    }
+
+    /// Returns the associations of all users and accounts on the cluster of this client.
+    /// Limits of the associations are not retrieved via slurmrestd.
+    ///
+    /// # Errors
+    ///
+    /// - See [`SlurmRestd::send`]
+    pub fn list_associations(&self) -> AppResult<Vec<SlurmAssociation>> {
+        let associations = self
+            .associations(None)?
+            .into_iter()
+            .map(|association| SlurmAssociation {
+                user: association.user,
+                account: association.account,
+                default_qos: association.default.qos,
+                qos: association.qos,
+                partition: association.partition,
+                limits: Default::default(),
+            })
+            .collect();
+        Ok(associations)
+    }
+
+    /// Makes the user a coordinator of the account.
+    ///
+    /// # Errors
+    ///
+    /// - See [`SlurmRestd::send`]
+    pub fn add_coordinator(&self, account: &str, username: &str) -> AppResult {
+        let user = json!({
+            "users": [{ "name": username, "coordinators": [{ "name": account }] }]
+        });
+        self.send("POST", "users", &[], Some(user))
+            .with_context(|| {
+                format!(
+                    "Failed to add {} as coordinator of account {} to Slurm",
+                    username, account
+                )
+            })?;
+        info!(
+            "Added {} as coordinator of account {} to Slurm via slurmrestd",
+            username, account
+        );
+        Ok(())
+    }
+
+    /// Returns all accounts which have coordinators.
+    ///
+    /// # Errors
+    ///
+    /// - See [`SlurmRestd::send`]
+    pub fn list_coordinators(&self) -> AppResult<Vec<AccountCoordinators>> {
+        let response = self.send("GET", "accounts", &[], None)?;
+        let coordinators = response
+            .accounts
+            .into_iter()
+            .filter(|account| !account.coordinators.is_empty())
+            .map(|account| AccountCoordinators {
+                account: account.name,
+                coordinators: account
+                    .coordinators
+                    .into_iter()
+                    .map(|coordinator| coordinator.name)
+                    .collect(),
+            })
+            .collect();
+        Ok(coordinators)
+    }
+
+    /// Returns the associations of `user` or of all users if `user` is `None`.
+    /// Only associations of the cluster of this client are returned if it has one.
+    fn associations(&self, user: Option<&str>) -> AppResult<Vec<Association>> {
+        let query: Vec<(&str, &str)> = user
+            .map(|user| ("user", user))
+            .into_iter()
+            .chain(self.cluster.as_deref().map(|cluster| ("cluster", cluster)))
+            .collect();
+        let response = self.send("GET", "associations", &query, None)?;
+        Ok(response.associations)
+    }
+
+    fn post_associations(&self, associations: &[Association]) -> AppResult {
+        self.send(
+            "POST",
+            "associations",
+            &[],
+            Some(json!({ "associations": associations })),
+        )?;
+        Ok(())
+    }
+
+    /// Sends a request to the endpoint `path` below `/slurmdb/<version>/`
+    /// with the pairs of `query` as query parameters.
+    ///
+    /// # Errors
+    ///
+    /// - If slurmrestd could not be reached.
+    /// - If slurmrestd answers with an error status code.
+    /// - If the answer contains errors or is not valid JSON.
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<serde_json::Value>,
+    ) -> AppResult<SlurmdbResponse> {
+        let url = format!("{}/{}", self.base_url, path);
+        debug!("Sending {} request to slurmrestd: {}", method, url);
+
+        let mut request = self
+            .agent
+            .request(method, &url)
+            .set(HEADER_USER_TOKEN, &self.token)
+            .query_pairs(query.iter().copied());
+        if let Some(user_name) = &self.user_name {
+            request = request.set(HEADER_USER_NAME, user_name);
+        }
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                let errors = response
+                    .into_json::<SlurmdbResponse>()
+                    .map(|answer| answer.error_messages())
+                    .unwrap_or_default();
+                bail!(
+                    "slurmrestd answered {} {} with status {}. {}",
+                    method,
+                    url,
+                    status,
+                    errors
+                );
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("Could not reach slurmrestd at {}", url))
+            }
+        };
+
+        let answer: SlurmdbResponse = response
+            .into_json()
+            .with_context(|| format!("Invalid answer from slurmrestd for {} {}", method, url))?;
+        for warning in answer.warnings.iter() {
+            warn!("slurmrestd: {}", warning.description);
+        }
+        if !answer.errors.is_empty() {
+            bail!(
+                "slurmrestd reported errors for {} {}. {}",
+                method,
+                url,
+                answer.error_messages()
+            );
+        }
+        Ok(answer)
+    }
+}
+
+/// Answer of slurmrestd to any request to the slurmdb endpoints.
+/// Only the parts used by this client are deserialized.
+#[derive(Debug, Default, Deserialize)]
+struct SlurmdbResponse {
+    #[serde(default)]
+    errors: Vec<SlurmdbError>,
+    #[serde(default)]
+    warnings: Vec<SlurmdbWarning>,
+    #[serde(default)]
+    associations: Vec<Association>,
+    #[serde(default)]
+    accounts: Vec<Account>,
+}
+
+impl SlurmdbResponse {
+    fn error_messages(&self) -> String {
+        self.errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SlurmdbError {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    error: String,
+    #[serde(default)]
+    error_number: i64,
+}
+
+impl std::fmt::Display for SlurmdbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Error {} ({}): {}",
+            self.error_number, self.error, self.description
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SlurmdbWarning {
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Association {
+    #[serde(default)]
+    account: String,
+    #[serde(default)]
+    user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cluster: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partition: Option<String>,
+    #[serde(default)]
+    default: AssociationDefault,
+    #[serde(default)]
+    qos: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+struct Account {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    coordinators: Vec<Coordinator>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+struct Coordinator {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct AssociationDefault {
+    #[serde(default)]
+    qos: String,
+}
+
+/// Limits of users are only set via sacctmgr.
+///
+/// # Errors
+///
+/// - If `limits` is not empty.
+fn ensure_no_limits(limits: &[SlurmLimit]) -> AppResult {
+    if !limits.is_empty() {
+        bail!("Limits of users can not be set via slurmrestd. Set use_slurmrestd = false to set them via sacctmgr.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod testing {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    use super::*;
+    use crate::{
+        cli::{CommonUserFields, UserToAdd},
+        Entity,
+    };
+
+    /// Request as received by [`MockSlurmRestd`]
+    #[derive(Debug)]
+    struct ReceivedRequest {
+        request_line: String,
+        token: Option<String>,
+        user_name: Option<String>,
+        body: String,
+    }
+
+    /// Local HTTP server which answers each request with the next of the given responses.
+    struct MockSlurmRestd {
+        url: String,
+        server: JoinHandle<Vec<ReceivedRequest>>,
+    }
+
+    impl MockSlurmRestd {
+        fn start(responses: Vec<(u16, &'static str)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let server = std::thread::spawn(move || {
+                responses
+                    .into_iter()
+                    .map(|(status, body)| {
+                        let (stream, _) = listener.accept().unwrap();
+                        let mut reader = BufReader::new(stream);
+                        let request = Self::read_request(&mut reader);
+                        let mut stream = reader.into_inner();
+                        write!(
+                            stream,
+                            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        )
+                        .unwrap();
+                        request
+                    })
+                    .collect()
+            });
+            Self { url, server }
+        }
+
+        fn client(&self) -> SlurmRestd {
+            SlurmRestd::new(
+                &self.url,
+                DEFAULT_API_VERSION,
+                Some("admin".to_owned()),
+                "secret".to_owned(),
+                Some("cluster".to_owned()),
+            )
+        }
+
+        fn received(self) -> Vec<ReceivedRequest> {
+            self.server.join().unwrap()
+        }
+
+        fn read_request(reader: &mut impl BufRead) -> ReceivedRequest {
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let (mut token, mut user_name, mut length) = (None, None, 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(": ").unwrap();
+                match name.to_lowercase().as_str() {
+                    "x-slurm-user-token" => token = Some(value.to_owned()),
+                    "x-slurm-user-name" => user_name = Some(value.to_owned()),
+                    "content-length" => length = value.parse().unwrap(),
+                    _ => (),
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            ReceivedRequest {
+                request_line: request_line.trim_end().to_owned(),
+                token,
+                user_name,
+                body: String::from_utf8(body).unwrap(),
+            }
+        }
+    }
+
+    const EMPTY_ANSWER: &str = r#"{"errors": [], "warnings": []}"#;
+
+    #[test]
+    fn adds_user_with_association() {
+        let mock = MockSlurmRestd::start(vec![(200, EMPTY_ANSWER), (200, EMPTY_ANSWER)]);
+        let config = MgmtConfig::default();
+        let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+        fields.group = Some("staff".try_into().unwrap());
+        let to_add = UserToAdd::new(
+            "Jane".try_into().unwrap(),
+            "Doe".try_into().unwrap(),
+            fields,
+        );
+        let entity = NewEntity::new_user_addition_conf(to_add, &config).unwrap();
+
+        mock.client().add_user(&entity).unwrap();
+
+        let received = mock.received();
+        for request in received.iter() {
+            assert_eq!(Some("secret"), request.token.as_deref());
+            assert_eq!(Some("admin"), request.user_name.as_deref());
+        }
+        insta::assert_debug_snapshot!(received);
+    }
+
+    #[test]
+    fn lists_associations_of_users() {
+        let answer = r#"{
+            "associations": [
+                {"account": "student", "user": "zoe", "cluster": "cluster",
+                 "default": {"qos": "basic"}, "qos": ["basic", "interactive"]},
+                {"account": "root", "user": "", "cluster": "cluster",
+                 "default": {"qos": ""}, "qos": ["normal"]},
+                {"account": "staff", "user": "jane", "cluster": "cluster", "partition": "a100",
+                 "default": {"qos": "advanced"}, "qos": ["advanced"], "id": {"id": 3}}
+            ],
+            "errors": []
+        }"#;
+        let mock = MockSlurmRestd::start(vec![(200, answer)]);
+
+        let listed: Vec<String> = mock
+            .client()
+            .list_associations()
+            .unwrap()
+            .iter()
+            .map(SlurmAssociation::to_parsable_line)
+            .collect();
+
+        let received = mock.received();
+        assert_eq!(
+            "GET /slurmdb/v0.0.40/associations?cluster=cluster HTTP/1.1",
+            received[0].request_line
+        );
+        assert_eq!(
+            [
+                "zoe|student||basic|basic,interactive|||||||",
+                "|root|||normal|||||||",
+                "jane|staff|a100|advanced|advanced|||||||",
+            ],
+            listed.as_slice()
+        );
+    }
+
+    #[test]
+    fn modifies_qos_of_all_associations() {
+        let answer = r#"{
+            "associations": [
+                {"account": "staff", "user": "jane", "cluster": "cluster",
+                 "default": {"qos": "advanced"}, "qos": ["advanced"]},
+                {"account": "staff", "user": "jane", "cluster": "cluster", "partition": "gpu",
+                 "default": {"qos": "advanced"}, "qos": ["advanced"]}
+            ]
+        }"#;
+        let mock = MockSlurmRestd::start(vec![(200, answer), (200, EMPTY_ANSWER)]);
+        let config = MgmtConfig::default();
+        let mut fields = CommonUserFields::new("jane".try_into().unwrap());
+        fields.qos = vec!["basic".to_owned()];
+        fields.default_qos = Some("basic".try_into().unwrap());
+        let entity = Entity::new(None, None, fields, &config).unwrap();
+        let modifiable = ChangesToUser::try_new(entity).unwrap();
+
+        mock.client().modify_user(&modifiable).unwrap();
+
+        let received = mock.received();
+        assert_eq!(
+            "GET /slurmdb/v0.0.40/associations?user=jane&cluster=cluster HTTP/1.1",
+            received[0].request_line
+        );
+        insta::assert_snapshot!(received[1].body);
+    }
+
+    #[test]
+    fn removes_association_with_encoded_query() {
+        let mock = MockSlurmRestd::start(vec![(200, EMPTY_ANSWER)]);
+        let config = MgmtConfig::default();
+        let mut entity = Entity::new(
+            None,
+            None,
+            CommonUserFields::new("jane".try_into().unwrap()),
+            &config,
+        )
+        .unwrap();
+        entity.remove_accounts = vec!["gpu&cpu:a100".parse().unwrap()];
+        let modifiable = ChangesToUser::try_new(entity).unwrap();
+
+        mock.client().modify_user(&modifiable).unwrap();
+
+        let received = mock.received();
+        assert_eq!(
+            "DELETE /slurmdb/v0.0.40/association?user=jane&account=gpu%26cpu&partition=a100&cluster=cluster HTTP/1.1",
+            received[0].request_line
+        );
+    }
+
+    #[test]
+    fn reports_errors_of_slurmrestd() {
+        let answer = r#"{
+            "errors": [{"description": "Nothing found", "error_number": 9003,
+                        "error": "No error", "source": "_delete_user"}]
+        }"#;
+        let mock = MockSlurmRestd::start(vec![(500, answer)]);
+
+        let error = mock.client().delete_user("jane").unwrap_err();
+
+        let received = mock.received();
+        assert_eq!(
+            "DELETE /slurmdb/v0.0.40/user/jane HTTP/1.1",
+            received[0].request_line
+        );
+        let error = format!("{:#}", error);
+        assert!(error.starts_with("Failed to delete user jane from Slurm"));
+        assert!(error.ends_with("with status 500. Error 9003 (No error): Nothing found"));
+    }
+}
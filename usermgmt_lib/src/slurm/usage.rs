@@ -0,0 +1,373 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use super::qos::wall_time_secs;
+use crate::prelude::*;
+
+/// Character which splits cells in a row of the output of sreport and sacct with the option --parsable2
+const SPLIT_BETWEEN_CELLS: char = '|';
+/// Character which splits the TRES in the field AllocTRES of sacct like `cpu=4,gres/gpu=1`
+const SPLIT_BETWEEN_TRES: char = ',';
+const CPU_TRES: &str = "cpu";
+const GPU_TRES: &str = "gres/gpu";
+const SECONDS_PER_HOUR: f64 = 3600.0;
+/// Format of dates as sreport and sacct expect them
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Fields which sreport is asked for in the report AccountUtilizationByUser.
+/// The output of sreport contains the fields in exactly this order.
+pub const SREPORT_FIELDS: [&str; 5] = ["Cluster", "Account", "Login", "TresName", "Used"];
+/// Fields which sacct is asked for when listing jobs.
+/// The output of sacct contains the fields in exactly this order.
+pub const SACCT_FIELDS: [&str; 6] = ["JobID", "User", "Account", "State", "Elapsed", "AllocTRES"];
+
+/// Whose usage is reported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageFilter {
+    All,
+    User(String),
+    /// Usage of an account and each of its users
+    Account(String),
+}
+
+/// Days for which the usage is reported. The end is excluded like it is for sreport and sacct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsagePeriod {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl UsagePeriod {
+    /// # Errors
+    ///
+    /// - If `start` is not before `end`.
+    pub fn new(start: NaiveDate, end: NaiveDate) -> AppResult<Self> {
+        if start >= end {
+            bail!(
+                "Start {} of the usage period must be before its end {}",
+                start,
+                end
+            );
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn start(&self) -> String {
+        self.start.format(DATE_FORMAT).to_string()
+    }
+
+    pub fn end(&self) -> String {
+        self.end.format(DATE_FORMAT).to_string()
+    }
+}
+
+/// CPU and GPU hours used by an account or by a user within an account as reported by sreport.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountUsage {
+    pub cluster: String,
+    pub account: String,
+    /// `None` for the usage of the whole account
+    pub user: Option<String>,
+    pub cpu_hours: f64,
+    pub gpu_hours: f64,
+}
+
+/// Job with the CPU and GPU hours it used as listed by sacct.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobUsage {
+    pub job_id: String,
+    pub user: String,
+    pub account: String,
+    pub state: String,
+    pub elapsed_secs: u64,
+    pub cpus: u64,
+    pub gpus: u64,
+    pub cpu_hours: f64,
+    pub gpu_hours: f64,
+}
+
+/// Usage of users and accounts within a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub start: String,
+    pub end: String,
+    pub accounts: Vec<AccountUsage>,
+    pub jobs: Vec<JobUsage>,
+}
+
+impl UsageReport {
+    /// Returns pretty ASCII tables, one with the usage of accounts and users and one with the jobs.
+    pub fn to_pretty_tables(&self) -> String {
+        use prettytable::{Row, Table};
+
+        let hours = |hours: f64| format!("{:.2}", hours);
+        let mut accounts = Table::new();
+        accounts.set_titles(Row::from([
+            "Cluster",
+            "Account",
+            "User",
+            "CPU hours",
+            "GPU hours",
+        ]));
+        for usage in self.accounts.iter() {
+            accounts.add_row(Row::from([
+                usage.cluster.clone(),
+                usage.account.clone(),
+                usage.user.clone().unwrap_or_default(),
+                hours(usage.cpu_hours),
+                hours(usage.gpu_hours),
+            ]));
+        }
+        let mut jobs = Table::new();
+        jobs.set_titles(Row::from([
+            "Job",
+            "User",
+            "Account",
+            "State",
+            "CPUs",
+            "GPUs",
+            "CPU hours",
+            "GPU hours",
+        ]));
+        for job in self.jobs.iter() {
+            jobs.add_row(Row::from([
+                job.job_id.clone(),
+                job.user.clone(),
+                job.account.clone(),
+                job.state.clone(),
+                job.cpus.to_string(),
+                job.gpus.to_string(),
+                hours(job.cpu_hours),
+                hours(job.gpu_hours),
+            ]));
+        }
+        format!(
+            "Usage from {} until {}\n{}\n{}",
+            self.start, self.end, accounts, jobs
+        )
+    }
+}
+
+/// Arguments for sreport to report the CPU and GPU hours per account and user
+/// in the fields [`SREPORT_FIELDS`].
+pub fn sreport_args(filter: &UsageFilter, period: &UsagePeriod) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "--parsable2",
+        "--noheader",
+        "-t",
+        "Hours",
+        "--tres=cpu,gres/gpu",
+        "cluster",
+        "AccountUtilizationByUser",
+    ]
+    .into_iter()
+    .map(ToOwned::to_owned)
+    .collect();
+    args.push(format!("start={}", period.start()));
+    args.push(format!("end={}", period.end()));
+    match filter {
+        UsageFilter::All => (),
+        UsageFilter::User(user) => args.push(format!("users={}", user)),
+        UsageFilter::Account(account) => args.push(format!("accounts={}", account)),
+    }
+    args.push(format!("format={}", SREPORT_FIELDS.join(",")));
+    args
+}
+
+/// Arguments for sacct to list the jobs with the fields [`SACCT_FIELDS`].
+/// Only the allocations of jobs are listed and not their steps.
+pub fn sacct_args(filter: &UsageFilter, period: &UsagePeriod) -> Vec<String> {
+    let mut args: Vec<String> = ["--parsable2", "--noheader", "--allocations"]
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect();
+    args.push(format!("--starttime={}", period.start()));
+    args.push(format!("--endtime={}", period.end()));
+    match filter {
+        UsageFilter::All => args.push("--allusers".to_owned()),
+        UsageFilter::User(user) => args.push(format!("--user={}", user)),
+        UsageFilter::Account(account) => {
+            args.push("--allusers".to_owned());
+            args.push(format!("--accounts={}", account));
+        }
+    }
+    args.push(format!("--format={}", SACCT_FIELDS.join(",")));
+    args
+}
+
+/// Parses the output of sreport with the arguments of [`sreport_args`].
+/// The rows for CPU and GPU of an account or user are merged into one [`AccountUsage`].
+///
+/// # Errors
+///
+/// - If the number of fields in a line is not the number of [`SREPORT_FIELDS`].
+/// - If the used hours are not a number.
+pub fn parse_account_usage(output: &str) -> AppResult<Vec<AccountUsage>> {
+    let mut all: Vec<AccountUsage> = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let [cluster, account, login, tres, used] = cells.as_slice() else {
+            bail!(
+                "Malformed usage in line {} of sreport output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                SREPORT_FIELDS.len(),
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        let used: f64 = used.trim().parse().with_context(|| {
+            format!(
+                "Used hours '{}' in line {} of sreport output are not a number",
+                used,
+                index + 1
+            )
+        })?;
+        let user = (!login.is_empty()).then(|| login.to_string());
+        let position = all.iter().position(|known| {
+            known.cluster == *cluster && known.account == *account && known.user == user
+        });
+        let usage = match position {
+            Some(position) => &mut all[position],
+            None => {
+                all.push(AccountUsage {
+                    cluster: cluster.to_string(),
+                    account: account.to_string(),
+                    user,
+                    cpu_hours: 0.0,
+                    gpu_hours: 0.0,
+                });
+                let last = all.len() - 1;
+                &mut all[last]
+            }
+        };
+        match *tres {
+            CPU_TRES => usage.cpu_hours += used,
+            GPU_TRES => usage.gpu_hours += used,
+            _ => (),
+        }
+    }
+    Ok(all)
+}
+
+/// Parses the output of sacct with the arguments of [`sacct_args`].
+///
+/// # Errors
+///
+/// - If the number of fields in a line is not the number of [`SACCT_FIELDS`].
+/// - If the elapsed time of a job is not in the format `[days-]hours:minutes:seconds`.
+pub fn parse_job_usage(output: &str) -> AppResult<Vec<JobUsage>> {
+    let mut jobs = Vec::new();
+    for (index, line) in output.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).collect();
+        let [job_id, user, account, state, elapsed, alloc_tres] = cells.as_slice() else {
+            bail!(
+                "Malformed job in line {} of sacct output: '{}': Expected {} fields separated by '{}' but found {}",
+                index + 1,
+                line,
+                SACCT_FIELDS.len(),
+                SPLIT_BETWEEN_CELLS,
+                cells.len()
+            );
+        };
+        let elapsed_secs = wall_time_secs(elapsed).with_context(|| {
+            format!(
+                "Elapsed time '{}' of job {} in line {} of sacct output is malformed",
+                elapsed,
+                job_id,
+                index + 1
+            )
+        })?;
+        let (cpus, gpus) = (
+            tres_count(alloc_tres, CPU_TRES),
+            tres_count(alloc_tres, GPU_TRES),
+        );
+        let hours = elapsed_secs as f64 / SECONDS_PER_HOUR;
+        jobs.push(JobUsage {
+            job_id: job_id.to_string(),
+            user: user.to_string(),
+            account: account.to_string(),
+            state: state.to_string(),
+            elapsed_secs,
+            cpus,
+            gpus,
+            cpu_hours: cpus as f64 * hours,
+            gpu_hours: gpus as f64 * hours,
+        });
+    }
+    Ok(jobs)
+}
+
+/// Returns the count of `tres` in a list like `billing=4,cpu=4,gres/gpu=1,mem=8G` or 0 if absent.
+fn tres_count(alloc_tres: &str, tres: &str) -> u64 {
+    alloc_tres
+        .split(SPLIT_BETWEEN_TRES)
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == tres)
+        .and_then(|(_, count)| count.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn period() -> UsagePeriod {
+        UsagePeriod::new(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn builds_arguments_for_sreport_and_sacct() {
+        let period = period();
+        let actual: Vec<Vec<String>> = [
+            UsageFilter::All,
+            UsageFilter::User("jane".to_owned()),
+            UsageFilter::Account("proj1".to_owned()),
+        ]
+        .iter()
+        .flat_map(|filter| [sreport_args(filter, &period), sacct_args(filter, &period)])
+        .collect();
+        insta::assert_debug_snapshot!(actual);
+
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert!(UsagePeriod::new(day, day).is_err());
+    }
+
+    #[test]
+    fn parses_account_usage() {
+        let input = "cluster|proj1||cpu|120\n\
+                     cluster|proj1||gres/gpu|10\n\
+                     cluster|proj1|jane|cpu|100\n\
+                     cluster|proj1|jane|gres/gpu|10\n\
+                     cluster|proj1|zoe|cpu|20\n\
+                     cluster|proj1|zoe|gres/gpu|0\n";
+
+        let actual = parse_account_usage(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+
+        assert!(parse_account_usage("cluster|proj1|jane|cpu|many\n").is_err());
+        assert!(parse_account_usage("cluster|proj1|jane\n").is_err());
+    }
+
+    #[test]
+    fn parses_jobs_with_cpu_and_gpu_hours() {
+        let input = "101|jane|proj1|COMPLETED|02:00:00|billing=4,cpu=4,gres/gpu=1,mem=8G,node=1\n\
+                     102|zoe|proj1|RUNNING|1-00:30:00|cpu=2,mem=4G,node=1\n\
+                     103|zoe|proj1|PENDING|00:00:00|\n";
+
+        let actual = parse_job_usage(input).unwrap();
+        insta::assert_debug_snapshot!(actual);
+
+        assert!(parse_job_usage("104|jane|proj1|FAILED|soon|cpu=1\n").is_err());
+    }
+}
@@ -1,8 +1,19 @@
 use std::path::Path;
+use std::time::Duration;
 
-use crate::{config::MgmtConfig, prelude::AppResult};
+use crate::prelude::*;
+use crate::{config::MgmtConfig, constants};
 use log::debug;
+mod file_transfer;
+mod fingerprint;
+mod jump_host;
+mod known_hosts;
+mod local_command;
+mod remote_output;
+mod ssh_auth_method;
+mod ssh_client_config;
 mod ssh_connection;
+mod ssh_connection_pool;
 
 use ssh2::{Agent, PublicKey, Session};
 mod ssh_credentials;
@@ -10,20 +21,27 @@ mod ssh_given_credential;
 mod ssh_key_pairs;
 mod ssh_public_key_suggestion;
 
+pub use file_transfer::{FileOwner, DEFAULT_UPLOAD_MODE};
+pub use fingerprint::sha256_fingerprint;
+pub use known_hosts::{known_hosts_path, UnknownHostKey};
+pub use remote_output::RemoteOutput;
+pub use ssh_auth_method::SshAuthMethod;
+pub use ssh_client_config::{ssh_client_config_path, SshClientConfig, SshHostOptions};
 pub use ssh_connection::SshConnection;
+pub use ssh_connection_pool::SshConnectionPool;
 pub use ssh_credentials::SshCredentials;
 pub use ssh_given_credential::SshGivenCredential;
 pub use ssh_key_pairs::SshKeyPair;
 pub use ssh_public_key_suggestion::SshPublicKeySuggestion;
 
+/// Returns the key pair at `path` or at `ssh_key_path` of the configuration.
+/// `ssh_certificate_path` of the configuration is used as its certificate if given.
 pub fn create_ssh_key_pair_conf(path: Option<&Path>, conf: &MgmtConfig) -> Option<SshKeyPair> {
-    if path.is_some() {
-        return path
-            .to_owned()
-            .map(|path| path.to_path_buf())
-            .map(SshKeyPair::from_one_path);
-    }
-    conf.ssh_key_path.clone().map(SshKeyPair::from_one_path)
+    path.map(Path::to_path_buf)
+        .or_else(|| conf.ssh_key_path.clone())
+        .map(|path| {
+            SshKeyPair::from_one_path(path).with_certificate(conf.ssh_certificate_path.clone())
+        })
 }
 
 /// Contains all accessible ssh keys with their ssh agent.
@@ -36,24 +54,63 @@ pub enum EntitiesAndSshAgent {
     Many(Agent, Vec<PublicKey>),
 }
 
+/// Executes `cmd` via `sh -c` on the local machine the same way as for a host with `local = true`.
+/// See [`SshConnection::is_local`].
+///
+/// # Errors
+///
+/// - If the command could not be run or did not finish within `ssh_command_timeout_secs` of `config`.
+pub fn run_local_command(cmd: &str, config: &MgmtConfig) -> AppResult<RemoteOutput> {
+    debug!("Running local command: {}", cmd);
+    local_command::run_local_command(cmd, None, command_timeout(config))
+        .with_context(|| format!("Local command '{}' failed", cmd))
+}
+
+/// Time after which a command is aborted. See `ssh_command_timeout_secs` in the configuration.
+fn command_timeout(config: &MgmtConfig) -> Duration {
+    Duration::from_secs(
+        config
+            .ssh_command_timeout_secs
+            .unwrap_or(constants::DEFAULT_SSH_COMMAND_TIMEOUT_SECS)
+            .into(),
+    )
+}
+
 /// Executes given command `cmd` on remote machine over ssh
 ///
 /// # Errors
 ///
 /// - If the execution of remote command fails. See [`SshConnection::exec`].
-pub fn run_remote_command<C>(sess: &SshConnection<C>, cmd: &str) -> AppResult<(i32, String)>
+pub fn run_remote_command<C>(sess: &SshConnection<C>, cmd: &str) -> AppResult<RemoteOutput>
+where
+    C: SshCredentials,
+{
+    run_remote_command_with_input(sess, cmd, None)
+}
+
+/// Like [`run_remote_command`] but passes `input` to the stdin of the command if given.
+/// The input is not logged as it can contain secrets like a password.
+pub fn run_remote_command_with_input<C>(
+    sess: &SshConnection<C>,
+    cmd: &str,
+    input: Option<&str>,
+) -> AppResult<RemoteOutput>
 where
     C: SshCredentials,
 {
     debug!("Running command: {}", cmd);
 
-    let (s, exit_status) = sess.exec(cmd)?;
+    let output = sess.exec_with_input(cmd, input)?;
 
-    debug!("Command exit status: {}", exit_status);
-    if exit_status != 0 {
-        debug!("Command output: {}", s);
+    debug!(
+        "Command exit status: {} after {:?}",
+        output.exit_code, output.duration
+    );
+    if !output.is_success() {
+        debug!("Command output: {}", output.stdout);
+        debug!("Command error output: {}", output.stderr);
     }
-    Ok((exit_status, s))
+    Ok(output)
 }
 
 /// Tries get all identities, pub keys, from the active ssh agent.
@@ -0,0 +1,307 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use ssh2::{FileStat, OpenFlags, OpenType, RenameFlags, Sftp};
+
+use crate::prelude::*;
+
+/// Permissions of uploaded files if no mode is given
+pub const DEFAULT_UPLOAD_MODE: u32 = 0o644;
+
+/// Numeric user and group which own an uploaded file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileOwner {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Writes the local file `local` into a temporary file next to `remote` via SFTP,
+/// sets its mode and owner and renames it to `remote` afterwards.
+/// Thus readers never see a partially written file at `remote`.
+///
+/// # Errors
+///
+/// - If the local file could not be read or the temporary remote file could not be written.
+/// - If mode or owner could not be set, for example because the SSH user may not change owners.
+/// - If the temporary file could not be renamed to `remote`.
+pub(super) fn upload_via_sftp(
+    sftp: &Sftp,
+    local: &Path,
+    remote: &Path,
+    mode: Option<u32>,
+    owner: Option<FileOwner>,
+) -> AppResult {
+    let temporary = temporary_path(remote);
+    let uploaded = write_via_sftp(sftp, local, &temporary, mode, owner)
+        .and_then(|()| rename_via_sftp(sftp, &temporary, remote));
+
+    if uploaded.is_err() {
+        if let Err(error) = sftp.unlink(&temporary) {
+            warn!(
+                "Could not delete temporary remote file at {:?}: {}",
+                temporary, error
+            );
+        }
+    }
+    uploaded
+}
+
+/// Writes the local file `local` to `remote` via SFTP with `mode` or [`DEFAULT_UPLOAD_MODE`] and `owner`.
+fn write_via_sftp(
+    sftp: &Sftp,
+    local: &Path,
+    remote: &Path,
+    mode: Option<u32>,
+    owner: Option<FileOwner>,
+) -> AppResult {
+    let mode = mode.unwrap_or(DEFAULT_UPLOAD_MODE);
+    let mut source = File::open(local)
+        .with_context(|| format!("Could not open local file at {:?} for upload", local))?;
+    let mut target = sftp
+        .open_mode(
+            remote,
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            mode as i32,
+            OpenType::File,
+        )
+        .with_context(|| format!("Could not create remote file at {:?}", remote))?;
+    io::copy(&mut source, &mut target)
+        .with_context(|| format!("Could not upload file from {:?} to {:?}", local, remote))?;
+    drop(target);
+
+    // The mode given at creation is subject to the umask of the server.
+    let stat = FileStat {
+        size: None,
+        uid: owner.map(|owner| owner.uid),
+        gid: owner.map(|owner| owner.gid),
+        perm: Some(mode),
+        atime: None,
+        mtime: None,
+    };
+    sftp.setstat(remote, stat).with_context(|| {
+        format!(
+            "Could not set mode {:o} and owner {:?} of remote file at {:?}",
+            mode, owner, remote
+        )
+    })
+}
+
+/// Reads the remote file `remote` via SFTP into a temporary file next to `local`
+/// and renames it to `local` afterwards.
+///
+/// # Errors
+///
+/// - If the remote file could not be read or the temporary local file could not be written.
+/// - If the temporary file could not be renamed to `local`.
+pub(super) fn download_via_sftp(sftp: &Sftp, remote: &Path, local: &Path) -> AppResult {
+    write_atomically(local, |target, _| {
+        let mut source = sftp
+            .open(remote)
+            .with_context(|| format!("Could not open remote file at {:?}", remote))?;
+        io::copy(&mut source, target)
+            .with_context(|| format!("Could not download file from {:?} to {:?}", remote, local))?;
+        Ok(())
+    })
+}
+
+/// Same as [`upload_via_sftp`] for a connection to the local machine.
+/// Mode and owner are set via chmod and chown.
+///
+/// # Errors
+///
+/// - If `from` could not be read or the temporary file could not be written.
+/// - If chmod or chown failed.
+/// - If the temporary file could not be renamed to `to`.
+pub(super) fn copy_locally(
+    from: &Path,
+    to: &Path,
+    mode: Option<u32>,
+    owner: Option<FileOwner>,
+) -> AppResult {
+    write_atomically(to, |target, temporary| {
+        let mut source = File::open(from)
+            .with_context(|| format!("Could not open local file at {:?} for copying", from))?;
+        io::copy(&mut source, target)
+            .with_context(|| format!("Could not copy local file from {:?} to {:?}", from, to))?;
+        let mode = mode.unwrap_or(DEFAULT_UPLOAD_MODE);
+        run_local_tool("chmod", &format!("{:o}", mode), temporary)?;
+        if let Some(owner) = owner {
+            run_local_tool("chown", &format!("{}:{}", owner.uid, owner.gid), temporary)?;
+        }
+        Ok(())
+    })
+}
+
+/// Temporary file in the same directory as `target`, so that it can be renamed to `target`.
+/// The name is hidden and unique per process and time.
+fn temporary_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.subsec_nanos())
+        .unwrap_or_default();
+    target.with_file_name(format!(
+        ".{}.{}-{}.usermgmt.tmp",
+        name,
+        process::id(),
+        nanos
+    ))
+}
+
+/// Renames `from` to `to` on the server and replaces an existing file at `to`.
+/// Servers like OpenSSH do not replace existing files on rename via SFTP.
+/// For them an existing file is deleted first, so `to` is briefly absent but never partially written.
+fn rename_via_sftp(sftp: &Sftp, from: &Path, to: &Path) -> AppResult {
+    let flags = RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE;
+    if sftp.rename(from, to, Some(flags)).is_ok() {
+        return Ok(());
+    }
+    if sftp.stat(to).is_ok() {
+        sftp.unlink(to)
+            .with_context(|| format!("Could not replace remote file at {:?}", to))?;
+    }
+    sftp.rename(from, to, None)
+        .with_context(|| format!("Could not rename remote file {:?} to {:?}", from, to))
+}
+
+/// Writes into a temporary file next to `target` via `write` and renames it to `target` afterwards.
+/// `write` gets the opened temporary file and its path. The temporary file is deleted if writing fails.
+fn write_atomically(target: &Path, write: impl FnOnce(&mut File, &Path) -> AppResult) -> AppResult {
+    let temporary = temporary_path(target);
+    let written = File::create(&temporary)
+        .with_context(|| format!("Could not create local file at {:?}", temporary))
+        .and_then(|mut file| write(&mut file, &temporary))
+        .and_then(|()| {
+            std::fs::rename(&temporary, target).with_context(|| {
+                format!(
+                    "Could not rename local file {:?} to {:?}",
+                    temporary, target
+                )
+            })
+        });
+    if written.is_err() && temporary.exists() {
+        if let Err(error) = std::fs::remove_file(&temporary) {
+            warn!(
+                "Could not delete temporary local file at {:?}: {}",
+                temporary, error
+            );
+        }
+    }
+    written
+}
+
+fn run_local_tool(tool: &str, argument: &str, path: &Path) -> AppResult {
+    let output = Command::new(tool)
+        .arg(argument)
+        .arg(path)
+        .output()
+        .with_context(|| format!("Could not run {} on the local machine", tool))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} {:?} failed: {}",
+            tool,
+            argument,
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::config::{MgmtConfig, SshHostConfig};
+    use crate::ssh::{SshConnection, SshGivenCredential};
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("usermgmt_{}_{}", name, process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Uploads a file, replaces it by a second upload and downloads it again.
+    fn assert_round_trip(connection: &SshConnection<SshGivenCredential>, directory: &Path) {
+        let (source, remote, downloaded) = (
+            directory.join("source"),
+            directory.join("README"),
+            directory.join("downloaded"),
+        );
+        std::fs::write(&source, "first").unwrap();
+        connection
+            .upload(&source, &remote, Some(0o600), None)
+            .unwrap();
+        std::fs::write(&source, "Welcome").unwrap();
+        connection.upload(&source, &remote, None, None).unwrap();
+        connection.download(&remote, &downloaded).unwrap();
+
+        assert_eq!("Welcome", std::fs::read_to_string(&downloaded).unwrap());
+        let mut names: Vec<String> = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(vec!["README", "downloaded", "source"], names);
+    }
+
+    #[test]
+    fn places_temporary_files_hidden_next_to_the_target() {
+        let actual = temporary_path(Path::new("/home/alice/README"));
+
+        assert_eq!(Some(Path::new("/home/alice")), actual.parent());
+        let name = actual.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with(".README."));
+        assert!(name.ends_with(".usermgmt.tmp"));
+    }
+
+    #[test]
+    fn transfers_files_atomically_on_the_local_machine() {
+        let directory = test_directory("local_transfer");
+        let config = MgmtConfig {
+            ssh_hosts: [(
+                "localhost".to_owned(),
+                SshHostConfig {
+                    local: Some(true),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let connection = SshConnection::new("localhost", &config, SshGivenCredential::default());
+
+        assert_round_trip(&connection, &directory);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// Needs an sshd on 127.0.0.1 whose host key is in the known hosts file
+    /// and a key of the current user within the SSH agent.
+    #[test]
+    #[ignore = "needs a local sshd, run via cargo test -- --ignored"]
+    fn transfers_files_atomically_via_sftp_of_local_sshd() {
+        let directory = test_directory("sftp_transfer");
+        let host = SshHostConfig {
+            local: Some(false),
+            ..Default::default()
+        };
+        let config = MgmtConfig {
+            ssh_agent: true,
+            ssh_hosts: [("127.0.0.1".to_owned(), host)].into_iter().collect(),
+            ..Default::default()
+        };
+        let user = std::env::var("USER").unwrap();
+        let credentials = SshGivenCredential::new(&user, "", None);
+        let connection = SshConnection::new("127.0.0.1", &config, credentials);
+
+        assert_round_trip(&connection, &directory);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+/// Round constants of SHA-256
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Fingerprint of a public key in the same form as `ssh-add -l` or `ssh-keygen -l` show it,
+/// for example `SHA256:bafCAbiwukZZtpoLyD9CpXEbHKwB9D2p67ws9A+g8ms`.
+/// `public_key_blob` is the key in the binary SSH wire format, not the base64 text of a `.pub` file.
+pub fn sha256_fingerprint(public_key_blob: &[u8]) -> String {
+    format_sha256(&sha256(public_key_blob))
+}
+
+/// Formats an SHA-256 hash as fingerprint like OpenSSH does.
+pub(super) fn format_sha256(hash: &[u8]) -> String {
+    format!("SHA256:{}", base64_without_padding(hash))
+}
+
+/// True if `fingerprint` denotes the same key as `expected`, which may omit the prefix `SHA256:`.
+pub(super) fn matches_fingerprint(fingerprint: &str, expected: &str) -> bool {
+    let without_prefix = |text: &str| -> String {
+        let text = text.trim();
+        text.strip_prefix("SHA256:").unwrap_or(text).to_owned()
+    };
+    without_prefix(fingerprint) == without_prefix(expected)
+}
+
+/// Encodes as base64 without trailing '=' like OpenSSH does for fingerprints.
+fn base64_without_padding(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let block = chunk.iter().enumerate().fold(0u32, |block, (index, byte)| {
+            block | (u32::from(*byte) << (16 - 8 * index))
+        });
+        for index in 0..=chunk.len() {
+            let sextet = (block >> (18 - 6 * index)) & 0b11_1111;
+            encoded.push(char::from(ALPHABET[sextet as usize]));
+        }
+    }
+    encoded
+}
+
+/// SHA-256 as specified in FIPS 180-4. Only used for fingerprints of public keys, which are small.
+fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut schedule = [0u32; 64];
+        for (word, bytes) in schedule.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for index in 16..64 {
+            let (before_15, before_2) = (schedule[index - 15], schedule[index - 2]);
+            let s0 = before_15.rotate_right(7) ^ before_15.rotate_right(18) ^ (before_15 >> 3);
+            let s1 = before_2.rotate_right(17) ^ before_2.rotate_right(19) ^ (before_2 >> 10);
+            schedule[index] = schedule[index - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[index - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for (constant, word) in SHA256_ROUND_CONSTANTS.iter().zip(schedule) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*constant)
+                .wrapping_add(word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut hash = [0; 32];
+    for (bytes, value) in hash.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn encodes_fingerprints_as_base64_without_padding() {
+        assert_eq!("", base64_without_padding(b""));
+        assert_eq!("Zg", base64_without_padding(b"f"));
+        assert_eq!("Zm8", base64_without_padding(b"fo"));
+        assert_eq!("Zm9v", base64_without_padding(b"foo"));
+        assert_eq!("Zm9vYmFy", base64_without_padding(b"foobar"));
+        assert_eq!("+/8", base64_without_padding(&[0xfb, 0xff]));
+    }
+
+    #[test]
+    fn hashes_like_sha256() {
+        let hex =
+            |hash: [u8; 32]| -> String { hash.iter().map(|b| format!("{:02x}", b)).collect() };
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            hex(sha256(b""))
+        );
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            hex(sha256(b"abc"))
+        );
+        assert_eq!(
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            hex(sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            ))
+        );
+    }
+
+    #[test]
+    fn computes_fingerprints_like_ssh_keygen() {
+        // Blob of "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAICR+P/FZHltpoOE5fHYU9FHkbDeDPcTS8U3uNT3ab6IS"
+        let blob = [
+            0x00, 0x00, 0x00, 0x0b, 0x73, 0x73, 0x68, 0x2d, 0x65, 0x64, 0x32, 0x35, 0x35, 0x31,
+            0x39, 0x00, 0x00, 0x00, 0x20, 0x24, 0x7e, 0x3f, 0xf1, 0x59, 0x1e, 0x5b, 0x69, 0xa0,
+            0xe1, 0x39, 0x7c, 0x76, 0x14, 0xf4, 0x51, 0xe4, 0x6c, 0x37, 0x83, 0x3d, 0xc4, 0xd2,
+            0xf1, 0x4d, 0xee, 0x35, 0x3d, 0xda, 0x6f, 0xa2, 0x12,
+        ];
+
+        let actual = sha256_fingerprint(&blob);
+
+        assert_eq!("SHA256:bafCAbiwukZZtpoLyD9CpXEbHKwB9D2p67ws9A+g8ms", actual);
+        assert!(matches_fingerprint(
+            &actual,
+            " bafCAbiwukZZtpoLyD9CpXEbHKwB9D2p67ws9A+g8ms"
+        ));
+        assert!(!matches_fingerprint(&actual, "SHA256:other"));
+    }
+}
@@ -0,0 +1,253 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use log::{debug, warn};
+use polling::{Event, Poller};
+use ssh2::{BlockDirections, Channel, Session};
+
+use crate::prelude::*;
+
+/// Size of the buffer for each direction of a forwarded connection
+const FORWARD_BUFFER_SIZE: usize = 16 * 1024;
+/// Key of the socket of the jump session within the poller of a forwarded connection
+const JUMP_SOCKET_KEY: usize = 0;
+
+/// One host of a list of jump hosts in the form `[user@]host[:port]`.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct JumpHop {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u32>,
+}
+
+/// Parses jump hosts like the value of the option ProxyJump of OpenSSH.
+/// Hosts are separated by commas and visited in the given order. The value `none` yields no jump host.
+/// IPv6 addresses with port are enclosed in brackets like `[::1]:22`.
+pub(super) fn parse_jump_hops(jump_hosts: &str) -> Vec<JumpHop> {
+    if jump_hosts.trim().eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    jump_hosts
+        .split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let (user, address) = match hop.split_once('@') {
+                Some((user, address)) => (Some(user.to_owned()), address),
+                None => (None, hop),
+            };
+            let (host, port) = match address.strip_prefix('[') {
+                Some(bracketed) => match bracketed.split_once(']') {
+                    Some((host, rest)) => (host, rest.strip_prefix(':')),
+                    None => (bracketed, None),
+                },
+                None => match address.split_once(':') {
+                    Some((host, port)) if !port.contains(':') => (host, Some(port)),
+                    _ => (address, None),
+                },
+            };
+            JumpHop {
+                user,
+                host: host.to_owned(),
+                port: port.and_then(|port| port.parse().ok()),
+            }
+        })
+        .collect()
+}
+
+/// Opens a connection to `endpoint` at `port` through the established session `jump_session` to a
+/// jump host, like the option ProxyJump of OpenSSH.
+/// `jump_socket` is the socket over which `jump_session` communicates with the jump host.
+///
+/// The returned stream is one end of a connected socket pair, so no other process can take over the connection.
+/// Data is forwarded between a `direct-tcpip` channel of the jump session and the other end of the pair
+/// by a thread. The thread ends when either side closes the connection.
+///
+/// # Errors
+///
+/// - If the jump host refuses to open a channel to the endpoint.
+/// - If the local socket pair could not be created.
+pub(super) fn connect_through_jump_host(
+    jump_session: Session,
+    jump_socket: OwnedFd,
+    endpoint: &str,
+    port: u32,
+) -> AppResult<UnixStream> {
+    let port = u16::try_from(port).with_context(|| format!("Port {} is not valid", port))?;
+    let channel = jump_session
+        .channel_direct_tcpip(endpoint, port, None)
+        .with_context(|| {
+            format!(
+                "Jump host could not open a channel to endpoint {} via port {}",
+                endpoint, port
+            )
+        })?;
+
+    let (local, forwarded) =
+        UnixStream::pair().context("Could not create a local socket pair for the jump host")?;
+    let endpoint = endpoint.to_owned();
+    thread::spawn(
+        move || match forward(&jump_session, &jump_socket, channel, forwarded) {
+            Ok(()) => debug!("Closed forwarding to {} through jump host", endpoint),
+            Err(error) => warn!(
+                "Forwarding to {} through jump host failed: {}",
+                endpoint, error
+            ),
+        },
+    );
+    Ok(local)
+}
+
+/// Copies data between `channel` and `local` until one of them reaches its end.
+///
+/// Data from `local` is read by a separate thread with blocking reads.
+/// This thread waits via a poller until the jump host or this separate thread has new data.
+fn forward(
+    session: &Session,
+    socket: &OwnedFd,
+    mut channel: Channel,
+    mut local: UnixStream,
+) -> io::Result<()> {
+    let poller = Arc::new(Poller::new()?);
+    let from_local = read_in_background(local.try_clone()?, Arc::clone(&poller));
+
+    session.set_blocking(false);
+    poller.add(socket, Event::readable(JUMP_SOCKET_KEY))?;
+    let forwarded = forward_until_closed(
+        session,
+        socket,
+        &poller,
+        &mut channel,
+        &mut local,
+        &from_local,
+    );
+    // Ends the reading thread and signals the end to the SSH session on the other side of the pair.
+    let _ = local.shutdown(Shutdown::Both);
+    poller.delete(socket)?;
+    forwarded
+}
+
+fn forward_until_closed(
+    session: &Session,
+    socket: &OwnedFd,
+    poller: &Poller,
+    channel: &mut Channel,
+    local: &mut UnixStream,
+    from_local: &Receiver<Vec<u8>>,
+) -> io::Result<()> {
+    let mut buffer = [0; FORWARD_BUFFER_SIZE];
+    let mut to_remote = Vec::new();
+    let mut events = Vec::new();
+    loop {
+        loop {
+            match channel.read(&mut buffer) {
+                Ok(0) if channel.eof() => return Ok(()),
+                Ok(0) => break,
+                Ok(read) => local.write_all(&buffer[..read])?,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let local_closed = loop {
+            match from_local.try_recv() {
+                Ok(data) => to_remote.extend_from_slice(&data),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        while !to_remote.is_empty() {
+            match channel.write(&to_remote) {
+                Ok(written) => {
+                    to_remote.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+        if local_closed && to_remote.is_empty() {
+            return Ok(());
+        }
+
+        let outbound = matches!(
+            session.block_directions(),
+            BlockDirections::Outbound | BlockDirections::Both
+        );
+        let interest = Event {
+            key: JUMP_SOCKET_KEY,
+            readable: true,
+            writable: outbound || !to_remote.is_empty(),
+        };
+        poller.modify(socket, interest)?;
+        events.clear();
+        poller.wait(&mut events, None)?;
+    }
+}
+
+/// Reads from `local` on a new thread and hands the data over via the returned receiver.
+/// `poller` is notified after each read and when `local` is closed, which disconnects the receiver.
+fn read_in_background(mut local: UnixStream, poller: Arc<Poller>) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0; FORWARD_BUFFER_SIZE];
+        loop {
+            let read = match local.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+            if sender.send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+            if let Err(error) = poller.notify() {
+                warn!(
+                    "Could not notify the forwarding to the jump host: {}",
+                    error
+                );
+            }
+        }
+        drop(sender);
+        if let Err(error) = poller.notify() {
+            warn!(
+                "Could not notify the forwarding to the jump host: {}",
+                error
+            );
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parses_jump_hosts_with_users_and_ports() {
+        let actual = parse_jump_hops("admin@login.de:2222, bastion, [::1]:22");
+        assert_eq!(
+            vec![
+                JumpHop {
+                    user: Some("admin".to_owned()),
+                    host: "login.de".to_owned(),
+                    port: Some(2222)
+                },
+                JumpHop {
+                    user: None,
+                    host: "bastion".to_owned(),
+                    port: None
+                },
+                JumpHop {
+                    user: None,
+                    host: "::1".to_owned(),
+                    port: Some(22)
+                },
+            ],
+            actual
+        );
+        assert!(parse_jump_hops("none").is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use log::info;
+use ssh2::{CheckResult, HashType, KnownHostFileKind, KnownHostKeyFormat, Session};
+
+use crate::prelude::*;
+
+use super::{fingerprint, SshCredentials};
+
+/// Path of the known hosts file relative to the home directory if none is configured
+const DEFAULT_KNOWN_HOSTS: &str = ".ssh/known_hosts";
+
+/// Host key of a server which is not in the known hosts file yet.
+/// It is returned as error if the key was not trusted, so that frontends can offer to trust it
+/// via [`UnknownHostKey::trust`] and retry.
+#[derive(Debug, Clone)]
+pub struct UnknownHostKey {
+    host: String,
+    key: Vec<u8>,
+    format: KnownHostKeyFormat,
+    fingerprint: String,
+    known_hosts: PathBuf,
+}
+
+impl UnknownHostKey {
+    /// Host as written into the known hosts file, `[host]:port` for other ports than 22
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// SHA256 fingerprint of the key in the same form as OpenSSH shows it
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub fn known_hosts(&self) -> &Path {
+        &self.known_hosts
+    }
+
+    /// Appends the key to the known hosts file so that later connections to the host succeed.
+    /// Existing lines of the file are not touched.
+    /// The file and its directory are created if they do not exist.
+    ///
+    /// # Errors
+    ///
+    /// - If the known hosts file could not be written.
+    pub fn trust(&self) -> AppResult {
+        let line = self.known_hosts_line()?;
+        if let Some(directory) = self.known_hosts.parent() {
+            std::fs::create_dir_all(directory).with_context(|| {
+                format!(
+                    "Could not create directory for known hosts at {:?}",
+                    directory
+                )
+            })?;
+        }
+        let write_error = || format!("Could not write known hosts file at {:?}", self.known_hosts);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.known_hosts)
+            .with_context(write_error)?;
+        let separator = if ends_with_line_break(&mut file).with_context(write_error)? {
+            ""
+        } else {
+            "\n"
+        };
+        writeln!(file, "{}{}", separator, line).with_context(write_error)?;
+        info!(
+            "Added host key of {} with fingerprint {} to {:?}",
+            self.host, self.fingerprint, self.known_hosts
+        );
+        Ok(())
+    }
+
+    /// Returns the entry of the key as line of a known hosts file in the OpenSSH format.
+    fn known_hosts_line(&self) -> AppResult<String> {
+        let session = Session::new().context("Unable to build SSH session")?;
+        let mut known_hosts = session
+            .known_hosts()
+            .context("Unable to initialize known hosts")?;
+        let could_not_add = || format!("Could not add host key of {}", self.host);
+        known_hosts
+            .add(&self.host, &self.key, "added by usermgmt", self.format)
+            .with_context(could_not_add)?;
+        let entry = known_hosts
+            .iter()
+            .with_context(could_not_add)?
+            .into_iter()
+            .next()
+            .with_context(could_not_add)?;
+        let line = known_hosts
+            .write_string(&entry, KnownHostFileKind::OpenSSH)
+            .with_context(could_not_add)?;
+        Ok(line.trim_end().to_owned())
+    }
+}
+
+/// True if `file` is empty or its last character is a line break.
+fn ends_with_line_break(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+impl Display for UnknownHostKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The authenticity of host {} can not be established. Its key with the fingerprint {} is not in the known hosts file {:?}",
+            self.host, self.fingerprint, self.known_hosts
+        )
+    }
+}
+
+impl std::error::Error for UnknownHostKey {}
+
+/// Returns the `configured` known hosts file or `~/.ssh/known_hosts` if none is configured.
+///
+/// # Errors
+///
+/// - If no file is configured and the home directory can not be determined.
+pub fn known_hosts_path(configured: Option<&Path>) -> AppResult<PathBuf> {
+    match configured {
+        Some(path) => Ok(path.to_path_buf()),
+        None => dirs::home_dir()
+            .map(|home| home.join(DEFAULT_KNOWN_HOSTS))
+            .ok_or_else(|| anyhow!("Could not determine the home directory for the known hosts")),
+    }
+}
+
+/// Checks the key of the host of `session` against the known hosts file `known_hosts`.
+/// An unknown key is added to the file if `strict` is false and the user confirms it via `credentials`.
+///
+/// # Errors
+///
+/// - If the key of the host differs from the key in the known hosts file.
+/// - If the key is unknown and was not trusted. The error is an [`UnknownHostKey`] then.
+/// - If the known hosts file could not be read or written.
+pub(super) fn verify_host_key(
+    session: &Session,
+    endpoint: &str,
+    port: u32,
+    known_hosts: &Path,
+    strict: bool,
+    credentials: &impl SshCredentials,
+) -> AppResult {
+    let (key, key_type) = session
+        .host_key()
+        .ok_or_else(|| anyhow!("Host {} did not provide a host key", endpoint))?;
+    let fingerprint = session
+        .host_key_hash(HashType::Sha256)
+        .map(fingerprint::format_sha256)
+        .unwrap_or_default();
+    let port = u16::try_from(port).with_context(|| format!("Port {} is not valid", port))?;
+
+    let checked = read_known_hosts(session, known_hosts)?.check_port(endpoint, port, key);
+    match checked {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => bail!(
+            "Host key of {} has changed and does not match the key in {:?}! \
+            Someone could be eavesdropping on you (man-in-the-middle attack) or the host key was replaced. \
+            Fingerprint of the offered key: {}. Remove the old key from the file if the change is expected.",
+            endpoint,
+            known_hosts,
+            fingerprint
+        ),
+        CheckResult::Failure => bail!(
+            "Could not check the host key of {} against {:?}",
+            endpoint,
+            known_hosts
+        ),
+        CheckResult::NotFound => {
+            let unknown = UnknownHostKey {
+                host: host_entry(endpoint, port),
+                key: key.to_vec(),
+                format: key_type.into(),
+                fingerprint,
+                known_hosts: known_hosts.to_path_buf(),
+            };
+            if !strict && credentials.confirm_unknown_host_key(&unknown)? {
+                unknown.trust()
+            } else {
+                Err(unknown.into())
+            }
+        }
+    }
+}
+
+fn read_known_hosts(session: &Session, path: &Path) -> AppResult<ssh2::KnownHosts> {
+    let mut known_hosts = session
+        .known_hosts()
+        .context("Unable to initialize known hosts")?;
+    if path.exists() {
+        known_hosts
+            .read_file(path, KnownHostFileKind::OpenSSH)
+            .with_context(|| format!("Could not read known hosts file at {:?}", path))?;
+    }
+    Ok(known_hosts)
+}
+
+/// Name of a host within a known hosts file. Other ports than 22 are part of the name.
+fn host_entry(endpoint: &str, port: u16) -> String {
+    if port == 22 {
+        endpoint.to_owned()
+    } else {
+        format!("[{}]:{}", endpoint, port)
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn names_hosts_with_port_only_for_other_ports_than_22() {
+        assert_eq!("head.node", host_entry("head.node", 22));
+        assert_eq!("[head.node]:2222", host_entry("head.node", 2222));
+    }
+
+    #[test]
+    fn appends_trusted_key_without_touching_other_lines() {
+        let directory =
+            std::env::temp_dir().join(format!("usermgmt_known_hosts_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("known_hosts");
+        let existing =
+            "# kept comment\n@cert-authority *.node ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIA== ca";
+        std::fs::write(&path, existing).unwrap();
+
+        let mut key = vec![0, 0, 0, 11];
+        key.extend_from_slice(b"ssh-ed25519");
+        key.extend_from_slice(&[0, 0, 0, 32]);
+        key.extend_from_slice(&[7; 32]);
+        let unknown = UnknownHostKey {
+            host: host_entry("head.node", 2222),
+            key,
+            format: KnownHostKeyFormat::Ed25519,
+            fingerprint: String::new(),
+            known_hosts: path.clone(),
+        };
+        unknown.trust().unwrap();
+
+        let actual = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(directory).unwrap();
+        let (kept, appended) = actual.split_at(existing.len());
+        assert_eq!(existing, kept);
+        insta::assert_snapshot!(appended);
+    }
+}
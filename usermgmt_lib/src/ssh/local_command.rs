@@ -0,0 +1,135 @@
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+
+use super::RemoteOutput;
+
+/// Pause between two checks whether a local command has exited
+const EXIT_POLL_PAUSE: Duration = Duration::from_millis(10);
+
+/// Executes `cmd` via `sh -c` on the local machine like a command over SSH on a remote machine.
+/// `input` is written to the stdin of the command if given.
+/// The command is killed if it does not exit within `timeout`.
+///
+/// # Errors
+///
+/// - If the command could not be started.
+/// - If `input` could not be written or the output could not be read.
+/// - If the command did not exit within `timeout`.
+pub(super) fn run_local_command(
+    cmd: &str,
+    input: Option<&str>,
+    timeout: Duration,
+) -> AppResult<RemoteOutput> {
+    let started = Instant::now();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Could not start command on the local machine")?;
+
+    // Both streams are read by own threads,
+    // so that a command filling one stream does not wait for the other one to be read.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .context("Could not write input to command on the local machine")?;
+    }
+
+    let exit_code = wait_for_exit(&mut child, started, timeout)?;
+    Ok(RemoteOutput {
+        stdout: join_output(stdout).context("Could not read output of local command")?,
+        stderr: join_output(stderr).context("Could not read error output of local command")?,
+        exit_code,
+        duration: started.elapsed(),
+    })
+}
+
+/// Returns the exit code of `child`. A command terminated by a signal yields -1.
+fn wait_for_exit(child: &mut Child, started: Instant, timeout: Duration) -> AppResult<i32> {
+    loop {
+        let status = child
+            .try_wait()
+            .context("Could not wait for command on the local machine")?;
+        if let Some(status) = status {
+            return Ok(status.code().unwrap_or(-1));
+        }
+        if started.elapsed() > timeout {
+            // Best effort to stop the command. The error of the timeout is more relevant.
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!(
+                "Command did not finish within {} seconds",
+                timeout.as_secs()
+            );
+        }
+        thread::sleep(EXIT_POLL_PAUSE);
+    }
+}
+
+fn read_in_background(
+    stream: Option<impl Read + Send + 'static>,
+) -> Option<JoinHandle<io::Result<Vec<u8>>>> {
+    stream.map(|mut stream| {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            stream.read_to_end(&mut buffer).map(|_| buffer)
+        })
+    })
+}
+
+fn join_output(reader: Option<JoinHandle<io::Result<Vec<u8>>>>) -> AppResult<String> {
+    let bytes = match reader {
+        Some(reader) => reader
+            .join()
+            .map_err(|_| anyhow!("Thread reading the output panicked"))??,
+        None => Vec::new(),
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn collects_output_input_and_exit_code_of_local_commands() {
+        let actual = run_local_command(
+            "read line; echo \"got $line\"; echo failed >&2; exit 3",
+            Some("secret\n"),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert_eq!(
+            ("got secret\n", "failed\n", 3),
+            (
+                actual.stdout.as_str(),
+                actual.stderr.as_str(),
+                actual.exit_code
+            )
+        );
+    }
+
+    #[test]
+    fn kills_local_commands_after_timeout() {
+        let actual = run_local_command("sleep 5", None, Duration::from_millis(50));
+
+        assert_eq!(
+            "Command did not finish within 0 seconds",
+            actual.unwrap_err().to_string()
+        );
+    }
+}
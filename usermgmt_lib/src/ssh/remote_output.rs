@@ -0,0 +1,169 @@
+use std::io::{self, ErrorKind, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ssh2::{Channel, Session};
+
+use crate::prelude::*;
+
+/// Pause between two reads if a command has not produced new output
+const OUTPUT_POLL_PAUSE: Duration = Duration::from_millis(10);
+
+/// Outcome of a command executed on a remote machine or on the local machine for a local connection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// Time from the start of the command until its exit
+    pub duration: Duration,
+}
+
+impl RemoteOutput {
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// Returns the output if the command returned with exit code 0.
+    ///
+    /// # Errors
+    ///
+    /// - If the exit code is not 0. The error contains `description` and the stderr of the command.
+    pub fn check_success(self, description: &str) -> AppResult<Self> {
+        if self.is_success() {
+            return Ok(self);
+        }
+        let stderr = self.stderr.trim();
+        if stderr.is_empty() {
+            bail!("{} returned exit code {}", description, self.exit_code);
+        }
+        bail!(
+            "{} returned exit code {} with error output: {}",
+            description,
+            self.exit_code,
+            stderr
+        )
+    }
+}
+
+/// Reads stdout and stderr of the command of `channel` until it exits and returns its output.
+/// The command is aborted if it does not exit within `timeout`.
+///
+/// # Errors
+///
+/// - If the output or exit code could not be read.
+/// - If the command did not exit within `timeout`.
+pub(super) fn read_remote_output(
+    session: &Session,
+    channel: &mut Channel,
+    started: Instant,
+    timeout: Duration,
+) -> AppResult<RemoteOutput> {
+    // Both streams are read in turns without blocking,
+    // so that a command filling one stream does not wait for the other one to be read.
+    session.set_blocking(false);
+    let read = read_both_streams(channel, started, timeout);
+    session.set_blocking(true);
+    let (stdout, stderr) = match read {
+        Ok(output) => output,
+        Err(error) => {
+            // Best effort to stop the command. The error of the timeout is more relevant.
+            let _ = channel.close();
+            return Err(error);
+        }
+    };
+
+    channel
+        .wait_close()
+        .context("Could not close SSH channel of executed command")?;
+    let exit_code = channel
+        .exit_status()
+        .context("Could not retrieve exit code of executed command over SSH")?;
+
+    Ok(RemoteOutput {
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        exit_code,
+        duration: started.elapsed(),
+    })
+}
+
+fn read_both_streams(
+    channel: &mut Channel,
+    started: Instant,
+    timeout: Duration,
+) -> AppResult<(Vec<u8>, Vec<u8>)> {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    loop {
+        let read_stdout = read_available(channel, &mut stdout)
+            .context("Could not read output of executed command over SSH channel")?;
+        let read_stderr = read_available(&mut channel.stderr(), &mut stderr)
+            .context("Could not read error output of executed command over SSH channel")?;
+        if read_stdout || read_stderr {
+            continue;
+        }
+        if channel.eof() {
+            return Ok((stdout, stderr));
+        }
+        if started.elapsed() > timeout {
+            bail!(
+                "Command did not finish within {} seconds",
+                timeout.as_secs()
+            );
+        }
+        thread::sleep(OUTPUT_POLL_PAUSE);
+    }
+}
+
+/// Appends the pending data of `stream` to `buffer`.
+/// Returns true if any data was read.
+fn read_available(stream: &mut impl Read, buffer: &mut Vec<u8>) -> io::Result<bool> {
+    let mut chunk = [0; 4096];
+    match stream.read(&mut chunk) {
+        Ok(read) => {
+            buffer.extend_from_slice(&chunk[..read]);
+            Ok(read > 0)
+        }
+        Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn reports_stderr_only_for_failed_commands() {
+        let output = RemoteOutput {
+            stdout: "partial".to_owned(),
+            stderr: "setquota: Cannot find filesystem /data\n".to_owned(),
+            exit_code: 1,
+            duration: Duration::from_millis(20),
+        };
+        let success = RemoteOutput {
+            exit_code: 0,
+            ..output.clone()
+        };
+        let without_stderr = RemoteOutput {
+            stderr: String::new(),
+            ..output.clone()
+        };
+
+        assert_eq!(
+            success.clone(),
+            success.check_success("Quota setup").unwrap()
+        );
+        assert_eq!(
+            "Quota setup returned exit code 1 with error output: setquota: Cannot find filesystem /data",
+            output.check_success("Quota setup").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Quota setup returned exit code 1",
+            without_stderr
+                .check_success("Quota setup")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
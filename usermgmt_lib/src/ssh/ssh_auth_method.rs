@@ -0,0 +1,31 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// Method by which an SSH connection was authenticated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SshAuthMethod {
+    /// Identity of the SSH agent with its comment and fingerprint
+    Agent {
+        comment: String,
+        fingerprint: String,
+    },
+    /// OpenSSH user certificate at the path together with its private key
+    Certificate(PathBuf),
+    /// Key pair whose private key is at the path
+    KeyFile(PathBuf),
+    Password,
+}
+
+impl Display for SshAuthMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Agent {
+                comment,
+                fingerprint,
+            } => write!(f, "SSH agent key '{}' ({})", comment, fingerprint),
+            Self::Certificate(path) => write!(f, "certificate {:?}", path),
+            Self::KeyFile(path) => write!(f, "key file {:?}", path),
+            Self::Password => write!(f, "password"),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use crate::prelude::*;
+
+/// Path of the OpenSSH client configuration relative to the home directory if none is configured
+const DEFAULT_SSH_CLIENT_CONFIG: &str = ".ssh/config";
+
+/// Settings for one host as given by an OpenSSH client configuration like `~/.ssh/config`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SshHostOptions {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u32>,
+    pub identity_file: Option<PathBuf>,
+    pub proxy_jump: Option<String>,
+}
+
+/// Sections `Host` of an OpenSSH client configuration.
+/// Only the options of [`SshHostOptions`] are considered. Sections `Match` never apply.
+#[derive(Debug, Default)]
+pub struct SshClientConfig {
+    sections: Vec<HostSection>,
+}
+
+#[derive(Debug)]
+struct HostSection {
+    patterns: Vec<String>,
+    options: SshHostOptions,
+}
+
+impl SshClientConfig {
+    /// Reads and parses the OpenSSH client configuration at `path`.
+    ///
+    /// # Errors
+    ///
+    /// - If the file could not be read.
+    /// - If the content is not valid. See [`SshClientConfig::parse`].
+    pub fn load(path: &Path) -> AppResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read SSH client configuration at {:?}", path))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid SSH client configuration at {:?}", path))
+    }
+
+    /// Parses the content of an OpenSSH client configuration.
+    /// Options before the first section `Host` apply to every host.
+    ///
+    /// # Errors
+    ///
+    /// - If a line has a keyword without value.
+    /// - If the value of `Port` is not a number.
+    pub fn parse(content: &str) -> AppResult<Self> {
+        let mut sections = vec![HostSection {
+            patterns: vec!["*".to_owned()],
+            options: SshHostOptions::default(),
+        }];
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, value) = line
+                .split_once(|c: char| c.is_whitespace() || c == '=')
+                .map(|(keyword, value)| (keyword, value.trim_start_matches('=').trim()))
+                .filter(|(_, value)| !value.is_empty())
+                .ok_or_else(|| anyhow!("Line {} has no value: '{}'", index + 1, line))?;
+            let value = unquote(value);
+            let section = sections
+                .last_mut()
+                .expect("There is always the section for every host.");
+            let options = &mut section.options;
+            match keyword.to_lowercase().as_str() {
+                "host" => sections.push(HostSection {
+                    patterns: value.split_whitespace().map(unquote).collect(),
+                    options: SshHostOptions::default(),
+                }),
+                "match" => sections.push(HostSection {
+                    patterns: Vec::new(),
+                    options: SshHostOptions::default(),
+                }),
+                "hostname" => {
+                    options.host_name.get_or_insert(value);
+                }
+                "user" => {
+                    options.user.get_or_insert(value);
+                }
+                "port" => {
+                    let port = value.parse().with_context(|| {
+                        format!("Port '{}' in line {} is not a number", value, index + 1)
+                    })?;
+                    options.port.get_or_insert(port);
+                }
+                "identityfile" => {
+                    options.identity_file.get_or_insert(expand_home(&value));
+                }
+                "proxyjump" => {
+                    options.proxy_jump.get_or_insert(value);
+                }
+                other => debug!("Ignoring option {} of SSH client configuration", other),
+            }
+        }
+        Ok(Self { sections })
+    }
+
+    /// Returns the options for `host` from all matching sections.
+    /// The first value of an option wins like in OpenSSH.
+    /// The token `%h` in `HostName` is replaced by `host`.
+    pub fn options_for(&self, host: &str) -> SshHostOptions {
+        let mut found = SshHostOptions::default();
+        for section in self.sections.iter().filter(|section| section.matches(host)) {
+            let options = section.options.clone();
+            found.host_name = found.host_name.or(options.host_name);
+            found.user = found.user.or(options.user);
+            found.port = found.port.or(options.port);
+            found.identity_file = found.identity_file.or(options.identity_file);
+            found.proxy_jump = found.proxy_jump.or(options.proxy_jump);
+        }
+        found.host_name = found.host_name.map(|name| name.replace("%h", host));
+        found
+    }
+}
+
+impl HostSection {
+    /// A section matches if one pattern matches and no negated pattern like `!login*` matches.
+    fn matches(&self, host: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) if matches_pattern(negated, host) => return false,
+                Some(_) => (),
+                None => matched = matched || matches_pattern(pattern, host),
+            }
+        }
+        matched
+    }
+}
+
+/// Returns the `configured` OpenSSH client configuration or `~/.ssh/config` if none is configured.
+///
+/// # Errors
+///
+/// - If no file is configured and the home directory can not be determined.
+pub fn ssh_client_config_path(configured: Option<&Path>) -> AppResult<PathBuf> {
+    match configured {
+        Some(path) => Ok(path.to_path_buf()),
+        None => dirs::home_dir()
+            .map(|home| home.join(DEFAULT_SSH_CLIENT_CONFIG))
+            .ok_or_else(|| {
+                anyhow!("Could not determine the home directory for the SSH client configuration")
+            }),
+    }
+}
+
+/// Matches `text` against a pattern with the wildcards '*' for any characters and '?' for one character.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match last_star {
+                Some((star, matched_until)) => {
+                    p = star + 1;
+                    t = matched_until + 1;
+                    last_star = Some((star, matched_until + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(relative), Some(home)) => home.join(relative),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn unquote(text: &str) -> String {
+    text.strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(text)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const CONFIG: &str = "\
+# Options for every host
+User admin
+
+Host login
+    HostName login.cluster.de
+    Port 2222
+
+Host ml? !ml9
+    HostName %h.cluster.de
+    ProxyJump login
+    IdentityFile=/keys/nodes
+
+Host *.cluster.de \"nfs*\"
+    User root
+    Port 22
+";
+
+    #[test]
+    fn applies_first_value_of_matching_sections() {
+        let config = SshClientConfig::parse(CONFIG).unwrap();
+
+        let actual: Vec<(&str, SshHostOptions)> = ["login", "ml0", "ml9", "nfs1", "other"]
+            .into_iter()
+            .map(|host| (host, config.options_for(host)))
+            .collect();
+        insta::assert_debug_snapshot!(actual);
+        assert!(SshClientConfig::parse("Host login\nPort ssh\n").is_err());
+    }
+
+    #[test]
+    fn matches_patterns_with_wildcards() {
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("ml?", "ML1"));
+        assert!(matches_pattern("*.cluster.de", "ml0.cluster.de"));
+        assert!(matches_pattern("n*s*", "nfs"));
+        assert!(!matches_pattern("ml?", "ml10"));
+        assert!(!matches_pattern("*.cluster.de", "cluster.de"));
+    }
+}
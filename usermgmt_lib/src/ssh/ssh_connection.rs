@@ -1,26 +1,45 @@
 use crate::prelude::*;
 
-use std::io::Read;
-use std::net::{SocketAddr, TcpStream};
-use std::time::Duration;
+use std::borrow::Cow;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use once_cell::unsync::OnceCell;
 
-use log::{info, warn};
-use ssh2::Session;
+use log::{debug, info, warn};
+use ssh2::{Session, Sftp};
 
 use crate::config::MgmtConfig;
 use crate::prelude::AppResult;
-use crate::ssh::{self, EntitiesAndSshAgent, SshPublicKeySuggestion};
+use crate::ssh::{
+    self, EntitiesAndSshAgent, SshClientConfig, SshHostOptions, SshKeyPair, SshPublicKeySuggestion,
+};
 
 use super::SshCredentials;
+use super::{file_transfer, fingerprint, jump_host, known_hosts, local_command, remote_output};
+use super::{FileOwner, RemoteOutput, SshAuthMethod};
 
 pub struct SshConnection<'a, T> {
-    endpoint: &'a str,
+    endpoint: Cow<'a, str>,
+    /// Host name or IP address to connect to, which differs from the endpoint for an alias of the SSH client configuration
+    address: String,
     port: u32,
+    user: Option<String>,
+    identity: Option<SshKeyPair>,
     ssh_agent: bool,
+    agent_key_fingerprint: Option<String>,
+    known_hosts: Option<PathBuf>,
+    strict_host_key_checking: bool,
+    /// Commands run on the local machine without SSH
+    local: bool,
+    command_timeout: Duration,
     credentials: T,
+    jump_host: Option<Box<SshConnection<'a, T>>>,
     session: OnceCell<Session>,
+    auth_method: OnceCell<SshAuthMethod>,
 }
 
 impl<'a, T> SshConnection<'a, T>
@@ -31,25 +50,108 @@ where
         self.credentials.password()
     }
 
+    /// Returns the user configured for the endpoint in `ssh_hosts` or the SSH client configuration.
+    /// Otherwise the user of the credentials is returned.
     pub fn username(&self) -> AppResult<&str> {
-        self.credentials.username()
+        match &self.user {
+            Some(user) => Ok(user),
+            None => self.credentials.username(),
+        }
+    }
+
+    /// Returns the method by which the connection was authenticated once it is established.
+    pub fn auth_method(&self) -> Option<&SshAuthMethod> {
+        self.auth_method.get()
+    }
+
+    /// Returns the key pair of the credentials or the identity file of the SSH client configuration.
+    pub fn key_pair(&self) -> Option<&SshKeyPair> {
+        self.credentials
+            .ssh_paths_pair_key()
+            .or(self.identity.as_ref())
     }
 
-    pub fn new(endpoint: &'a str, config: &MgmtConfig, credentials: T) -> Self {
+    /// Port and user are taken from the entry of `endpoint` in `ssh_hosts` if present.
+    /// Otherwise they are taken from the SSH client configuration if `use_ssh_config` is set.
+    /// The connection goes through `ssh_jump_host` or the `ProxyJump` of the SSH client configuration.
+    pub fn new(endpoint: &'a str, config: &'a MgmtConfig, credentials: T) -> Self {
+        let options = client_options(endpoint, config);
+        let hops = match (&config.ssh_jump_host, &options.proxy_jump) {
+            (Some(jump_host), _) | (None, Some(jump_host)) => jump_host::parse_jump_hops(jump_host),
+            (None, None) => Vec::new(),
+        };
+        let jump_host =
+            hops.into_iter()
+                .filter(|hop| hop.host != endpoint)
+                .fold(None, |previous, hop| {
+                    let options = client_options(&hop.host, config);
+                    let mut connection = Self::with_options(
+                        Cow::Owned(hop.host),
+                        config,
+                        credentials.clone(),
+                        options,
+                    );
+                    connection.port = hop.port.unwrap_or(connection.port);
+                    connection.user = hop.user.or(connection.user);
+                    connection.jump_host = previous;
+                    Some(Box::new(connection))
+                });
         Self {
-            endpoint,
-            port: config.ssh_port,
-            ssh_agent: config.ssh_agent,
-            credentials,
-            session: OnceCell::new(),
+            jump_host,
+            ..Self::with_options(Cow::Borrowed(endpoint), config, credentials, options)
         }
     }
 
+    /// Endpoint as given in the configuration, for example a host name or an alias
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// True if commands and file transfers happen on the local machine without SSH.
+    /// This is only the case for `local = true` in the entry of the endpoint in `ssh_hosts`.
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
     pub fn from_head_node(config: &'a MgmtConfig, credentials: T) -> Self {
         Self::new(&config.head_node, config, credentials)
     }
 
-    /// Tries to execute a given command on a remote machine over ssh
+    /// Connection without jump host whose values of `ssh_hosts` take precedence over `options`.
+    fn with_options(
+        endpoint: Cow<'a, str>,
+        config: &MgmtConfig,
+        credentials: T,
+        options: SshHostOptions,
+    ) -> Self {
+        let host = config.ssh_hosts.get(endpoint.as_ref());
+        let local = host.and_then(|host| host.local).unwrap_or(false);
+        Self {
+            address: options
+                .host_name
+                .unwrap_or_else(|| endpoint.clone().into_owned()),
+            port: host
+                .and_then(|host| host.port)
+                .or(options.port)
+                .unwrap_or(config.ssh_port),
+            user: host.and_then(|host| host.user.clone()).or(options.user),
+            identity: options.identity_file.map(SshKeyPair::from_one_path),
+            endpoint,
+            ssh_agent: config.ssh_agent,
+            agent_key_fingerprint: config.ssh_agent_key_fingerprint.clone(),
+            known_hosts: config.ssh_known_hosts_path.clone(),
+            strict_host_key_checking: config.ssh_strict_host_key_checking,
+            local,
+            command_timeout: ssh::command_timeout(config),
+            credentials,
+            jump_host: None,
+            session: OnceCell::new(),
+            auth_method: OnceCell::new(),
+        }
+    }
+
+    /// Tries to execute a given command on a remote machine over ssh.
+    /// Returns stdout, stderr and exit code of the command.
     ///
     /// # Error
     ///
@@ -57,201 +159,518 @@ where
     /// - If Authentication failed.
     /// - If remote command could not be executed.
     /// - If output or exit code of executed remote command could not be retrieved.
+    /// - If the command did not finish within `ssh_command_timeout_secs`.
     ///
-    pub fn exec(&self, cmd: &str) -> AppResult<(String, i32)> {
-        let session = self
-            .session
-            .get_or_try_init(|| -> AppResult<Session> { self.establish_connection() })?;
+    /// If the connection is local, the command runs on the local machine instead. See [`SshConnection::is_local`].
+    pub fn exec(&self, cmd: &str) -> AppResult<RemoteOutput> {
+        self.exec_with_input(cmd, None)
+    }
+
+    /// Like [`SshConnection::exec`] but writes `input` to the stdin of the command if given.
+    /// Stdin is closed afterwards, so that the command does not wait for further input.
+    ///
+    /// # Error
+    ///
+    /// - See [`SshConnection::exec`].
+    /// - If `input` could not be written to the stdin of the command.
+    pub fn exec_with_input(&self, cmd: &str, input: Option<&str>) -> AppResult<RemoteOutput> {
+        if self.local {
+            return local_command::run_local_command(cmd, input, self.command_timeout)
+                .with_context(|| format!("Local command '{}' failed", cmd));
+        }
+
+        let session = self.session()?;
 
         let mut channel = session
             .channel_session()
             .context("Unable to create channel for SSH session")?;
 
+        let started = Instant::now();
         channel
             .exec(cmd)
             .context("Execution of command on remote machine over SSH has failed.")?;
 
-        let mut output = String::new();
-        channel
-            .read_to_string(&mut output)
-            .context("Could not read output of executed command over SSH channel")?;
-        let exit_status = channel
-            .exit_status()
-            .context("Could not retrieve exit code of executed command over SSH")?;
+        if let Some(input) = input {
+            channel
+                .write_all(input.as_bytes())
+                .context("Could not write input to executed command over SSH channel")?;
+            channel
+                .send_eof()
+                .context("Could not close input of executed command over SSH channel")?;
+        }
 
-        Ok((output, exit_status))
+        remote_output::read_remote_output(session, &mut channel, started, self.command_timeout)
+            .with_context(|| format!("Command '{}' on {} failed", cmd, self.endpoint))
+    }
+
+    /// Copies the local file at `local` to the path `remote` on the remote machine via SFTP.
+    /// The file is written to a temporary file next to `remote` first, gets the permissions `mode`
+    /// or [`ssh::DEFAULT_UPLOAD_MODE`] and the `owner` if given and is renamed to `remote` at last.
+    /// Thus an existing file at `remote` is replaced but never seen partially written.
+    /// Setting the owner usually requires root as SSH user.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection could not be established. See [`SshConnection::establish_connection`].
+    /// - If the local file could not be read or the remote file could not be written.
+    /// - If mode or owner could not be set.
+    pub fn upload(
+        &self,
+        local: &Path,
+        remote: &Path,
+        mode: Option<u32>,
+        owner: Option<FileOwner>,
+    ) -> AppResult {
+        if self.local {
+            return file_transfer::copy_locally(local, remote, mode, owner);
+        }
+        file_transfer::upload_via_sftp(&self.sftp()?, local, remote, mode, owner)?;
+        info!("Uploaded {:?} to {:?} on {}", local, remote, self.endpoint);
+        Ok(())
+    }
+
+    /// Copies the file at the path `remote` on the remote machine to the local path `local` via SFTP.
+    /// The file is written to a temporary file next to `local` first and renamed to `local` at last.
+    /// Thus an existing file at `local` is replaced but never seen partially written.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection could not be established. See [`SshConnection::establish_connection`].
+    /// - If the remote file could not be read or the local file could not be written.
+    pub fn download(&self, remote: &Path, local: &Path) -> AppResult {
+        if self.local {
+            return file_transfer::copy_locally(remote, local, None, None);
+        }
+        file_transfer::download_via_sftp(&self.sftp()?, remote, local)?;
+        info!(
+            "Downloaded {:?} from {} to {:?}",
+            remote, self.endpoint, local
+        );
+        Ok(())
+    }
+
+    /// Deletes the file at the path `remote` on the remote machine via SFTP.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection could not be established. See [`SshConnection::establish_connection`].
+    /// - If the remote file could not be deleted.
+    pub fn remove_file(&self, remote: &Path) -> AppResult {
+        if self.local {
+            return std::fs::remove_file(remote)
+                .with_context(|| format!("Could not delete local file at {:?}", remote));
+        }
+        self.sftp()?
+            .unlink(remote)
+            .with_context(|| format!("Could not delete remote file at {:?}", remote))
+    }
+
+    /// Establishes the session now instead of on first use, so that failures to connect or
+    /// authenticate show up before any changes are made. Does nothing for a local connection.
+    ///
+    /// # Errors
+    ///
+    /// - If the connection could not be established. See [`SshConnection::establish_connection`].
+    pub fn ensure_connected(&self) -> AppResult {
+        if !self.local {
+            self.session()?;
+        }
+        Ok(())
+    }
+
+    fn sftp(&self) -> AppResult<Sftp> {
+        self.session()?
+            .sftp()
+            .context("Unable to start SFTP over SSH session")
+    }
+
+    /// Returns the session which is established on first use.
+    fn session(&self) -> AppResult<&Session> {
+        self.session
+            .get_or_try_init(|| -> AppResult<Session> { self.establish_connection() })
     }
 
     pub fn establish_connection(&self) -> AppResult<Session> {
-        info!("Connecting to host {}", self.endpoint);
+        self.establish_connection_with_socket()
+            .map(|(session, _)| session)
+    }
+
+    /// Same as [`SshConnection::establish_connection`] but also returns the socket of the session.
+    /// A jump host needs it to wait for data of its session. See [`jump_host::connect_through_jump_host`].
+    fn establish_connection_with_socket(&self) -> AppResult<(Session, OwnedFd)> {
+        info!("Connecting to host {} at {}", self.endpoint, self.address);
 
         let mut sess = Session::new().context("Unable to build SSH session")?;
         let timeout = constants::SSH_TIME_OUT_MILL_SECS;
-        let socket_addr: String = format!("{}:{}", self.endpoint, self.port);
-        let socket_addr: SocketAddr = socket_addr
-            .parse()
-            .with_context(|| format!("Socket address is not valid: {}", socket_addr))?;
         sess.set_timeout(timeout);
-
-        {
-            let tcp =
-                TcpStream::connect_timeout(&socket_addr, Duration::from_millis(timeout as u64))
+        let socket = match &self.jump_host {
+            Some(jump_host) => {
+                info!(
+                    "Connecting to host {} through jump host {}",
+                    self.address, jump_host.endpoint
+                );
+                let (jump_session, jump_socket) = jump_host
+                    .establish_connection_with_socket()
                     .with_context(|| {
-                        format!(
-                            "Unable to connect over tcp to endpoint {} via port {}",
-                            self.endpoint, self.port
-                        )
+                        format!("Could not connect to jump host {}", jump_host.endpoint)
                     })?;
-            sess.set_tcp_stream(tcp);
-        }
+                OwnedFd::from(jump_host::connect_through_jump_host(
+                    jump_session,
+                    jump_socket,
+                    &self.address,
+                    self.port,
+                )?)
+            }
+            None => OwnedFd::from(connect_to_any_address(
+                &self.address,
+                self.port,
+                Duration::from_millis(timeout as u64),
+            )?),
+        };
+        sess.set_tcp_stream(
+            socket
+                .try_clone()
+                .context("Could not duplicate the socket of the SSH session")?,
+        );
 
         sess.handshake()
             .context("Unable to perform SSH handshake")?;
 
-        auth(self, &mut sess, &self.credentials)?;
+        let known_hosts = ssh::known_hosts_path(self.known_hosts.as_deref())?;
+        known_hosts::verify_host_key(
+            &sess,
+            &self.address,
+            self.port,
+            &known_hosts,
+            self.strict_host_key_checking,
+            &self.credentials,
+        )?;
 
-        return Ok(sess);
+        let method = authenticate(self, &mut sess)?;
+        info!(
+            "Authenticated on host {} as {} via {}",
+            self.endpoint,
+            self.username()?,
+            method
+        );
+        let _ = self.auth_method.set(method);
 
-        fn simple_password_auth<T>(
-            session: &mut Session,
-            session_connection: &SshConnection<T>,
-            username: &str,
-        ) -> AppResult
-        where
-            T: SshCredentials,
-        {
-            let password = session_connection.password()?;
-            session
-                .userauth_password(username, password)
-                .context("Authentication has failed with provided username/password.")?;
-            Ok(())
+        Ok((sess, socket))
+    }
+}
+
+/// Conducts the authentication on the session and returns the method which succeeded.
+/// The methods are tried in this order until one succeeds:
+///
+/// - SSH agent if `ssh_agent` is set in the configuration
+/// - OpenSSH user certificate together with its private key if there is one
+/// - Key pair from the credentials or the SSH client configuration
+/// - Username and password
+///
+/// The passphrase of an encrypted private key is asked for separately from the password.
+/// See [`SshCredentials::key_passphrase`].
+///
+/// # Errors
+///
+/// If none of the authentication methods succeeded. The error lists the failure of every method.
+fn authenticate<T>(connection: &SshConnection<T>, session: &mut Session) -> AppResult<SshAuthMethod>
+where
+    T: SshCredentials,
+{
+    let username = connection.username()?;
+    let mut failures = Vec::new();
+
+    if connection.ssh_agent {
+        match try_authenticate_via_ssh_agent(session, connection, username) {
+            Ok(method) => return Ok(method),
+            Err(error) => {
+                warn!(
+                    "Authentication via SSH agent failed with username {}: {:#}",
+                    username, error
+                );
+                failures.push(format!("SSH agent: {:#}", error));
+            }
         }
+    }
 
-        fn direct_key_path_auth<T>(
-            session: &mut Session,
-            session_connection: &SshConnection<T>,
-            credentials: &impl SshCredentials,
-        ) -> AppResult
-        where
-            T: SshCredentials,
+    if let Some(pair) = connection.key_pair() {
+        if let Some(method) =
+            try_authenticate_via_key_pair(session, connection, username, pair, &mut failures)?
         {
-            info!("Trying to authenticate over SSH by using key pair");
-            let username = credentials.username()?;
-            let password = session_connection.password()?;
-            let pair = credentials
-                .ssh_paths_pair_key()
-                .ok_or_else(|| anyhow!("No key pair provided"))?;
-            let (public, private) = (pair.pub_key(), pair.private_key());
-            info!(
-                "SSH key pair at ({:?}) and ({:?}) is used for authentication",
-                public, private
+            return Ok(method);
+        }
+    }
+
+    let password = connection.password()?;
+    match session.userauth_password(username, password) {
+        Ok(()) => Ok(SshAuthMethod::Password),
+        Err(error) => {
+            warn!(
+                "Authentication via password failed with username {}: {}",
+                username, error
             );
-            session.userauth_pubkey_file(username, Some(public), private, Some(password))?;
-            Ok(())
+            failures.push(format!("Password: {}", error));
+            Err(every_method_failed(username, &failures))
         }
+    }
+}
 
-        /// Conducts the authentication on the session.
-        /// It tries to first authenticate via ssh agent if allowed in the configuration.
-        /// After a failed ssh agent authentication or without it,
-        /// a simple username and password authentication is done as an alternative
-        ///
-        /// # Errors
-        ///
-        /// If none of authentication methods succeeded, ssh agent and password
-        /// authentication.
-        ///
-        fn auth<T>(
-            connection: &SshConnection<T>,
-            session: &mut Session,
-            cred: &impl SshCredentials,
-        ) -> AppResult
-        where
-            T: SshCredentials,
-        {
-            let username = cred.username()?;
-            if connection.ssh_agent {
-                match try_authenticate_via_ssh_agent(session, &connection.credentials, username) {
-                    Ok(_) => {
-                        info!(
-                            "Authentication via SSH agent succeeded with username {}",
-                            username
-                        );
-                    }
-                    Err(agent_error) => {
-                        warn!(
-                            "Authentication via SSH agent failed with username ({}).
-                                \n Details: {}",
-                            username, agent_error
-                        );
-                        pub_key_file_or_simple_auth(connection, session, cred)?;
-                    }
-                }
-            } else {
-                pub_key_file_or_simple_auth(connection, session, cred)?;
+/// Tries the certificate of `pair` if there is one and then the key file of `pair`.
+/// Returns the method which succeeded or `None` after adding every failure to `failures`.
+///
+/// # Errors
+///
+/// - If the passphrase of an encrypted private key could not be retrieved.
+fn try_authenticate_via_key_pair<T>(
+    session: &mut Session,
+    connection: &SshConnection<T>,
+    username: &str,
+    pair: &SshKeyPair,
+    failures: &mut Vec<String>,
+) -> AppResult<Option<SshAuthMethod>>
+where
+    T: SshCredentials,
+{
+    let private = pair.private_key();
+    let passphrase = if pair.is_private_key_encrypted() {
+        connection.credentials.key_passphrase(private)?
+    } else {
+        None
+    };
+    if let Some(certificate) = pair.certificate() {
+        info!(
+            "Trying to authenticate over SSH by using certificate {:?} with key {:?}",
+            certificate, private
+        );
+        match session.userauth_pubkey_file(username, Some(certificate), private, passphrase) {
+            Ok(()) => return Ok(Some(SshAuthMethod::Certificate(certificate.to_path_buf()))),
+            Err(error) => {
+                warn!(
+                    "Authentication via certificate {:?} failed: {}",
+                    certificate, error
+                );
+                failures.push(format!("Certificate {:?}: {}", certificate, error));
             }
+        }
+    }
 
-            return Ok(());
-
-            fn pub_key_file_or_simple_auth<T>(
-                connection: &SshConnection<T>,
-                session: &mut Session,
-                cred: &impl SshCredentials,
-            ) -> AppResult
-            where
-                T: SshCredentials,
-            {
-                let username = cred.username()?;
-                if let Err(error) = direct_key_path_auth(session, connection, cred) {
-                    warn!(
-                        "Could not connect over SSH via key file's path\n Details: {}",
-                        error
-                    );
-                    simple_password_auth(session, connection, username)?;
-                }
-                Ok(())
-            }
+    let public = pair.pub_key();
+    info!(
+        "Trying to authenticate over SSH by using key pair at ({:?}) and ({:?})",
+        public, private
+    );
+    match session.userauth_pubkey_file(username, Some(public), private, passphrase) {
+        Ok(()) => Ok(Some(SshAuthMethod::KeyFile(private.to_path_buf()))),
+        Err(error) => {
+            warn!(
+                "Authentication via key file {:?} failed: {}",
+                private, error
+            );
+            failures.push(format!("Key file {:?}: {}", private, error));
+            Ok(None)
         }
     }
 }
 
+fn every_method_failed(username: &str, failures: &[String]) -> AppError {
+    anyhow!(
+        "Authentication has failed with username {} for every method:\n{}",
+        username,
+        failures.join("\n")
+    )
+}
+
 /// Tries to authenticate a user via an active SSH agent.
-/// If more than one key is registered in the SSH agent, user is asked which one to use via prompt in the
-/// terminal.
+/// The key with the fingerprint `ssh_agent_key_fingerprint` of the configuration is used if given.
+/// Otherwise the only key is used or, if more than one key is registered in the SSH agent,
+/// the credentials are asked which one to use, see [`SshCredentials::auth_agent_resolve`].
 ///
 /// # Errors
 ///
 /// - If no SSH agent is accessible.
 /// - If no key is registered within SSH agent
+/// - If no key in the SSH agent has the configured fingerprint.
 /// - If the selection from user is not within the available range of SSH keys registered within
 ///  SSH agent.
-fn try_authenticate_via_ssh_agent(
+fn try_authenticate_via_ssh_agent<T>(
     session: &mut Session,
-    credentials: &impl SshCredentials,
+    connection: &SshConnection<T>,
     username: &str,
-) -> AppResult<()> {
+) -> AppResult<SshAuthMethod>
+where
+    T: SshCredentials,
+{
     let keys = ssh::get_agent_with_all_entities(session)?;
 
-    let (agent, chosen_key) = match keys {
+    let (agent, keys) = match keys {
         EntitiesAndSshAgent::None => bail!("No keys found in SSH agent session."),
-        EntitiesAndSshAgent::One(agent, only_key) => (agent, only_key),
-        EntitiesAndSshAgent::Many(agent, to_choose_from) => {
-            let choice: Vec<SshPublicKeySuggestion> = to_choose_from
-                .iter()
-                .map(SshPublicKeySuggestion::from)
-                .collect();
-
-            let user_choice = credentials.auth_agent_resolve(choice)?;
-            (
-                agent,
-                to_choose_from.into_iter().nth(user_choice).expect(
-                    "Function for retrieving entities guarantees that we have elements at this point.",
-                ),
-            )
-        }
+        EntitiesAndSshAgent::One(agent, only_key) => (agent, vec![only_key]),
+        EntitiesAndSshAgent::Many(agent, keys) => (agent, keys),
     };
+    let suggestions: Vec<SshPublicKeySuggestion> =
+        keys.iter().map(SshPublicKeySuggestion::from).collect();
+
+    let chosen = match (&connection.agent_key_fingerprint, suggestions.len()) {
+        (Some(expected), _) => suggestions
+            .iter()
+            .position(|key| fingerprint::matches_fingerprint(key.fingerprint(), expected))
+            .ok_or_else(|| {
+                let available: Vec<&str> =
+                    suggestions.iter().map(|key| key.fingerprint()).collect();
+                anyhow!(
+                    "No key with fingerprint {} in SSH agent. Available keys: {}",
+                    expected,
+                    available.join(", ")
+                )
+            })?,
+        (None, 1) => 0,
+        (None, _) => connection
+            .credentials
+            .auth_agent_resolve(suggestions.clone())?,
+    };
+    let (chosen_key, suggestion) = keys
+        .get(chosen)
+        .zip(suggestions.get(chosen))
+        .ok_or_else(|| anyhow!("Chosen key {} is not in the SSH agent", chosen))?;
+
+    info!(
+        "Using SSH key '{}' ({}) from SSH agent",
+        suggestion.comment(),
+        suggestion.fingerprint()
+    );
 
-    info!("Using SSH key '{}' from SSH agent", chosen_key.comment());
+    agent.userauth(username, chosen_key)?;
+    Ok(SshAuthMethod::Agent {
+        comment: suggestion.comment().to_owned(),
+        fingerprint: suggestion.fingerprint().to_owned(),
+    })
+}
+
+/// Returns the options of the SSH client configuration for `endpoint` if `use_ssh_config` is set.
+/// A configuration which could not be loaded is ignored with a warning.
+fn client_options(endpoint: &str, config: &MgmtConfig) -> SshHostOptions {
+    if !config.use_ssh_config {
+        return SshHostOptions::default();
+    }
+    let loaded = ssh::ssh_client_config_path(config.ssh_config_path.as_deref())
+        .and_then(|path| SshClientConfig::load(&path));
+    match loaded {
+        Ok(client_config) => client_config.options_for(endpoint),
+        Err(error) => {
+            warn!("Ignoring SSH client configuration: {:#}", error);
+            SshHostOptions::default()
+        }
+    }
+}
+
+/// Resolves `endpoint`, a host name or IP address, to all of its IPv4 and IPv6 addresses.
+///
+/// # Errors
+///
+/// - If `port` is not a valid port or the host name could not be resolved.
+fn resolve_socket_addresses(endpoint: &str, port: u32) -> AppResult<Vec<SocketAddr>> {
+    let port = u16::try_from(port).with_context(|| format!("Port {} is not valid", port))?;
+    let addresses: Vec<SocketAddr> = (endpoint, port)
+        .to_socket_addrs()
+        .with_context(|| format!("Could not resolve host {}", endpoint))?
+        .collect();
+    if addresses.is_empty() {
+        bail!("Host {} resolved to no address", endpoint);
+    }
+    Ok(addresses)
+}
 
-    agent.userauth(username, &chosen_key)?;
-    Ok(())
+/// Connects over tcp to the first reachable address of `endpoint`.
+/// Every address is tried with the given `timeout`.
+///
+/// # Errors
+///
+/// - If the endpoint could not be resolved. See [`resolve_socket_addresses`].
+/// - If no address of the endpoint is reachable.
+fn connect_to_any_address(endpoint: &str, port: u32, timeout: Duration) -> AppResult<TcpStream> {
+    let mut failures = Vec::new();
+    for address in resolve_socket_addresses(endpoint, port)? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(tcp) => {
+                debug!("Connected to {} via address {}", endpoint, address);
+                return Ok(tcp);
+            }
+            Err(error) => {
+                debug!(
+                    "Could not connect to {} via {}: {}",
+                    endpoint, address, error
+                );
+                failures.push(format!("{}: {}", address, error));
+            }
+        }
+    }
+    bail!(
+        "Unable to connect over tcp to endpoint {} via port {}. Tried addresses:\n{}",
+        endpoint,
+        port,
+        failures.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::config::SshHostConfig;
+    use crate::ssh::SshGivenCredential;
+
+    #[test]
+    fn resolves_ipv4_and_ipv6_addresses_and_rejects_invalid_ports() {
+        let actual = resolve_socket_addresses("127.0.0.1", 2222).unwrap();
+        assert_eq!(vec![SocketAddr::from(([127, 0, 0, 1], 2222))], actual);
+        let actual = resolve_socket_addresses("::1", 22).unwrap();
+        assert_eq!(
+            vec![SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 22))],
+            actual
+        );
+        assert!(resolve_socket_addresses("127.0.0.1", 70000).is_err());
+    }
+
+    #[test]
+    fn runs_locally_only_on_hosts_marked_as_local() {
+        let config = MgmtConfig {
+            ssh_hosts: [
+                (
+                    "head.node".to_owned(),
+                    SshHostConfig {
+                        local: Some(true),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "127.0.0.1".to_owned(),
+                    SshHostConfig {
+                        local: Some(false),
+                        ..Default::default()
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let actual: Vec<(&str, bool)> = ["localhost", "::1", "head.node", "127.0.0.1", "node1"]
+            .into_iter()
+            .map(|endpoint| {
+                let connection =
+                    SshConnection::new(endpoint, &config, SshGivenCredential::default());
+                (endpoint, connection.is_local())
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("localhost", false),
+                ("::1", false),
+                ("head.node", true),
+                ("127.0.0.1", false),
+                ("node1", false),
+            ],
+            actual
+        );
+    }
 }
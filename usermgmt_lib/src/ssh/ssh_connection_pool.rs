@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use log::debug;
+
+use crate::config::MgmtConfig;
+use crate::prelude::*;
+
+use super::{SshConnection, SshCredentials};
+
+/// Connections keyed by endpoint which are established on first use and reused afterwards.
+/// A pool is meant to be shared across an operation or a batch of operations,
+/// so that every host is only connected to and authenticated at once.
+pub struct SshConnectionPool<'a, C> {
+    config: &'a MgmtConfig,
+    credentials: C,
+    max_parallel: usize,
+    connections: Mutex<HashMap<&'a str, Arc<Mutex<SshConnection<'a, C>>>>>,
+}
+
+impl<'a, C> SshConnectionPool<'a, C>
+where
+    C: SshCredentials,
+{
+    /// Connections use the SSH settings of `config`.
+    /// At most `ssh_max_parallel` of them are used at the same time by [`SshConnectionPool::run_on_each`].
+    pub fn new(config: &'a MgmtConfig, credentials: C) -> Self {
+        let max_parallel = config
+            .ssh_max_parallel
+            .unwrap_or(constants::DEFAULT_SSH_MAX_PARALLEL)
+            .max(1) as usize;
+        Self {
+            config,
+            credentials,
+            max_parallel,
+            connections: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &'a MgmtConfig {
+        self.config
+    }
+
+    /// Performs `action` with the connection to `endpoint`.
+    /// The connection is created on first use. Only one action uses a connection at the same time.
+    pub fn with_connection<R>(
+        &self,
+        endpoint: &'a str,
+        action: impl FnOnce(&SshConnection<'a, C>) -> R,
+    ) -> R {
+        let connection = lock(&self.connections)
+            .entry(endpoint)
+            .or_insert_with(|| {
+                debug!("Adding connection to {} to the pool", endpoint);
+                let connection =
+                    SshConnection::new(endpoint, self.config, self.credentials.clone());
+                Arc::new(Mutex::new(connection))
+            })
+            .clone();
+        let connection = lock(&connection);
+        action(&connection)
+    }
+
+    /// Performs `action` for every task with the connection to the endpoint of the task.
+    /// At most `ssh_max_parallel` tasks are performed at the same time.
+    /// Returns the outcome of every task together with its endpoint in the order of `tasks`.
+    pub fn run_on_each<T, R>(
+        &self,
+        tasks: Vec<(&'a str, T)>,
+        action: impl Fn(&SshConnection<'a, C>, T) -> AppResult<R> + Sync,
+    ) -> Vec<(&'a str, AppResult<R>)>
+    where
+        T: Send,
+        R: Send,
+    {
+        let workers = self.max_parallel.min(tasks.len());
+        let queue = Mutex::new(tasks.into_iter().enumerate());
+        let outcomes = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let next = lock(&queue).next();
+                    let Some((index, (endpoint, task))) = next else {
+                        break;
+                    };
+                    let outcome =
+                        self.with_connection(endpoint, |connection| action(connection, task));
+                    lock(&outcomes).push((index, endpoint, outcome));
+                });
+            }
+        });
+
+        let mut outcomes = outcomes
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        outcomes.sort_by_key(|(index, _, _)| *index);
+        outcomes
+            .into_iter()
+            .map(|(_, endpoint, outcome)| (endpoint, outcome))
+            .collect()
+    }
+}
+
+/// A panic within an action does not leave connections or outcomes in an invalid state.
+/// Hence a poisoned lock is used anyway.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::ssh::SshGivenCredential;
+
+    #[test]
+    fn runs_tasks_in_parallel_with_reused_connections_and_keeps_their_order() {
+        let config = MgmtConfig {
+            ssh_max_parallel: Some(3),
+            ..Default::default()
+        };
+        let pool = SshConnectionPool::new(&config, SshGivenCredential::default());
+        let tasks: Vec<(&str, usize)> = ["a", "b", "a", "c", "b", "d"]
+            .into_iter()
+            .zip(0..)
+            .collect();
+
+        let actual = pool.run_on_each(tasks, |_, task| {
+            if task == 4 {
+                bail!("Task {} failed", task);
+            }
+            Ok(task * 10)
+        });
+
+        let actual: Vec<(&str, Result<usize, String>)> = actual
+            .into_iter()
+            .map(|(endpoint, outcome)| (endpoint, outcome.map_err(|error| error.to_string())))
+            .collect();
+        assert_eq!(
+            vec![
+                ("a", Ok(0)),
+                ("b", Ok(10)),
+                ("a", Ok(20)),
+                ("c", Ok(30)),
+                ("b", Err("Task 4 failed".to_owned())),
+                ("d", Ok(50)),
+            ],
+            actual
+        );
+        assert_eq!(4, lock(&pool.connections).len());
+    }
+}
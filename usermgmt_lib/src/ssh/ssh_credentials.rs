@@ -1,16 +1,32 @@
+use std::path::Path;
+
 use crate::prelude::*;
 
-use super::{SshKeyPair, SshPublicKeySuggestion};
+use super::{SshKeyPair, SshPublicKeySuggestion, UnknownHostKey};
 
 /// Several functions in this trait return a result
 /// to allow for implementer to propagate error in their environment.
 /// Example: an implementer for a CLI-App deals with errors and user input within a terminal.
-pub trait SshCredentials: Clone {
+///
+/// Credentials are cloned for every connection and used from several threads at once,
+/// see [`super::SshConnectionPool`]. Clones should therefore share values which the user entered.
+pub trait SshCredentials: Clone + Send + Sync {
     fn username(&self) -> AppResult<&str>;
     fn password(&self) -> AppResult<&str>;
     fn ssh_paths_pair_key(&self) -> Option<&SshKeyPair>;
+    /// Returns the passphrase of the encrypted private key at `_private_key`.
+    /// It is only asked for if the private key is protected by a passphrase.
+    /// By default no passphrase is known.
+    fn key_passphrase(&self, _private_key: &Path) -> AppResult<Option<&str>> {
+        Ok(None)
+    }
     /// Determines which key from the parameter `_many_keys` to use for a ssh connection.
     fn auth_agent_resolve(&self, _many_keys: Vec<SshPublicKeySuggestion>) -> AppResult<usize> {
         Err(anyhow!("No resolving for several keys implemented"))
     }
+    /// Decides whether the key of a host, which is not in the known hosts file yet, is trusted.
+    /// A trusted key is added to the known hosts file. By default no unknown key is trusted.
+    fn confirm_unknown_host_key(&self, _unknown: &UnknownHostKey) -> AppResult<bool> {
+        Ok(false)
+    }
 }
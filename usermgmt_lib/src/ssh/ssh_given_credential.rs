@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use super::{SshCredentials, SshKeyPair};
@@ -7,6 +8,7 @@ pub struct SshGivenCredential {
     username: Arc<str>,
     password: Arc<str>,
     ssh_key_pair: Option<Arc<SshKeyPair>>,
+    key_passphrase: Option<Arc<str>>,
 }
 
 impl Default for SshGivenCredential {
@@ -23,8 +25,15 @@ impl SshGivenCredential {
             username,
             password,
             ssh_key_pair,
+            key_passphrase: None,
         }
     }
+
+    /// Uses `passphrase` for an encrypted private key of the key pair if given.
+    pub fn with_key_passphrase(mut self, passphrase: Option<&str>) -> Self {
+        self.key_passphrase = passphrase.map(Arc::from);
+        self
+    }
 }
 
 impl SshCredentials for SshGivenCredential {
@@ -39,4 +48,8 @@ impl SshCredentials for SshGivenCredential {
     fn ssh_paths_pair_key(&self) -> Option<&SshKeyPair> {
         self.ssh_key_pair.as_deref()
     }
+
+    fn key_passphrase(&self, _private_key: &Path) -> crate::prelude::AppResult<Option<&str>> {
+        Ok(self.key_passphrase.as_deref())
+    }
 }
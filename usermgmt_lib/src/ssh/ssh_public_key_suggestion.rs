@@ -5,22 +5,29 @@ use ssh2::PublicKey;
 #[derive(Debug, Clone)]
 pub struct SshPublicKeySuggestion {
     comment: Arc<str>,
+    fingerprint: Arc<str>,
 }
 
 impl From<&PublicKey> for SshPublicKeySuggestion {
     fn from(value: &PublicKey) -> Self {
-        Self::new(value.comment())
+        Self::new(value.comment(), &super::sha256_fingerprint(value.blob()))
     }
 }
 
 impl SshPublicKeySuggestion {
-    pub fn new(comments: &str) -> Self {
+    pub fn new(comments: &str, fingerprint: &str) -> Self {
         Self {
             comment: comments.into(),
+            fingerprint: fingerprint.into(),
         }
     }
 
     pub fn comment(&self) -> &str {
         &self.comment
     }
+
+    /// Fingerprint like `SHA256:...` as `ssh-add -l` shows it
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
 }
@@ -28,6 +28,18 @@ impl ResultAccumulator {
     pub fn add_err(&mut self, err_msg: String) {
         self.errs.push(err_msg)
     }
+
+    /// Collects the error of the parameter "result" prefixed by the parameter "origin",
+    /// for example the host on which it occurred. Returns the value of an Ok variant.
+    pub fn add_if_err<T>(&mut self, origin: &str, result: AppResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.errs.push(format!("{}: {:#}", origin, error));
+                None
+            }
+        }
+    }
 }
 
 impl From<ResultAccumulator> for AppResult {
@@ -63,4 +75,15 @@ mod testing {
         let result = AppResult::from(accumulator);
         insta::assert_debug_snapshot!(result.err().unwrap());
     }
+
+    #[test]
+    fn collect_errors_of_results_with_origin() {
+        let mut accumulator = ResultAccumulator::new("Failed on some nodes".to_owned());
+        assert_eq!(Some(1), accumulator.add_if_err("node1", Ok(1)));
+        assert_eq!(
+            None,
+            accumulator.add_if_err::<()>("node2", Err(anyhow!("failed")))
+        );
+        assert_eq!(vec!["node2: failed".to_owned()], accumulator.errs);
+    }
 }